@@ -0,0 +1,260 @@
+use crate::LauncherItem;
+
+const MAX_RESULTS: usize = 50;
+const WORD_START_BONUS: i32 = 8;
+const CONSECUTIVE_BONUS: i32 = 5;
+const PREFIX_BONUS: i32 = 15;
+const MAX_GAP_PENALTY: usize = 3;
+
+const NAME_WEIGHT: i32 = 4;
+const GENERIC_NAME_WEIGHT: i32 = 2;
+const KEYWORD_WEIGHT: i32 = 2;
+const CATEGORY_WEIGHT: i32 = 1;
+const MAX_FRECENCY_BONUS: i32 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchResult {
+    pub item: usize,
+    pub action: Option<usize>,
+}
+
+// Scores `query` as a subsequence of `text`, rewarding matches on word starts
+// and runs of consecutive characters.
+pub fn score(query: &str, text: &str) -> Option<i32> {
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&i| text[i] == c)?;
+
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        match previous {
+            Some(previous) if previous + 1 == found => score += CONSECUTIVE_BONUS,
+            Some(previous) => score -= (found - previous - 1).min(MAX_GAP_PENALTY) as i32,
+            None => score -= found.min(MAX_GAP_PENALTY) as i32,
+        }
+
+        previous = Some(found);
+        position = found + 1;
+    }
+
+    // An empty query matches nothing.
+    previous?;
+    if text
+        .iter()
+        .collect::<String>()
+        .starts_with(&query.to_lowercase())
+    {
+        score += PREFIX_BONUS;
+    }
+    Some(score)
+}
+
+fn item_score(query: &str, item: &LauncherItem) -> Option<i32> {
+    let weighted = |text: &str, weight: i32| score(query, text).map(|score| score * weight);
+
+    [
+        weighted(&item.name, NAME_WEIGHT),
+        item.generic_name
+            .as_deref()
+            .and_then(|name| weighted(name, GENERIC_NAME_WEIGHT)),
+    ]
+    .into_iter()
+    .chain(
+        item.keywords
+            .iter()
+            .map(|keyword| weighted(keyword, KEYWORD_WEIGHT)),
+    )
+    .chain(
+        item.categories
+            .iter()
+            .map(|category| weighted(category, CATEGORY_WEIGHT)),
+    )
+    .flatten()
+    .max()
+    .map(|score| score + frecency_bonus(item))
+}
+
+// Frequently used apps win close matches without burying better ones.
+fn frecency_bonus(item: &LauncherItem) -> i32 {
+    (item.frecency.max(0.0).sqrt() as i32).min(MAX_FRECENCY_BONUS)
+}
+
+// Apps ranked by their best matching field, each followed by the desktop
+// actions that match the query. The top result lists all of its actions.
+// Without a query, apps are listed by frecency and then by name.
+pub fn search(items: &[LauncherItem], query: &str) -> Vec<SearchResult> {
+    let query = query.trim();
+    if query.is_empty() {
+        let mut order = (0..items.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| {
+            items[b]
+                .frecency
+                .total_cmp(&items[a].frecency)
+                .then_with(|| {
+                    items[a]
+                        .name
+                        .to_lowercase()
+                        .cmp(&items[b].name.to_lowercase())
+                })
+        });
+        return order
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|item| SearchResult { item, action: None })
+            .collect();
+    }
+
+    let mut ranked = items
+        .iter()
+        .enumerate()
+        .filter_map(|(i, item)| {
+            let actions = item
+                .actions
+                .iter()
+                .enumerate()
+                .filter_map(|(a, action)| score(query, &action.name).map(|score| (a, score)))
+                .collect::<Vec<_>>();
+            let best_action = actions.iter().map(|(_, score)| *score).max();
+            let score = item_score(query, item).or(best_action)?;
+            Some((i, score, actions))
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|(a, score_a, _), (b, score_b, _)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| items[*a].name.len().cmp(&items[*b].name.len()))
+    });
+
+    let mut results = Vec::new();
+    for (position, (item, _, actions)) in ranked.into_iter().take(MAX_RESULTS).enumerate() {
+        results.push(SearchResult { item, action: None });
+
+        let actions = match position {
+            0 => (0..items[item].actions.len()).collect(),
+            _ => actions
+                .into_iter()
+                .map(|(action, _)| action)
+                .collect::<Vec<_>>(),
+        };
+        results.extend(actions.into_iter().map(|action| SearchResult {
+            item,
+            action: Some(action),
+        }));
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LauncherAction;
+
+    fn item(name: &str, generic_name: Option<&str>, keywords: &[&str]) -> LauncherItem {
+        LauncherItem {
+            id: format!("{}.desktop", name.to_lowercase()),
+            name: name.to_string(),
+            generic_name: generic_name.map(str::to_string),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn names(items: &[LauncherItem], results: &[SearchResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|result| match result.action {
+                Some(action) => format!("  {}", items[result.item].actions[action].name),
+                None => items[result.item].name.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn score_prefers_word_starts_and_consecutive_matches() {
+        assert!(score("vsc", "Visual Studio Code") > score("vsc", "Dvscanner"));
+        assert!(score("fire", "Firefox") > score("fire", "Wildfire Editor"));
+        assert_eq!(score("xyz", "Firefox"), None);
+        assert_eq!(score("", "Firefox"), None);
+    }
+
+    #[test]
+    fn search_matches_generic_names_and_keywords() {
+        let items = vec![
+            item("Firefox", Some("Web Browser"), &["Internet", "WWW"]),
+            item("Files", Some("File Manager"), &["folder", "explorer"]),
+            item("Kitty", Some("Terminal Emulator"), &["shell"]),
+        ];
+
+        assert_eq!(names(&items, &search(&items, "browser")), vec!["Firefox"]);
+        assert_eq!(names(&items, &search(&items, "shell")), vec!["Kitty"]);
+        assert_eq!(
+            names(&items, &search(&items, "fi")),
+            vec!["Files", "Firefox"]
+        );
+        assert_eq!(
+            names(&items, &search(&items, "")),
+            vec!["Files", "Firefox", "Kitty"]
+        );
+    }
+
+    #[test]
+    fn search_orders_by_frecency() {
+        let mut items = vec![
+            item("Files", None, &[]),
+            item("Firefox", None, &[]),
+            item("Kitty", None, &[]),
+        ];
+        items[1].frecency = 400.0;
+        items[2].frecency = 100.0;
+
+        assert_eq!(
+            names(&items, &search(&items, "")),
+            vec!["Firefox", "Kitty", "Files"]
+        );
+        assert_eq!(
+            names(&items, &search(&items, "fi")),
+            vec!["Firefox", "Files"]
+        );
+    }
+
+    #[test]
+    fn search_lists_actions_under_their_application() {
+        let action = |id: &str, name: &str| LauncherAction {
+            id: id.to_string(),
+            name: name.to_string(),
+        };
+        let mut firefox = item("Firefox", Some("Web Browser"), &[]);
+        firefox.actions = vec![
+            action("new-window", "New Window"),
+            action("new-private-window", "New Private Window"),
+        ];
+        let mut kitty = item("Kitty", None, &[]);
+        kitty.actions = vec![action("new-window", "New Window")];
+        let items = vec![firefox, kitty];
+
+        assert_eq!(
+            names(&items, &search(&items, "firefox")),
+            vec!["Firefox", "  New Window", "  New Private Window"]
+        );
+        assert_eq!(
+            names(&items, &search(&items, "private")),
+            vec!["Firefox", "  New Window", "  New Private Window"]
+        );
+        assert_eq!(
+            names(&items, &search(&items, "new window")),
+            vec![
+                "Kitty",
+                "  New Window",
+                "Firefox",
+                "  New Window",
+                "  New Private Window"
+            ]
+        );
+    }
+}
@@ -0,0 +1,276 @@
+pub mod fuzzy;
+pub mod traits;
+
+use gtk::gdk::Key;
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box as GtkBox, Label, ListBox, Orientation};
+use gtk4_layer_shell::LayerShell;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use fuzzy::SearchResult;
+use traits::HasLauncherEvent;
+
+const ICON_SIZE: i32 = 32;
+const ACTION_ICON_SIZE: i32 = 16;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LauncherItem {
+    pub id: String,
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub actions: Vec<LauncherAction>,
+    pub frecency: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LauncherAction {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Clone)]
+pub struct LauncherPanel {
+    window: ApplicationWindow,
+    entry: gtk::SearchEntry,
+    list: ListBox,
+    error: Label,
+    state: Rc<dyn HasLauncherEvent>,
+    items: Rc<RefCell<Vec<LauncherItem>>>,
+    results: Rc<RefCell<Vec<SearchResult>>>,
+}
+
+impl LauncherPanel {
+    pub fn new<S: HasLauncherEvent + 'static>(state: S) -> Self {
+        let window = ApplicationWindow::builder()
+            .title("Launcher")
+            .default_width(480)
+            .default_height(420)
+            .build();
+
+        let entry = gtk::SearchEntry::new();
+        entry.set_placeholder_text(Some("Search applications"));
+        entry.add_css_class("launcher-search");
+
+        let list = ListBox::new();
+        list.set_selection_mode(gtk::SelectionMode::Browse);
+        list.add_css_class("launcher-results");
+
+        let error = Label::new(None);
+        error.add_css_class("launcher-error");
+        error.set_wrap(true);
+        error.set_visible(false);
+
+        Self {
+            window,
+            entry,
+            list,
+            error,
+            state: Rc::new(state),
+            items: Rc::new(RefCell::new(Vec::new())),
+            results: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+    pub fn render(&self) -> ApplicationWindow {
+        let launcher_window = self.window.clone();
+
+        LayerShell::init_layer_shell(&launcher_window);
+        launcher_window.set_layer(gtk4_layer_shell::Layer::Overlay);
+        launcher_window.set_keyboard_mode(gtk4_layer_shell::KeyboardMode::Exclusive);
+        launcher_window.set_namespace(Some("hybar:launcher"));
+
+        let vbox = GtkBox::new(Orientation::Vertical, 10);
+        vbox.add_css_class("launcher-panel");
+
+        let scrolled_window = gtk::ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .child(&self.list)
+            .build();
+
+        vbox.append(&self.entry);
+        vbox.append(&self.error);
+        vbox.append(&scrolled_window);
+        launcher_window.set_child(Some(&vbox));
+        launcher_window.add_css_class("launcher-window");
+
+        let panel = self.clone();
+        self.entry
+            .connect_search_changed(move |_| panel.update_results());
+
+        let panel = self.clone();
+        self.entry.connect_activate(move |_| {
+            if let Some(row) = panel.list.selected_row() {
+                panel.launch_row(&row);
+            }
+        });
+
+        let panel = self.clone();
+        self.entry.connect_stop_search(move |_| panel.hide());
+
+        let panel = self.clone();
+        self.list
+            .connect_row_activated(move |_, row| panel.launch_row(row));
+
+        let keys = gtk::EventControllerKey::new();
+        keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+        let panel = self.clone();
+        keys.connect_key_pressed(move |_, key, _, _| match key {
+            Key::Down | Key::Tab => {
+                panel.move_selection(1);
+                glib::Propagation::Stop
+            }
+            Key::Up | Key::ISO_Left_Tab => {
+                panel.move_selection(-1);
+                glib::Propagation::Stop
+            }
+            Key::Escape => {
+                panel.hide();
+                glib::Propagation::Stop
+            }
+            _ => glib::Propagation::Proceed,
+        });
+        launcher_window.add_controller(keys);
+
+        launcher_window
+    }
+
+    pub fn toggle(&self) {
+        match self.window.is_visible() {
+            true => self.hide(),
+            false => self.show(),
+        }
+    }
+
+    pub fn show(&self) {
+        *self.items.borrow_mut() = self.state.entries();
+        self.error.set_visible(false);
+        self.entry.set_text("");
+        self.update_results();
+        self.window.present();
+        self.entry.grab_focus();
+    }
+
+    pub fn hide(&self) {
+        self.window.hide();
+    }
+
+    fn update_results(&self) {
+        while let Some(row) = self.list.row_at_index(0) {
+            self.list.remove(&row);
+        }
+
+        let items = self.items.borrow();
+        let results = fuzzy::search(&items, &self.entry.text());
+        for result in &results {
+            let item = &items[result.item];
+            let row = match result.action {
+                Some(action) => self.action_row(item, &item.actions[action]),
+                None => self.item_row(item),
+            };
+            self.list.append(&row);
+        }
+        *self.results.borrow_mut() = results;
+
+        self.list.select_row(self.list.row_at_index(0).as_ref());
+    }
+
+    fn item_row(&self, item: &LauncherItem) -> gtk::ListBoxRow {
+        let hbox = GtkBox::new(Orientation::Horizontal, 10);
+        hbox.append(&self.state.icon(item, ICON_SIZE, self.window.scale_factor()));
+
+        let labels = GtkBox::new(Orientation::Vertical, 2);
+        labels.set_valign(gtk::Align::Center);
+
+        let name = Label::new(Some(&item.name));
+        name.set_halign(gtk::Align::Start);
+        name.add_css_class("launcher-name");
+        labels.append(&name);
+
+        if let Some(description) = item.generic_name.as_ref().or(item.comment.as_ref()) {
+            let description = Label::new(Some(description));
+            description.set_halign(gtk::Align::Start);
+            description.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            description.add_css_class("launcher-description");
+            labels.append(&description);
+        }
+        hbox.append(&labels);
+
+        let row = gtk::ListBoxRow::new();
+        row.set_child(Some(&hbox));
+        row.add_css_class("launcher-item");
+        row
+    }
+
+    fn action_row(&self, item: &LauncherItem, action: &LauncherAction) -> gtk::ListBoxRow {
+        let hbox = GtkBox::new(Orientation::Horizontal, 8);
+        hbox.append(
+            &self
+                .state
+                .icon(item, ACTION_ICON_SIZE, self.window.scale_factor()),
+        );
+
+        let name = Label::new(Some(&action.name));
+        name.set_halign(gtk::Align::Start);
+        hbox.append(&name);
+
+        let row = gtk::ListBoxRow::new();
+        row.set_child(Some(&hbox));
+        row.add_css_class("launcher-action");
+        row
+    }
+
+    fn move_selection(&self, offset: i32) {
+        let count = self.results.borrow().len() as i32;
+        if count == 0 {
+            return;
+        }
+
+        let current = self.list.selected_row().map(|row| row.index()).unwrap_or(0);
+        let row = self.list.row_at_index((current + offset).rem_euclid(count));
+        self.list.select_row(row.as_ref());
+        if let Some(row) = row {
+            row.grab_focus();
+            self.entry.grab_focus();
+            self.entry.set_position(-1);
+        }
+    }
+
+    fn launch_row(&self, row: &gtk::ListBoxRow) {
+        let Some(result) = self.results.borrow().get(row.index() as usize).copied() else {
+            return;
+        };
+        let (id, action) = {
+            let items = self.items.borrow();
+            let item = &items[result.item];
+            let action = result.action.map(|action| item.actions[action].id.clone());
+            (item.id.clone(), action)
+        };
+
+        let token = self
+            .entry
+            .display()
+            .app_launch_context()
+            .startup_notify_id(None::<&gio::AppInfo>, &[])
+            .map(String::from);
+
+        // Hidden first, a launch that fails right away shows the panel again
+        // with the error.
+        self.hide();
+        let panel = self.clone();
+        self.state.launch(
+            &id,
+            action.as_deref(),
+            token,
+            Box::new(move |error| {
+                panel.window.present();
+                panel.error.set_text(&error);
+                panel.error.set_visible(true);
+            }),
+        );
+    }
+}
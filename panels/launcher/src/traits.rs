@@ -0,0 +1,14 @@
+use crate::LauncherItem;
+
+pub trait HasLauncherEvent {
+    fn entries(&self) -> Vec<LauncherItem>;
+    fn icon(&self, item: &LauncherItem, size: i32, scale: i32) -> gtk::Widget;
+
+    fn launch(
+        &self,
+        id: &str,
+        action: Option<&str>,
+        token: Option<String>,
+        on_error: Box<dyn FnOnce(String)>,
+    );
+}
@@ -0,0 +1,564 @@
+pub mod pulse;
+
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::ApplicationWindow;
+use gtk::EventControllerMotion;
+use gtk::{Align, Box, Label, Orientation};
+use gtk4_layer_shell::LayerShell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use pulse::{Connection, Device, Stream, Target};
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+// Above 100% the signal is amplified in software and starts to clip.
+pub const MAX_VOLUME: f64 = 100.0;
+
+// What the mixer shows, None while no server is reachable.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct State {
+    pub sinks: Vec<Device>,
+    pub sources: Vec<Device>,
+    pub streams: Vec<Stream>,
+    pub default_sink: String,
+    pub default_source: String,
+}
+
+impl State {
+    pub fn default_sink(&self) -> Option<&Device> {
+        self.sinks
+            .iter()
+            .find(|sink| sink.name == self.default_sink)
+            .or_else(|| self.sinks.first())
+    }
+
+    pub fn default_source(&self) -> Option<&Device> {
+        self.sources
+            .iter()
+            .find(|source| source.name == self.default_source)
+            .or_else(|| self.sources.first())
+    }
+
+    fn volume(&self, target: Target) -> Option<(&pulse::Volume, bool)> {
+        fn find(devices: &[Device], index: u32) -> Option<(&pulse::Volume, bool)> {
+            devices
+                .iter()
+                .find(|device| device.index == index)
+                .map(|device| (&device.volume, device.muted))
+        }
+        match target {
+            Target::Sink(index) => find(&self.sinks, index),
+            Target::Source(index) => find(&self.sources, index),
+            Target::Stream(index) => self
+                .streams
+                .iter()
+                .find(|stream| stream.index == index)
+                .map(|stream| (&stream.volume, stream.muted)),
+        }
+    }
+}
+
+enum Request {
+    Volume(Target, f64),
+    Mute(Target, bool),
+    DefaultSink(String),
+    DefaultSource(String),
+}
+
+enum Message {
+    Request(Request),
+    Changed,
+    // Sent by the event thread of a connection that is gone.
+    Disconnected(u64),
+}
+
+type StateCallback = std::boxed::Box<dyn Fn(Option<&State>)>;
+
+// The audio server as seen from the UI thread. Requests and server events
+// are handled on a worker thread, which sends back the whole state after
+// every change.
+#[derive(Clone)]
+pub struct Mixer {
+    state: Rc<RefCell<Option<State>>>,
+    callbacks: Rc<RefCell<Vec<StateCallback>>>,
+    requests: mpsc::Sender<Message>,
+}
+
+impl Mixer {
+    // `socket` overrides the server's usual socket.
+    pub fn new(socket: Option<PathBuf>) -> Self {
+        let (requests, messages) = mpsc::channel();
+        let (states, updates) = async_channel::unbounded();
+        let mixer = Self {
+            state: Rc::default(),
+            callbacks: Rc::default(),
+            requests: requests.clone(),
+        };
+
+        std::thread::spawn(move || run(socket, requests, messages, states));
+        let receiver = mixer.clone();
+        glib::MainContext::default().spawn_local(async move {
+            while let Ok(state) = updates.recv().await {
+                receiver.set(state);
+            }
+        });
+        mixer
+    }
+
+    pub fn state(&self) -> Option<State> {
+        self.state.borrow().clone()
+    }
+
+    pub fn connect_changed<F: Fn(Option<&State>) + 'static>(&self, callback: F) {
+        self.callbacks
+            .borrow_mut()
+            .push(std::boxed::Box::new(callback));
+    }
+
+    fn set(&self, state: Option<State>) {
+        if *self.state.borrow() == state {
+            return;
+        }
+        *self.state.borrow_mut() = state.clone();
+        for callback in self.callbacks.borrow().iter() {
+            callback(state.as_ref());
+        }
+    }
+
+    fn send(&self, request: Request) {
+        if self.requests.send(Message::Request(request)).is_err() {
+            eprintln!("The audio mixer has stopped");
+        }
+    }
+
+    pub fn set_volume(&self, target: Target, percent: f64) {
+        self.send(Request::Volume(target, percent.clamp(0.0, MAX_VOLUME)));
+    }
+
+    // Moves the volume by `step` percent from what it is now. Only raising it
+    // is capped, so lowering a device that was amplified elsewhere doesn't
+    // jump down to MAX_VOLUME first.
+    pub fn change_volume(&self, target: Target, step: f64) {
+        let current = self
+            .state
+            .borrow()
+            .as_ref()
+            .and_then(|state| state.volume(target).map(|(volume, _)| volume.percent()));
+        if let Some(current) = current {
+            let percent = (current + step).min(current.max(MAX_VOLUME)).max(0.0);
+            self.send(Request::Volume(target, percent));
+        }
+    }
+
+    pub fn set_mute(&self, target: Target, muted: bool) {
+        self.send(Request::Mute(target, muted));
+    }
+
+    pub fn toggle_mute(&self, target: Target) {
+        let muted = self
+            .state
+            .borrow()
+            .as_ref()
+            .and_then(|state| state.volume(target).map(|(_, muted)| muted));
+        if let Some(muted) = muted {
+            self.set_mute(target, !muted);
+        }
+    }
+
+    pub fn set_default_sink(&self, name: &str) {
+        self.send(Request::DefaultSink(name.to_string()));
+    }
+
+    pub fn set_default_source(&self, name: &str) {
+        self.send(Request::DefaultSource(name.to_string()));
+    }
+}
+
+fn run(
+    socket: Option<PathBuf>,
+    sender: mpsc::Sender<Message>,
+    messages: mpsc::Receiver<Message>,
+    states: async_channel::Sender<Option<State>>,
+) {
+    let mut generation = 0;
+    let mut reported = false;
+    loop {
+        generation += 1;
+        let connected = Connection::connect(socket.as_deref()).and_then(|connection| {
+            let mut events = Connection::connect(socket.as_deref())?;
+            events.subscribe()?;
+            Ok((connection, events))
+        });
+        let (mut connection, mut events) = match connected {
+            Ok(connected) => connected,
+            Err(e) => {
+                // A missing server is logged once, not on every retry.
+                if !reported {
+                    eprintln!("Failed to connect to the audio server: {}", e);
+                    reported = true;
+                }
+                if states.send_blocking(None).is_err() {
+                    return;
+                }
+                // Requests made while disconnected have nothing to act on.
+                let _ = messages.recv_timeout(RECONNECT_DELAY);
+                while messages.try_recv().is_ok() {}
+                continue;
+            }
+        };
+        reported = false;
+
+        std::thread::spawn({
+            let sender = sender.clone();
+            move || {
+                while events.next_event().is_ok() {
+                    if sender.send(Message::Changed).is_err() {
+                        return;
+                    }
+                }
+                let _ = sender.send(Message::Disconnected(generation));
+            }
+        });
+
+        loop {
+            let state = match read_state(&mut connection) {
+                Ok(state) => state,
+                Err(e) => {
+                    eprintln!("Failed to read the audio server state: {}", e);
+                    break;
+                }
+            };
+            if states.send_blocking(Some(state.clone())).is_err() {
+                return;
+            }
+
+            // Everything queued is applied before the next read, so a
+            // dragged slider does not cause one read per step.
+            let Ok(first) = messages.recv() else {
+                return;
+            };
+            let mut alive = true;
+            for message in std::iter::once(first).chain(messages.try_iter()) {
+                match message {
+                    Message::Request(request) => {
+                        if let Err(e) = apply(&mut connection, &state, request) {
+                            eprintln!("Failed to update the audio server: {}", e);
+                        }
+                    }
+                    Message::Changed => {}
+                    Message::Disconnected(gone) => alive &= gone != generation,
+                }
+            }
+            if !alive {
+                break;
+            }
+        }
+    }
+}
+
+fn read_state(connection: &mut Connection) -> std::io::Result<State> {
+    let server = connection.server_info()?;
+    Ok(State {
+        sinks: connection.sinks()?,
+        sources: connection.sources()?,
+        streams: connection.streams()?,
+        default_sink: server.default_sink,
+        default_source: server.default_source,
+    })
+}
+
+fn apply(connection: &mut Connection, state: &State, request: Request) -> std::io::Result<()> {
+    match request {
+        // Scaled from the last known volume to keep the balance.
+        Request::Volume(target, percent) => {
+            let Some((volume, _)) = state.volume(target) else {
+                return Ok(());
+            };
+            connection.set_volume(target, &volume.with_percent(percent))
+        }
+        Request::Mute(target, muted) => connection.set_mute(target, muted),
+        Request::DefaultSink(name) => connection.set_default_sink(&name),
+        Request::DefaultSource(name) => connection.set_default_source(&name),
+    }
+}
+
+pub fn build_ui(mixer: &Mixer) -> ApplicationWindow {
+    let main_box = Box::new(Orientation::Vertical, 12);
+    main_box.add_css_class("mixer");
+    let outputs = Section::new("Output devices");
+    let inputs = Section::new("Input devices");
+    let applications = Section::new("Applications");
+    let unavailable = Label::new(Some("No audio server"));
+    unavailable.add_css_class("mixer-unavailable");
+
+    main_box.append(&unavailable);
+    main_box.append(&outputs.container);
+    main_box.append(&inputs.container);
+    main_box.append(&applications.container);
+
+    let update = {
+        let mixer = mixer.clone();
+        move |state: Option<&State>| {
+            unavailable.set_visible(state.is_none());
+            let state = state.cloned().unwrap_or_default();
+            outputs.update(
+                &mixer,
+                devices(&state.sinks, &state.default_sink, Target::Sink),
+            );
+            inputs.update(
+                &mixer,
+                devices(&state.sources, &state.default_source, Target::Source),
+            );
+            applications.update(&mixer, streams(&state));
+        }
+    };
+    update(mixer.state().as_ref());
+    mixer.connect_changed(update);
+
+    window(main_box)
+}
+
+pub fn window(child: gtk::Box) -> ApplicationWindow {
+    let window = ApplicationWindow::builder()
+        .title("Mixer")
+        .default_width(400)
+        .build();
+    LayerShell::init_layer_shell(&window);
+
+    window.set_layer(gtk4_layer_shell::Layer::Overlay);
+    window.set_anchor(gtk4_layer_shell::Edge::Right, true);
+    window.set_anchor(gtk4_layer_shell::Edge::Left, false);
+    window.set_anchor(gtk4_layer_shell::Edge::Top, true);
+    window.set_anchor(gtk4_layer_shell::Edge::Bottom, false);
+    window.set_namespace(Some("hybar:mixer"));
+
+    window.set_child(Some(&child));
+    window.add_css_class("settings-window");
+    window.add_css_class("mixer-window");
+    let controller = EventControllerMotion::new();
+    let window_clone = window.clone();
+    controller.connect_leave(move |_| {
+        window_clone.hide();
+    });
+    window.add_controller(controller);
+    window
+}
+
+// One line of the mixer, a device when `default` is set.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    target: Target,
+    title: String,
+    subtitle: Option<String>,
+    icon: Option<String>,
+    percent: f64,
+    muted: bool,
+    // The device name and whether it is the default one.
+    default: Option<(String, bool)>,
+}
+
+fn devices(devices: &[Device], default: &str, target: fn(u32) -> Target) -> Vec<Entry> {
+    devices
+        .iter()
+        .map(|device| Entry {
+            target: target(device.index),
+            title: device.description.clone(),
+            subtitle: None,
+            icon: None,
+            percent: device.volume.percent(),
+            muted: device.muted,
+            default: Some((device.name.clone(), device.name == default)),
+        })
+        .collect()
+}
+
+fn streams(state: &State) -> Vec<Entry> {
+    state
+        .streams
+        .iter()
+        .map(|stream| Entry {
+            target: Target::Stream(stream.index),
+            title: stream.application.clone(),
+            subtitle: Some(stream.name.clone()).filter(|name| *name != stream.application),
+            icon: stream.icon.clone(),
+            percent: stream.volume.percent(),
+            muted: stream.muted,
+            default: None,
+        })
+        .collect()
+}
+
+struct Row {
+    container: Box,
+    default: Option<gtk::CheckButton>,
+    mute: gtk::ToggleButton,
+    scale: gtk::Scale,
+    // Set while the row follows the server, so that does not echo back.
+    updating: Rc<Cell<bool>>,
+}
+
+// A titled list whose rows are updated in place, so a slider being dragged
+// is not replaced under the pointer.
+struct Section {
+    container: Box,
+    list: Box,
+    rows: RefCell<HashMap<Target, (Entry, Row)>>,
+}
+
+impl Section {
+    fn new(title: &str) -> Self {
+        let container = Box::new(Orientation::Vertical, 6);
+        container.add_css_class("mixer-section");
+        let label = Label::new(Some(title));
+        label.add_css_class("mixer-title");
+        label.set_halign(Align::Start);
+        let list = Box::new(Orientation::Vertical, 6);
+        container.append(&label);
+        container.append(&list);
+
+        Self {
+            container,
+            list,
+            rows: RefCell::default(),
+        }
+    }
+
+    fn update(&self, mixer: &Mixer, entries: Vec<Entry>) {
+        self.container.set_visible(!entries.is_empty());
+        let mut rows = self.rows.borrow_mut();
+        rows.retain(|target, (_, row)| {
+            let keep = entries.iter().any(|entry| entry.target == *target);
+            if !keep {
+                self.list.remove(&row.container);
+            }
+            keep
+        });
+
+        for entry in entries {
+            match rows.get_mut(&entry.target) {
+                Some((current, row)) => {
+                    if *current != entry {
+                        row.show(&entry);
+                        *current = entry;
+                    }
+                }
+                None => {
+                    let group = rows.values().find_map(|(_, row)| row.default.clone());
+                    let row = Row::new(mixer, &entry, group.as_ref());
+                    self.list.append(&row.container);
+                    rows.insert(entry.target, (entry, row));
+                }
+            }
+        }
+    }
+}
+
+impl Row {
+    fn new(mixer: &Mixer, entry: &Entry, group: Option<&gtk::CheckButton>) -> Self {
+        let container = Box::new(Orientation::Vertical, 2);
+        container.add_css_class("mixer-row");
+
+        let header = Box::new(Orientation::Horizontal, 6);
+        let default = entry.default.as_ref().map(|(name, _)| {
+            let check = gtk::CheckButton::new();
+            check.set_group(group);
+            check.set_tooltip_text(Some("Use as default"));
+            header.append(&check);
+            (check, name.clone())
+        });
+        if let Some(icon) = &entry.icon {
+            header.append(&gtk::Image::from_icon_name(icon));
+        }
+        let title = Label::new(Some(&entry.title));
+        title.add_css_class("mixer-name");
+        title.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        title.set_hexpand(true);
+        title.set_xalign(0.0);
+        header.append(&title);
+        container.append(&header);
+        if let Some(subtitle) = &entry.subtitle {
+            let label = Label::new(Some(subtitle));
+            label.add_css_class("mixer-subtitle");
+            label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+            label.set_xalign(0.0);
+            container.append(&label);
+        }
+
+        let controls = Box::new(Orientation::Horizontal, 6);
+        let mute = gtk::ToggleButton::new();
+        mute.add_css_class("mixer-mute");
+        let scale = gtk::Scale::with_range(Orientation::Horizontal, 0.0, MAX_VOLUME, 1.0);
+        scale.set_hexpand(true);
+        scale.set_draw_value(true);
+        scale.set_digits(0);
+        scale.set_value_pos(gtk::PositionType::Right);
+        controls.append(&mute);
+        controls.append(&scale);
+        container.append(&controls);
+
+        let updating = Rc::new(Cell::new(false));
+        let target = entry.target;
+        scale.connect_value_changed({
+            let (mixer, updating) = (mixer.clone(), Rc::clone(&updating));
+            move |scale| {
+                if !updating.get() {
+                    mixer.set_volume(target, scale.value());
+                }
+            }
+        });
+        mute.connect_toggled({
+            let (mixer, updating) = (mixer.clone(), Rc::clone(&updating));
+            move |mute| {
+                if !updating.get() {
+                    mixer.set_mute(target, mute.is_active());
+                }
+            }
+        });
+        let default = default.map(|(check, name)| {
+            let (mixer, updating) = (mixer.clone(), Rc::clone(&updating));
+            check.connect_toggled(move |check| {
+                if updating.get() || !check.is_active() {
+                    return;
+                }
+                match target {
+                    Target::Source(_) => mixer.set_default_source(&name),
+                    _ => mixer.set_default_sink(&name),
+                }
+            });
+            check
+        });
+
+        let row = Self {
+            container,
+            default,
+            mute,
+            scale,
+            updating,
+        };
+        row.show(entry);
+        row
+    }
+
+    fn show(&self, entry: &Entry) {
+        self.updating.set(true);
+        self.scale.set_value(entry.percent.round());
+        self.mute.set_active(entry.muted);
+        self.mute.set_icon_name(match entry.muted {
+            true => "audio-volume-muted-symbolic",
+            false => "audio-volume-high-symbolic",
+        });
+        if let (Some(check), Some((_, default))) = (&self.default, &entry.default) {
+            check.set_active(*default);
+        }
+        match entry.muted {
+            true => self.container.add_css_class("muted"),
+            false => self.container.remove_css_class("muted"),
+        }
+        self.updating.set(false);
+    }
+}
@@ -0,0 +1,818 @@
+// A client for the PulseAudio native protocol, which pipewire-pulse speaks
+// as well. Only the introspection and control commands the mixer needs are
+// implemented; no audio is ever streamed.
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+
+// The oldest version with property lists, newer ones only append fields the
+// mixer does not use. Servers encode replies for the client's version.
+const PROTOCOL_VERSION: u32 = 13;
+const PROTOCOL_VERSION_MASK: u32 = 0x0000_ffff;
+const COOKIE_LEN: usize = 256;
+const HEADER_LEN: usize = 20;
+const CONTROL_CHANNEL: u32 = u32::MAX;
+const NO_TAG: u32 = u32::MAX;
+pub const INVALID_INDEX: u32 = u32::MAX;
+pub const VOLUME_NORM: u32 = 0x10000;
+const VOLUME_MAX: u32 = u32::MAX / 2;
+
+const COMMAND_ERROR: u32 = 0;
+const COMMAND_REPLY: u32 = 2;
+const COMMAND_AUTH: u32 = 8;
+const COMMAND_SET_CLIENT_NAME: u32 = 9;
+const COMMAND_GET_SERVER_INFO: u32 = 20;
+const COMMAND_GET_SINK_INFO_LIST: u32 = 22;
+const COMMAND_GET_SOURCE_INFO_LIST: u32 = 24;
+const COMMAND_GET_SINK_INPUT_INFO_LIST: u32 = 30;
+const COMMAND_SUBSCRIBE: u32 = 35;
+const COMMAND_SET_SINK_VOLUME: u32 = 36;
+const COMMAND_SET_SINK_INPUT_VOLUME: u32 = 37;
+const COMMAND_SET_SOURCE_VOLUME: u32 = 38;
+const COMMAND_SET_SINK_MUTE: u32 = 39;
+const COMMAND_SET_SOURCE_MUTE: u32 = 40;
+const COMMAND_SET_DEFAULT_SINK: u32 = 44;
+const COMMAND_SET_DEFAULT_SOURCE: u32 = 45;
+const COMMAND_SUBSCRIBE_EVENT: u32 = 66;
+const COMMAND_SET_SINK_INPUT_MUTE: u32 = 69;
+
+const SUBSCRIPTION_SINK: u32 = 0x0001;
+const SUBSCRIPTION_SOURCE: u32 = 0x0002;
+const SUBSCRIPTION_SINK_INPUT: u32 = 0x0004;
+const SUBSCRIPTION_SERVER: u32 = 0x0080;
+
+const TAG_STRING: u8 = b't';
+const TAG_STRING_NULL: u8 = b'N';
+const TAG_U32: u8 = b'L';
+const TAG_U8: u8 = b'B';
+const TAG_U64: u8 = b'R';
+const TAG_S64: u8 = b'r';
+const TAG_SAMPLE_SPEC: u8 = b'a';
+const TAG_ARBITRARY: u8 = b'x';
+const TAG_BOOLEAN_TRUE: u8 = b'1';
+const TAG_BOOLEAN_FALSE: u8 = b'0';
+const TAG_TIMEVAL: u8 = b'T';
+const TAG_USEC: u8 = b'U';
+const TAG_CHANNEL_MAP: u8 = b'm';
+const TAG_CVOLUME: u8 = b'v';
+const TAG_PROPLIST: u8 = b'P';
+const TAG_VOLUME: u8 = b'V';
+
+// One value per channel, VOLUME_NORM being 100%.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Volume(pub Vec<u32>);
+
+impl Volume {
+    // The loudest channel, as mixers show it.
+    pub fn percent(&self) -> f64 {
+        let max = self.0.iter().copied().max().unwrap_or(0);
+        max as f64 / VOLUME_NORM as f64 * 100.0
+    }
+
+    // Scales every channel so the loudest one is at `percent`, keeping the
+    // balance between them.
+    pub fn with_percent(&self, percent: f64) -> Volume {
+        let target = (percent.max(0.0) / 100.0 * VOLUME_NORM as f64).min(VOLUME_MAX as f64);
+        let max = self.0.iter().copied().max().unwrap_or(0);
+        if max == 0 {
+            let channels = self.0.len().max(1);
+            return Volume(vec![target.round() as u32; channels]);
+        }
+        Volume(
+            self.0
+                .iter()
+                .map(|channel| (*channel as f64 * target / max as f64).round() as u32)
+                .collect(),
+        )
+    }
+}
+
+// A sink or a source.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Device {
+    pub index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: Volume,
+    pub muted: bool,
+}
+
+// A playback stream of an application, a sink input in PulseAudio terms.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stream {
+    pub index: u32,
+    pub name: String,
+    pub application: String,
+    pub icon: Option<String>,
+    pub sink: u32,
+    pub volume: Volume,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ServerInfo {
+    pub default_sink: String,
+    pub default_source: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    Sink(u32),
+    Source(u32),
+    Stream(u32),
+}
+
+#[derive(Default)]
+pub struct TagWriter {
+    data: Vec<u8>,
+}
+
+impl TagWriter {
+    pub fn u32(&mut self, value: u32) -> &mut Self {
+        self.data.push(TAG_U32);
+        self.data.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    pub fn string(&mut self, value: Option<&str>) -> &mut Self {
+        match value {
+            Some(value) => {
+                self.data.push(TAG_STRING);
+                self.data.extend_from_slice(value.as_bytes());
+                self.data.push(0);
+            }
+            None => self.data.push(TAG_STRING_NULL),
+        }
+        self
+    }
+
+    pub fn boolean(&mut self, value: bool) -> &mut Self {
+        self.data.push(match value {
+            true => TAG_BOOLEAN_TRUE,
+            false => TAG_BOOLEAN_FALSE,
+        });
+        self
+    }
+
+    pub fn arbitrary(&mut self, value: &[u8]) -> &mut Self {
+        self.data.push(TAG_ARBITRARY);
+        self.data
+            .extend_from_slice(&(value.len() as u32).to_be_bytes());
+        self.data.extend_from_slice(value);
+        self
+    }
+
+    pub fn volume(&mut self, volume: &Volume) -> &mut Self {
+        self.data.push(TAG_CVOLUME);
+        self.data.push(volume.0.len() as u8);
+        for channel in &volume.0 {
+            self.data.extend_from_slice(&channel.to_be_bytes());
+        }
+        self
+    }
+
+    // Values are NUL terminated strings, as libpulse stores text properties.
+    pub fn proplist(&mut self, properties: &[(&str, &str)]) -> &mut Self {
+        self.data.push(TAG_PROPLIST);
+        for (key, value) in properties {
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.push(0);
+            self.string(Some(key));
+            self.u32(bytes.len() as u32);
+            self.arbitrary(&bytes);
+        }
+        self.string(None)
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub struct TagReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> TagReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(malformed("truncated value"));
+        }
+        let (value, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(value)
+    }
+
+    fn tag(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn expect(&mut self, expected: u8) -> io::Result<()> {
+        match self.tag()? {
+            tag if tag == expected => Ok(()),
+            tag => Err(malformed(&format!(
+                "expected tag {:?}, got {:?}",
+                expected as char, tag as char
+            ))),
+        }
+    }
+
+    fn be_u32(&mut self) -> io::Result<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    pub fn u32(&mut self) -> io::Result<u32> {
+        self.expect(TAG_U32)?;
+        self.be_u32()
+    }
+
+    pub fn string(&mut self) -> io::Result<Option<String>> {
+        match self.tag()? {
+            TAG_STRING_NULL => Ok(None),
+            TAG_STRING => {
+                let len = self
+                    .data
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .ok_or_else(|| malformed("unterminated string"))?;
+                let value = String::from_utf8_lossy(self.take(len)?).into_owned();
+                self.take(1)?;
+                Ok(Some(value))
+            }
+            tag => Err(malformed(&format!(
+                "expected a string, got {:?}",
+                tag as char
+            ))),
+        }
+    }
+
+    pub fn boolean(&mut self) -> io::Result<bool> {
+        match self.tag()? {
+            TAG_BOOLEAN_TRUE => Ok(true),
+            TAG_BOOLEAN_FALSE => Ok(false),
+            tag => Err(malformed(&format!(
+                "expected a boolean, got {:?}",
+                tag as char
+            ))),
+        }
+    }
+
+    pub fn volume(&mut self) -> io::Result<Volume> {
+        self.expect(TAG_CVOLUME)?;
+        let channels = self.take(1)?[0];
+        (0..channels)
+            .map(|_| self.be_u32())
+            .collect::<io::Result<_>>()
+            .map(Volume)
+    }
+
+    pub fn proplist(&mut self) -> io::Result<HashMap<String, String>> {
+        self.expect(TAG_PROPLIST)?;
+        let mut properties = HashMap::new();
+        while let Some(key) = self.string()? {
+            let len = self.u32()? as usize;
+            self.expect(TAG_ARBITRARY)?;
+            if self.be_u32()? as usize != len {
+                return Err(malformed("property length mismatch"));
+            }
+            let value = self.take(len)?;
+            let value = value.strip_suffix(&[0]).unwrap_or(value);
+            properties.insert(key, String::from_utf8_lossy(value).into_owned());
+        }
+        Ok(properties)
+    }
+
+    // Skips a value the mixer has no use for, whatever its type.
+    pub fn skip(&mut self) -> io::Result<()> {
+        let len = match self.tag()? {
+            TAG_STRING => {
+                self.string_tail()?;
+                return Ok(());
+            }
+            TAG_STRING_NULL | TAG_BOOLEAN_TRUE | TAG_BOOLEAN_FALSE => 0,
+            TAG_U8 => 1,
+            TAG_U32 | TAG_VOLUME => 4,
+            TAG_U64 | TAG_S64 | TAG_USEC | TAG_TIMEVAL => 8,
+            TAG_SAMPLE_SPEC => 6,
+            TAG_ARBITRARY => self.be_u32()? as usize,
+            TAG_CHANNEL_MAP => self.take(1)?[0] as usize,
+            TAG_CVOLUME => self.take(1)?[0] as usize * 4,
+            TAG_PROPLIST => {
+                while self.string()?.is_some() {
+                    self.skip()?;
+                    self.skip()?;
+                }
+                return Ok(());
+            }
+            tag => return Err(malformed(&format!("unknown tag {:?}", tag as char))),
+        };
+        self.take(len)?;
+        Ok(())
+    }
+
+    fn string_tail(&mut self) -> io::Result<()> {
+        let len = self
+            .data
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| malformed("unterminated string"))?;
+        self.take(len + 1)?;
+        Ok(())
+    }
+
+    fn skip_n(&mut self, count: usize) -> io::Result<()> {
+        (0..count).try_for_each(|_| self.skip())
+    }
+}
+
+pub struct Connection {
+    stream: UnixStream,
+    next_tag: u32,
+    events: VecDeque<u32>,
+}
+
+impl Connection {
+    pub fn connect(path: Option<&Path>) -> io::Result<Self> {
+        let path = path
+            .map(Path::to_path_buf)
+            .or_else(socket_path)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, "no PulseAudio socket configured")
+            })?;
+        let stream = UnixStream::connect(&path)?;
+        let mut connection = Self {
+            stream,
+            next_tag: 0,
+            events: VecDeque::new(),
+        };
+
+        // The credentials authorize clients of the same user, the cookie
+        // covers servers that want it anyway.
+        let tag = connection.take_tag();
+        let mut auth = TagWriter::default();
+        auth.u32(COMMAND_AUTH)
+            .u32(tag)
+            .u32(PROTOCOL_VERSION)
+            .arbitrary(&read_cookie());
+        send_with_credentials(&connection.stream, &packet(auth.into_bytes()))?;
+        let reply = connection.reply(tag)?;
+        let version = TagReader::new(&reply).u32()? & PROTOCOL_VERSION_MASK;
+        if version < PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("server protocol version {} is too old", version),
+            ));
+        }
+
+        connection.request(COMMAND_SET_CLIENT_NAME, |args| {
+            args.proplist(&[("application.name", "hybar"), ("application.id", "hybar")]);
+        })?;
+        Ok(connection)
+    }
+
+    fn take_tag(&mut self) -> u32 {
+        let tag = self.next_tag;
+        self.next_tag = self.next_tag.wrapping_add(1) % NO_TAG;
+        tag
+    }
+
+    fn request(&mut self, command: u32, args: impl FnOnce(&mut TagWriter)) -> io::Result<Vec<u8>> {
+        let tag = self.take_tag();
+        let mut writer = TagWriter::default();
+        writer.u32(command).u32(tag);
+        args(&mut writer);
+        self.stream.write_all(&packet(writer.into_bytes()))?;
+        self.reply(tag)
+    }
+
+    // Reads until the reply to `tag`, queueing subscription events that
+    // arrive in between.
+    fn reply(&mut self, tag: u32) -> io::Result<Vec<u8>> {
+        loop {
+            let (command, reply_tag, payload) = self.read_packet()?;
+            match command {
+                COMMAND_REPLY if reply_tag == tag => return Ok(payload),
+                COMMAND_ERROR if reply_tag == tag => {
+                    let code = TagReader::new(&payload).u32().unwrap_or(0);
+                    return Err(io::Error::other(format!("server error {}", code)));
+                }
+                COMMAND_SUBSCRIBE_EVENT => self.queue_event(&payload)?,
+                _ => {}
+            }
+        }
+    }
+
+    fn read_packet(&mut self) -> io::Result<(u32, u32, Vec<u8>)> {
+        loop {
+            let mut header = [0; HEADER_LEN];
+            self.stream.read_exact(&mut header)?;
+            let len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+            let channel = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+            let mut payload = vec![0; len];
+            self.stream.read_exact(&mut payload)?;
+            // Memory blocks belong to streams, which this client never opens.
+            if channel != CONTROL_CHANNEL {
+                continue;
+            }
+
+            let mut reader = TagReader::new(&payload);
+            let command = reader.u32()?;
+            let tag = reader.u32()?;
+            let consumed = payload.len() - reader.data.len();
+            return Ok((command, tag, payload[consumed..].to_vec()));
+        }
+    }
+
+    fn queue_event(&mut self, payload: &[u8]) -> io::Result<()> {
+        let event = TagReader::new(payload).u32()?;
+        self.events.push_back(event);
+        Ok(())
+    }
+
+    pub fn server_info(&mut self) -> io::Result<ServerInfo> {
+        let reply = self.request(COMMAND_GET_SERVER_INFO, |_| {})?;
+        let mut reader = TagReader::new(&reply);
+        // User, host, server version and name, then the sample spec.
+        reader.skip_n(5)?;
+        Ok(ServerInfo {
+            default_sink: reader.string()?.unwrap_or_default(),
+            default_source: reader.string()?.unwrap_or_default(),
+        })
+    }
+
+    pub fn sinks(&mut self) -> io::Result<Vec<Device>> {
+        let reply = self.request(COMMAND_GET_SINK_INFO_LIST, |_| {})?;
+        let mut reader = TagReader::new(&reply);
+        let mut sinks = Vec::new();
+        while !reader.is_empty() {
+            let (device, _) = read_device(&mut reader)?;
+            sinks.push(device);
+        }
+        Ok(sinks)
+    }
+
+    // Monitors of sinks are left out, they are not microphones.
+    pub fn sources(&mut self) -> io::Result<Vec<Device>> {
+        let reply = self.request(COMMAND_GET_SOURCE_INFO_LIST, |_| {})?;
+        let mut reader = TagReader::new(&reply);
+        let mut sources = Vec::new();
+        while !reader.is_empty() {
+            let (device, monitor_of) = read_device(&mut reader)?;
+            if monitor_of == INVALID_INDEX {
+                sources.push(device);
+            }
+        }
+        Ok(sources)
+    }
+
+    pub fn streams(&mut self) -> io::Result<Vec<Stream>> {
+        let reply = self.request(COMMAND_GET_SINK_INPUT_INFO_LIST, |_| {})?;
+        let mut reader = TagReader::new(&reply);
+        let mut streams = Vec::new();
+        while !reader.is_empty() {
+            let index = reader.u32()?;
+            let name = reader.string()?.unwrap_or_default();
+            // Owner module and client.
+            reader.skip_n(2)?;
+            let sink = reader.u32()?;
+            // Sample spec and channel map.
+            reader.skip_n(2)?;
+            let volume = reader.volume()?;
+            // Buffer and sink latency, resample method and driver.
+            reader.skip_n(4)?;
+            let muted = reader.boolean()?;
+            let mut properties = reader.proplist()?;
+
+            let application = properties
+                .remove("application.name")
+                .unwrap_or_else(|| name.clone());
+            streams.push(Stream {
+                index,
+                name,
+                application,
+                icon: properties.remove("application.icon_name"),
+                sink,
+                volume,
+                muted,
+            });
+        }
+        Ok(streams)
+    }
+
+    pub fn set_volume(&mut self, target: Target, volume: &Volume) -> io::Result<()> {
+        let (command, index, by_name) = match target {
+            Target::Sink(index) => (COMMAND_SET_SINK_VOLUME, index, true),
+            Target::Source(index) => (COMMAND_SET_SOURCE_VOLUME, index, true),
+            Target::Stream(index) => (COMMAND_SET_SINK_INPUT_VOLUME, index, false),
+        };
+        self.request(command, |args| {
+            args.u32(index);
+            if by_name {
+                args.string(None);
+            }
+            args.volume(volume);
+        })?;
+        Ok(())
+    }
+
+    pub fn set_mute(&mut self, target: Target, muted: bool) -> io::Result<()> {
+        let (command, index, by_name) = match target {
+            Target::Sink(index) => (COMMAND_SET_SINK_MUTE, index, true),
+            Target::Source(index) => (COMMAND_SET_SOURCE_MUTE, index, true),
+            Target::Stream(index) => (COMMAND_SET_SINK_INPUT_MUTE, index, false),
+        };
+        self.request(command, |args| {
+            args.u32(index);
+            if by_name {
+                args.string(None);
+            }
+            args.boolean(muted);
+        })?;
+        Ok(())
+    }
+
+    pub fn set_default_sink(&mut self, name: &str) -> io::Result<()> {
+        self.request(COMMAND_SET_DEFAULT_SINK, |args| {
+            args.string(Some(name));
+        })?;
+        Ok(())
+    }
+
+    pub fn set_default_source(&mut self, name: &str) -> io::Result<()> {
+        self.request(COMMAND_SET_DEFAULT_SOURCE, |args| {
+            args.string(Some(name));
+        })?;
+        Ok(())
+    }
+
+    pub fn subscribe(&mut self) -> io::Result<()> {
+        let mask =
+            SUBSCRIPTION_SINK | SUBSCRIPTION_SOURCE | SUBSCRIPTION_SINK_INPUT | SUBSCRIPTION_SERVER;
+        self.request(COMMAND_SUBSCRIBE, |args| {
+            args.u32(mask);
+        })?;
+        Ok(())
+    }
+
+    // Blocks until the server reports a change after `subscribe`.
+    pub fn next_event(&mut self) -> io::Result<u32> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Ok(event);
+            }
+            let (command, _, payload) = self.read_packet()?;
+            if command == COMMAND_SUBSCRIBE_EVENT {
+                self.queue_event(&payload)?;
+            }
+        }
+    }
+}
+
+// Sinks and sources share their layout, the returned index is the sink a
+// source monitors.
+fn read_device(reader: &mut TagReader) -> io::Result<(Device, u32)> {
+    let index = reader.u32()?;
+    let name = reader.string()?.unwrap_or_default();
+    let description = reader.string()?.unwrap_or_else(|| name.clone());
+    // Sample spec, channel map and owner module.
+    reader.skip_n(3)?;
+    let volume = reader.volume()?;
+    let muted = reader.boolean()?;
+    let linked = reader.u32()?;
+    // Linked device name, latency, driver, flags, properties and the
+    // requested latency.
+    reader.skip_n(6)?;
+
+    let device = Device {
+        index,
+        name,
+        description,
+        volume,
+        muted,
+    };
+    Ok((device, linked))
+}
+
+fn packet(payload: Vec<u8>) -> Vec<u8> {
+    let mut packet = Vec::with_capacity(HEADER_LEN + payload.len());
+    packet.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    packet.extend_from_slice(&CONTROL_CHANNEL.to_be_bytes());
+    // Offset and flags only matter for memory blocks.
+    packet.extend_from_slice(&[0; 12]);
+    packet.extend(payload);
+    packet
+}
+
+fn send_with_credentials(stream: &UnixStream, data: &[u8]) -> io::Result<()> {
+    let credentials = unsafe {
+        libc::ucred {
+            pid: libc::getpid(),
+            uid: libc::getuid(),
+            gid: libc::getgid(),
+        }
+    };
+    let credentials_len = std::mem::size_of::<libc::ucred>() as u32;
+    let mut control = vec![0u8; unsafe { libc::CMSG_SPACE(credentials_len) } as usize];
+    let mut iov = libc::iovec {
+        iov_base: data.as_ptr() as *mut libc::c_void,
+        iov_len: data.len(),
+    };
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = control.len() as _;
+
+    let sent = unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        (*header).cmsg_level = libc::SOL_SOCKET;
+        (*header).cmsg_type = libc::SCM_CREDENTIALS;
+        (*header).cmsg_len = libc::CMSG_LEN(credentials_len) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(header).cast(), credentials);
+        libc::sendmsg(stream.as_raw_fd(), &message, libc::MSG_NOSIGNAL)
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    (&*stream).write_all(&data[sent as usize..])
+}
+
+// $PULSE_SERVER when it names a local socket, otherwise the per-user one.
+fn socket_path() -> Option<PathBuf> {
+    if let Ok(server) = std::env::var("PULSE_SERVER") {
+        let path = server.strip_prefix("unix:").unwrap_or(&server);
+        if path.starts_with('/') {
+            return Some(PathBuf::from(path));
+        }
+    }
+    let runtime = std::env::var("XDG_RUNTIME_DIR")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("/run/user/{}", unsafe { libc::getuid() })));
+    Some(runtime.join("pulse/native"))
+}
+
+// Servers authorizing by credentials ignore the cookie, so a missing one is
+// sent as zeros.
+fn read_cookie() -> Vec<u8> {
+    let home = std::env::var("HOME").map(PathBuf::from).unwrap_or_default();
+    let config = std::env::var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| home.join(".config"));
+    let candidates = std::env::var("PULSE_COOKIE")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain([config.join("pulse/cookie"), home.join(".pulse-cookie")]);
+
+    let mut cookie = candidates
+        .filter_map(|path| std::fs::read(path).ok())
+        .find(|cookie| cookie.len() >= COOKIE_LEN)
+        .unwrap_or_default();
+    cookie.resize(COOKIE_LEN, 0);
+    cookie
+}
+
+fn malformed(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Child, Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn tagstruct_values_round_trip() {
+        let mut writer = TagWriter::default();
+        writer
+            .u32(7)
+            .string(Some("alsa_output.pci"))
+            .string(None)
+            .boolean(true)
+            .volume(&Volume(vec![VOLUME_NORM, VOLUME_NORM / 2]))
+            .proplist(&[("application.name", "Firefox")])
+            .u32(9);
+        let bytes = writer.into_bytes();
+
+        let mut reader = TagReader::new(&bytes);
+        assert_eq!(reader.u32().unwrap(), 7);
+        assert_eq!(reader.string().unwrap().as_deref(), Some("alsa_output.pci"));
+        reader.skip().unwrap();
+        assert!(reader.boolean().unwrap());
+        assert_eq!(
+            reader.volume().unwrap(),
+            Volume(vec![VOLUME_NORM, VOLUME_NORM / 2])
+        );
+        assert_eq!(reader.proplist().unwrap()["application.name"], "Firefox");
+        assert_eq!(reader.u32().unwrap(), 9);
+        assert!(reader.is_empty());
+        assert!(TagReader::new(&bytes[..3]).u32().is_err());
+    }
+
+    #[test]
+    fn volume_keeps_the_channel_balance() {
+        let volume = Volume(vec![VOLUME_NORM / 2, VOLUME_NORM / 4]);
+        assert_eq!(volume.percent(), 50.0);
+        assert_eq!(
+            volume.with_percent(100.0),
+            Volume(vec![VOLUME_NORM, VOLUME_NORM / 2])
+        );
+        assert_eq!(
+            Volume(vec![0, 0]).with_percent(25.0),
+            Volume(vec![VOLUME_NORM / 4; 2])
+        );
+    }
+
+    struct Server(Child);
+
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    // A private PulseAudio daemon with two null sinks, or None where it is
+    // not installed.
+    fn start_server(dir: &Path) -> Option<(Server, PathBuf)> {
+        let socket = dir.join("native");
+        let child = Command::new("pulseaudio")
+            .args([
+                "--daemonize=no",
+                "--exit-idle-time=-1",
+                "-n",
+                "--disable-shm",
+            ])
+            .arg(format!(
+                "--load=module-native-protocol-unix socket={} auth-anonymous=1",
+                socket.display()
+            ))
+            .arg("--load=module-null-sink sink_name=first")
+            .arg("--load=module-null-sink sink_name=second")
+            .env("HOME", dir)
+            .env("XDG_RUNTIME_DIR", dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let server = Server(child);
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !socket.exists() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        socket.exists().then_some((server, socket))
+    }
+
+    #[test]
+    fn controls_null_sinks_of_a_private_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let Some((_server, socket)) = start_server(dir.path()) else {
+            eprintln!("pulseaudio is not available, skipping");
+            return;
+        };
+
+        let mut connection = Connection::connect(Some(&socket)).unwrap();
+        let mut events = Connection::connect(Some(&socket)).unwrap();
+        events.subscribe().unwrap();
+
+        let sinks = connection.sinks().unwrap();
+        let second = sinks.iter().find(|sink| sink.name == "second").unwrap();
+        assert!(connection
+            .sources()
+            .unwrap()
+            .iter()
+            .all(|source| !source.name.ends_with(".monitor")));
+
+        connection
+            .set_volume(
+                Target::Sink(second.index),
+                &second.volume.with_percent(40.0),
+            )
+            .unwrap();
+        connection
+            .set_mute(Target::Sink(second.index), true)
+            .unwrap();
+        connection.set_default_sink("second").unwrap();
+        events.next_event().unwrap();
+
+        let sinks = connection.sinks().unwrap();
+        let second = sinks.iter().find(|sink| sink.name == "second").unwrap();
+        assert_eq!(second.volume.percent().round(), 40.0);
+        assert!(second.muted);
+        assert_eq!(connection.server_info().unwrap().default_sink, "second");
+    }
+}
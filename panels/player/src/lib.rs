@@ -6,9 +6,49 @@ use gtk::EventControllerMotion;
 use gtk::{Align, Box, Button, Label, Orientation};
 use gtk4_layer_shell::LayerShell;
 use mpris::{PlaybackStatus, PlayerFinder};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::time::Duration;
 
-pub fn build_ui() -> (ApplicationWindow, Label) {
+// The active player's track, with an empty status when nothing is playing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Track {
+    pub title: String,
+    pub artist: String,
+    pub status: String,
+}
+
+type TrackCallback = std::boxed::Box<dyn Fn(&Track)>;
+
+#[derive(Clone, Default)]
+pub struct NowPlaying {
+    track: Rc<RefCell<Track>>,
+    callbacks: Rc<RefCell<Vec<TrackCallback>>>,
+}
+
+impl NowPlaying {
+    pub fn track(&self) -> Track {
+        self.track.borrow().clone()
+    }
+
+    pub fn connect_changed<F: Fn(&Track) + 'static>(&self, callback: F) {
+        self.callbacks
+            .borrow_mut()
+            .push(std::boxed::Box::new(callback));
+    }
+
+    fn set(&self, track: Track) {
+        if *self.track.borrow() == track {
+            return;
+        }
+        *self.track.borrow_mut() = track.clone();
+        for callback in self.callbacks.borrow().iter() {
+            callback(&track);
+        }
+    }
+}
+
+pub fn build_ui() -> (ApplicationWindow, NowPlaying) {
     let main_box = main_box();
     let activity_container = activity_container();
     let label = activity_label();
@@ -63,6 +103,8 @@ pub fn build_ui() -> (ApplicationWindow, Label) {
     let cover_art_clone = cover_art.clone();
     let artist_label_clone = artist_label.clone();
     let progress_clone = progress_bar.clone();
+    let now_playing = NowPlaying::default();
+    let now_playing_clone = now_playing.clone();
     glib::timeout_add_local(Duration::from_millis(500), move || {
         update_status(
             &label_clone,
@@ -70,11 +112,12 @@ pub fn build_ui() -> (ApplicationWindow, Label) {
             &artist_label_clone,
             progress_clone.clone(),
             &cover_art_clone,
+            &now_playing_clone,
         );
         glib::ControlFlow::Continue
     });
 
-    (window, label)
+    (window, now_playing)
 }
 
 pub fn window(child: gtk::Box) -> ApplicationWindow {
@@ -200,6 +243,7 @@ fn update_status(
     artists_l: &Label,
     _progress_l: gtk::ProgressBar,
     cover_art: &gtk::Picture,
+    now_playing: &NowPlaying,
 ) {
     let finder = match PlayerFinder::new() {
         Ok(f) => f,
@@ -219,13 +263,13 @@ fn update_status(
         //};
 
         //progress_l.set_fraction(progress);
-        let status_icon = match player
+        let (status, status_icon) = match player
             .get_playback_status()
             .unwrap_or(PlaybackStatus::Stopped)
         {
-            PlaybackStatus::Playing => "🎵 Playing",
-            PlaybackStatus::Paused => "⏸ Paused",
-            PlaybackStatus::Stopped => "⏹ Stopped",
+            PlaybackStatus::Playing => ("playing", "🎵 Playing"),
+            PlaybackStatus::Paused => ("paused", "⏸ Paused"),
+            PlaybackStatus::Stopped => ("stopped", "⏹ Stopped"),
         };
 
         let (title, artists, _duration) = match metadata {
@@ -258,7 +302,13 @@ fn update_status(
         let texture = Texture::from_file(&file).ok();
 
         cover_art.set_paintable(texture.as_ref());
+        now_playing.set(Track {
+            title: title.to_string(),
+            artist: artists,
+            status: status.to_string(),
+        });
     } else {
         title_l.set_text("💤 No activity");
+        now_playing.set(Track::default());
     }
 }
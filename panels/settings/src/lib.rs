@@ -66,6 +66,12 @@ impl SettingsPanel {
         let save_button = gtk::Button::with_label("Save Settings");
         save_button.add_css_class("info");
 
+        let clear_history_button = gtk::Button::with_label("Clear Launch History");
+        clear_history_button.add_css_class("cancel");
+
+        let state_clone = Arc::clone(&self.state);
+        clear_history_button.connect_clicked(move |_| state_clone.clear_history());
+
         let cancel_button = gtk::Button::with_label("Cancel");
         cancel_button.add_css_class("cancel");
 
@@ -79,6 +85,7 @@ impl SettingsPanel {
 
         vbox.append(&theme_selector);
         vbox.append(&_bar_position_selector);
+        vbox.append(&clear_history_button);
         //vbox.append(&reload_button);
         //vbox.append(&cancel_button);
         //vbox.append(&save_button);
@@ -9,4 +9,6 @@ pub trait HasSettingsEvent: Send + Sync {
 
     fn set_bar_position(&self, position: String);
     fn get_bar_position(&self) -> String;
+
+    fn clear_history(&self);
 }
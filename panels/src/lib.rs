@@ -9,3 +9,11 @@ pub mod calendar {
 pub mod player {
     pub use player::*;
 }
+
+pub mod launcher {
+    pub use launcher::*;
+}
+
+pub mod mixer {
+    pub use mixer::*;
+}
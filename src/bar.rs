@@ -1,9 +1,10 @@
 mod events;
-use gtk::{Application, prelude::*};
+use gtk::{Application, gio, glib, prelude::*};
 use std::{
     cell::{Cell, RefCell},
     rc::Rc,
     sync::Arc,
+    time::Duration,
 };
 
 use crate::{
@@ -16,9 +17,11 @@ use crate::{
         windows::BarWindows,
     },
     user::config::load_config,
-    utils::css::load_css,
+    utils::{css::load_css, launcher_entry},
 };
 
+const ERROR_POPOVER_MS: u64 = 4000;
+
 pub struct Hybar {
     window: BarWindows,
     preferences: Rc<RefCell<BarPreferences>>,
@@ -78,6 +81,10 @@ impl Hybar {
         .into()
     }
 
+    pub fn sender(&self) -> async_channel::Sender<UiEvent> {
+        self.channel.0.clone()
+    }
+
     pub fn build(self: Arc<Self>) {
         if let Err(e) = bootstrap_config() {
             eprintln!("Error inicializando configuración: {e}");
@@ -120,6 +127,20 @@ impl Hybar {
             });
         }
 
+        if self.widgets.borrow().widget_exists("apps") {
+            let sender = self.channel.0.clone();
+            gio::bus_get(
+                gio::BusType::Session,
+                gio::Cancellable::NONE,
+                move |connection| match connection {
+                    Ok(connection) => {
+                        launcher_entry::listen(&connection, sender);
+                    }
+                    Err(e) => eprintln!("Could not connect to the session bus: {e}"),
+                },
+            );
+        }
+
         let background = gtk::Box::builder().build();
         background.set_hexpand(true);
         background.set_vexpand(true);
@@ -166,17 +187,18 @@ impl Hybar {
                         Rc::clone(&section_center),
                     ),
                     UiEvent::ThemeChanged(theme) => load_css(&theme),
-                    UiEvent::WorkspaceChanged => {
-                        this.widgets.borrow_mut().widgets.workspaces.update(None);
-                    }
-                    UiEvent::WorkspaceUrgent(urgent) => this
-                        .widgets
-                        .borrow_mut()
-                        .widgets
-                        .workspaces
-                        .update(Some(urgent)),
+                    UiEvent::WorkspaceChanged => this.workspace_changed(),
+                    UiEvent::WorkspaceUrgent(urgent) => this.window_urgent(&urgent),
+                    UiEvent::ActiveWindowChanged(id) => this.active_window_changed(&id),
                     UiEvent::WindowOpened((name, id)) => this.window_opened(&id, &name),
                     UiEvent::WindowClosed(id) => this.window_closed(&id),
+                    UiEvent::DockPinChanged((name, pinned)) => this.dock_pin_changed(&name, pinned),
+                    UiEvent::DockChanged => this.dock_changed(),
+                    UiEvent::ClearHistory => this.clear_history(),
+                    UiEvent::Remote(command) => this.remote_command(command),
+                    UiEvent::LauncherEntryUpdated((id, entry)) => {
+                        this.launcher_entry_updated(id, entry)
+                    }
                 }
             }
         });
@@ -237,7 +259,7 @@ impl Hybar {
     }
 }
 
-pub fn set_popover(button: &gtk::Button, child: gtk::Widget) {
+pub fn set_popover(widget: &impl IsA<gtk::Widget>, child: gtk::Widget) -> gtk::Popover {
     let popover = gtk::Popover::builder()
         .child(&child)
         .has_arrow(true)
@@ -246,12 +268,32 @@ pub fn set_popover(button: &gtk::Button, child: gtk::Widget) {
         .build();
 
     popover.add_css_class("popover");
-    popover.set_parent(button);
+    popover.set_parent(widget);
+    popover
+}
 
-    let popover_show = popover.clone();
+pub fn show_error_popover(widget: &gtk::Widget, message: &str) {
+    let label = gtk::Label::new(Some(message));
+    label.set_wrap(true);
+    label.set_max_width_chars(40);
 
-    button.connect_clicked(move |_| {
-        popover_show.popup();
+    let popover = gtk::Popover::builder()
+        .child(&label)
+        .has_arrow(true)
+        .autohide(true)
+        .position(gtk::PositionType::Bottom)
+        .build();
+    popover.add_css_class("popover");
+    popover.add_css_class("error-popover");
+    popover.set_parent(widget);
+    popover.connect_closed(|popover| popover.unparent());
+    popover.popup();
+
+    let popover = popover.downgrade();
+    glib::timeout_add_local_once(Duration::from_millis(ERROR_POPOVER_MS), move || {
+        if let Some(popover) = popover.upgrade() {
+            popover.popdown();
+        }
     });
 }
 
@@ -0,0 +1,69 @@
+use crate::{
+    bar::{Hybar, find_widget_child},
+    ui::widgets::apps::{OPENED_CLASS, PINNED_CLASS},
+    user::config::save_favorites,
+    utils::launcher_entry::LauncherEntry,
+};
+use gtk::prelude::*;
+
+impl Hybar {
+    pub fn dock_pin_changed(&self, name: &str, pinned: bool) {
+        {
+            let widgets_builder = self.widgets.borrow();
+            let Some(widget) = find_widget_child(&widgets_builder.widgets.apps, name) else {
+                return;
+            };
+
+            if pinned {
+                widget.add_css_class(PINNED_CLASS);
+            } else {
+                widget.remove_css_class(PINNED_CLASS);
+                if !widget.has_css_class(OPENED_CLASS) {
+                    widgets_builder.remove_widget_app(&widget);
+                }
+            }
+        }
+
+        self.dock_changed();
+    }
+
+    pub fn launcher_entry_updated(&self, desktop_id: String, update: LauncherEntry) {
+        let widgets_builder = self.widgets.borrow();
+        widgets_builder
+            .dock_state
+            .launcher_entries
+            .borrow_mut()
+            .entry(desktop_id)
+            .or_default()
+            .merge(update);
+        widgets_builder.refresh_dock();
+    }
+
+    pub fn clear_history(&self) {
+        self.widgets.borrow().clear_history();
+    }
+
+    pub fn dock_changed(&self) {
+        let favorites = {
+            let widgets_builder = self.widgets.borrow();
+            let mut favorites = Vec::new();
+            let mut child = widgets_builder.widgets.apps.first_child();
+            while let Some(widget) = child {
+                if widget.has_css_class(PINNED_CLASS) {
+                    favorites.push(widget.widget_name().to_string());
+                }
+                child = widget.next_sibling();
+            }
+            favorites
+        };
+
+        if self.preferences.borrow().favorites == favorites {
+            return;
+        }
+
+        if let Err(e) = save_favorites(&favorites) {
+            eprintln!("Error saving dock favorites: {e}");
+        }
+        self.preferences.borrow_mut().favorites = favorites;
+    }
+}
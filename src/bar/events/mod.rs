@@ -1,4 +1,6 @@
+pub mod dock_changed;
 pub mod preferences_changed;
 pub mod reload_bar;
+pub mod remote_command;
 pub mod title_changed;
 pub mod window_event;
@@ -0,0 +1,16 @@
+use crate::{bar::Hybar, remote::RemoteCommand};
+
+impl Hybar {
+    pub fn remote_command(&self, command: RemoteCommand) {
+        match command {
+            RemoteCommand::Refresh(name) => {
+                if !self.widgets.borrow().refresh_script(&name) {
+                    eprintln!("No script widget named {}", name);
+                }
+            }
+            RemoteCommand::Osd { kind, value, muted } => {
+                self.widgets.borrow().osd.show(kind, value, muted)
+            }
+        }
+    }
+}
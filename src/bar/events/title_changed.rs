@@ -1,18 +1,21 @@
-use crate::bar::{Hybar, find_widget_child};
 use gtk::prelude::*;
 
+use crate::bar::Hybar;
+
+const TITLE_ICON_SIZE: i32 = 16;
+
 impl Hybar {
     pub fn title_changed(&self, title: &str) {
         let widgets_builder = self.widgets.borrow();
-        widgets_builder.widgets.title.set_title(title);
-
-        let client_name = title.split(",").next().unwrap_or("");
-        let parent = &widgets_builder.widgets.apps;
-
-        let widget = find_widget_child(parent, client_name);
+        let (class, title) = title.split_once(',').unwrap_or(("", title));
+        widgets_builder.widgets.title.set_title(class, title);
 
-        if let Some(widget) = widget {
-            widget.grab_focus();
-        }
+        let icon = Some(class)
+            .filter(|class| widgets_builder.title_icons() && !class.is_empty())
+            .map(|class| {
+                let scale = widgets_builder.widgets.title.widget().scale_factor();
+                widgets_builder.icons.widget(class, TITLE_ICON_SIZE, scale)
+            });
+        widgets_builder.widgets.title.set_icon(icon);
     }
 }
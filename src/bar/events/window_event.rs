@@ -1,57 +1,76 @@
-use crate::bar::{Hybar, find_widget_child};
+use crate::{
+    bar::{Hybar, find_widget_child},
+    ui::widgets::{apps::RECENT_CLASS, workspaces::get_active_workspace},
+};
 use gtk::prelude::*;
 
 impl Hybar {
-    pub fn window_opened(&self, _id: &str, name: &str) {
-        let mut widgets_builder = self.widgets.borrow_mut();
-        widgets_builder.update_active_clients();
-        let parent = &widgets_builder.widgets.apps;
-        let widget = find_widget_child(parent, name);
+    pub fn window_opened(&self, id: &str, name: &str) {
+        {
+            let address = format!("0x{}", id);
+            let widgets_builder = self.widgets.borrow();
+            widgets_builder.update_active_clients();
+
+            let apps = &widgets_builder.widgets.apps;
+            let clients = widgets_builder.get_active_clients();
+            let client = clients.iter().find(|client| client.address == address);
+
+            let dock_state = &widgets_builder.dock_state;
+            for app_name in dock_state.launching_apps() {
+                let opened = match client {
+                    Some(client) => widgets_builder.app_matcher.matches(client, &app_name),
+                    None => app_name.eq_ignore_ascii_case(name),
+                };
+                if opened {
+                    dock_state.finish_launch(&app_name);
+                }
+            }
 
-        match widget {
-            Some(w) => w.add_css_class("opened"),
-            None => widgets_builder.create_widget_app(name, true),
+            let has_button = match client {
+                Some(client) => dock_buttons(apps).iter().any(|button| {
+                    widgets_builder
+                        .app_matcher
+                        .matches(client, &button.widget_name())
+                }),
+                None => find_widget_child(apps, name).is_some(),
+            };
+
+            if !has_button {
+                widgets_builder.create_widget_app(name);
+            }
+            widgets_builder.refresh_dock();
         }
-        widgets_builder.widgets.workspaces.update_previews();
+
+        self.update_previews();
     }
 
     pub fn window_closed(&self, id: &str) {
         {
             let address = format!("0x{}", id);
             let widgets_builder = self.widgets.borrow();
-
-            let clients = &widgets_builder.get_active_clients();
-            let find_client = clients.iter().find(|client| client.address == address);
-
             widgets_builder.update_active_clients();
-            let client = match find_client {
-                Some(client) => {
-                    let filtered_clients: Vec<_> =
-                        clients.iter().filter(|c| c.class == client.class).collect();
-                    if filtered_clients.len() > 1 {
-                        return;
-                    };
-                    client
-                }
-                None => return,
-            };
-            let apps = &widgets_builder.widgets.apps;
-
-            let widget = find_widget_child(apps, &client.class.to_lowercase());
-            if let Some(widget) = widget {
-                widget.remove_css_class("opened");
-                apps.grab_focus();
+            widgets_builder
+                .dock_state
+                .urgent
+                .borrow_mut()
+                .remove(&address);
+            widgets_builder.refresh_dock();
 
-                let is_favorite = self
-                    .preferences
-                    .borrow()
+            let preferences = self.preferences.borrow();
+            let clients = widgets_builder.get_active_clients();
+            for button in dock_buttons(&widgets_builder.widgets.apps) {
+                let name = button.widget_name();
+                let is_favorite = preferences
                     .favorites
                     .iter()
-                    .any(|fav| fav == &client.class);
+                    .any(|fav| fav.eq_ignore_ascii_case(&name))
+                    || button.has_css_class(RECENT_CLASS);
+                let has_windows = clients
+                    .iter()
+                    .any(|client| widgets_builder.app_matcher.matches(client, &name));
 
-                if !is_favorite {
-                    let widgets_builder = self.widgets.borrow();
-                    widgets_builder.remove_widget_app(&widget);
+                if !is_favorite && !has_windows {
+                    widgets_builder.remove_widget_app(&button);
                 }
             }
         }
@@ -59,6 +78,58 @@ impl Hybar {
         self.update_previews();
     }
 
+    pub fn window_urgent(&self, id: &str) {
+        let mut widgets_builder = self.widgets.borrow_mut();
+        widgets_builder
+            .widgets
+            .workspaces
+            .update(Some(id.to_string()));
+
+        let address = format!("0x{}", id);
+        let dock_state = &widgets_builder.dock_state;
+        if dock_state.active_window.borrow().as_deref() != Some(address.as_str()) {
+            dock_state.urgent.borrow_mut().insert(address);
+        }
+        widgets_builder.refresh_dock();
+    }
+
+    pub fn active_window_changed(&self, id: &str) {
+        let widgets_builder = self.widgets.borrow();
+        let address = (!id.is_empty()).then(|| format!("0x{}", id));
+
+        if let Some(address) = &address {
+            widgets_builder
+                .dock_state
+                .urgent
+                .borrow_mut()
+                .remove(address);
+
+            // Single instance apps only focus their existing window.
+            let clients = widgets_builder.get_active_clients();
+            if let Some(client) = clients.iter().find(|client| &client.address == address) {
+                let dock_state = &widgets_builder.dock_state;
+                for app_name in dock_state.launching_apps() {
+                    if widgets_builder.app_matcher.matches(client, &app_name) {
+                        dock_state.finish_launch(&app_name);
+                    }
+                }
+            }
+        }
+        *widgets_builder.dock_state.active_window.borrow_mut() = address;
+        widgets_builder.refresh_dock();
+    }
+
+    pub fn workspace_changed(&self) {
+        let mut widgets_builder = self.widgets.borrow_mut();
+        widgets_builder.widgets.workspaces.update(None);
+        widgets_builder
+            .dock_state
+            .active_workspace
+            .set(get_active_workspace().map(|ws| ws.id as i64));
+        widgets_builder.update_active_clients();
+        widgets_builder.refresh_dock();
+    }
+
     fn update_previews(&self) {
         self.widgets
             .borrow_mut()
@@ -67,3 +138,13 @@ impl Hybar {
             .update_previews();
     }
 }
+
+fn dock_buttons(apps: &gtk::Widget) -> Vec<gtk::Widget> {
+    let mut buttons = Vec::new();
+    let mut child = apps.first_child();
+    while let Some(button) = child {
+        child = button.next_sibling();
+        buttons.push(button);
+    }
+    buttons
+}
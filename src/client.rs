@@ -14,6 +14,7 @@ struct States {
     is_fullscreen: bool,
     has_fullscreen_update: bool,
     latest_title: Option<String>,
+    active_window: Option<String>,
     has_workspace_urgent: Option<String>,
     has_open_window: Option<(String, String)>,
     has_close_window: Option<String>,
@@ -35,6 +36,7 @@ impl HyprlandClient {
                 is_fullscreen: false,
                 has_fullscreen_update: false,
                 latest_title: None,
+                active_window: None,
                 has_workspace_urgent: None,
                 has_open_window: None,
                 has_close_window: None,
@@ -108,7 +110,7 @@ impl HyprlandClient {
         let event_value = line.next().unwrap_or("").trim();
 
         match event_name {
-            "change" | "workspace" => {
+            "change" | "workspace" | "movewindow" => {
                 self.states.has_workspace_update = true;
             }
             "fullscreen" => {
@@ -122,7 +124,10 @@ impl HyprlandClient {
                     title = "".to_string();
                 }
 
-                self.states.latest_title = Some(title.to_lowercase());
+                self.states.latest_title = Some(title);
+            }
+            "activewindowv2" => {
+                self.states.active_window = Some(event_value.to_string());
             }
             "urgent" => {
                 let urgent_id = event_value.trim().to_string();
@@ -190,6 +195,14 @@ impl HyprlandClient {
             needs_gtk_update = true;
         }
 
+        if let Some(address) = self.states.active_window.take() {
+            self.sender
+                .send(UiEvent::ActiveWindowChanged(address))
+                .await
+                .ok();
+            needs_gtk_update = true;
+        }
+
         if let Some(urgent_id) = self.states.has_workspace_urgent.take() {
             self.sender
                 .send(UiEvent::WorkspaceUrgent(urgent_id.clone()))
@@ -9,9 +9,43 @@ pub enum BarWidget {
     Playback,
     Settings,
     Shutdown,
+    Launcher,
+    Battery,
+    Cpu,
+    Memory,
+    Load,
+    Network,
+    Volume,
+    Backlight,
     Custom(String),
 }
 
+impl BarWidget {
+    // The key of the widget's options under `widgets`, or `custom_apps` for
+    // custom widgets.
+    pub fn config_name(&self) -> &str {
+        match self {
+            BarWidget::Workspaces => "workspaces",
+            BarWidget::Time => "clock",
+            BarWidget::Separator => "separator",
+            BarWidget::AppTitle => "title",
+            BarWidget::Apps => "apps",
+            BarWidget::Playback => "player",
+            BarWidget::Settings => "settings",
+            BarWidget::Shutdown => "shutdown",
+            BarWidget::Launcher => "launcher",
+            BarWidget::Battery => "battery",
+            BarWidget::Cpu => "cpu",
+            BarWidget::Memory => "memory",
+            BarWidget::Load => "load",
+            BarWidget::Network => "network",
+            BarWidget::Volume => "volume",
+            BarWidget::Backlight => "backlight",
+            BarWidget::Custom(name) => name,
+        }
+    }
+}
+
 impl FromStr for BarWidget {
     type Err = ();
 
@@ -25,8 +59,245 @@ impl FromStr for BarWidget {
             "playback" | "player" => Ok(BarWidget::Playback),
             "settings" => Ok(BarWidget::Settings),
             "shutdown" => Ok(BarWidget::Shutdown),
+            "launcher" => Ok(BarWidget::Launcher),
+            "battery" => Ok(BarWidget::Battery),
+            "cpu" => Ok(BarWidget::Cpu),
+            "memory" | "ram" => Ok(BarWidget::Memory),
+            "load" | "loadavg" => Ok(BarWidget::Load),
+            "network" | "net" => Ok(BarWidget::Network),
+            "volume" | "audio" | "pulseaudio" => Ok(BarWidget::Volume),
+            "backlight" | "brightness" => Ok(BarWidget::Backlight),
             "custom" => Ok(BarWidget::Custom(s.to_string())),
             _ => Err(()),
         }
     }
 }
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum CycleScope {
+    #[default]
+    All,
+    Workspace,
+}
+
+impl FromStr for CycleScope {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "all" => Ok(CycleScope::All),
+            "workspace" | "current" => Ok(CycleScope::Workspace),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub enum DockIndicator {
+    #[default]
+    Dots,
+    Number,
+    None,
+}
+
+impl FromStr for DockIndicator {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dots" => Ok(DockIndicator::Dots),
+            "number" | "count" => Ok(DockIndicator::Number),
+            "none" => Ok(DockIndicator::None),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LaunchStrategy {
+    #[default]
+    Direct,
+    Hyprctl,
+    Systemd,
+    Uwsm,
+}
+
+impl FromStr for LaunchStrategy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "direct" => Ok(LaunchStrategy::Direct),
+            "hyprctl" | "hyprland" => Ok(LaunchStrategy::Hyprctl),
+            "systemd" | "systemd-run" | "scope" => Ok(LaunchStrategy::Systemd),
+            "uwsm" => Ok(LaunchStrategy::Uwsm),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CustomKind {
+    #[default]
+    Button,
+    Script,
+    Stream,
+}
+
+impl FromStr for CustomKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "button" | "app" => Ok(CustomKind::Button),
+            "script" | "poll" => Ok(CustomKind::Script),
+            "stream" | "continuous" => Ok(CustomKind::Stream),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum GraphStyle {
+    #[default]
+    Line,
+    Bars,
+    Blocks,
+}
+
+impl FromStr for GraphStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "line" | "sparkline" => Ok(GraphStyle::Line),
+            "bars" | "bar" => Ok(GraphStyle::Bars),
+            "blocks" | "block" => Ok(GraphStyle::Blocks),
+            _ => Err(()),
+        }
+    }
+}
+
+impl GraphStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphStyle::Line => "line",
+            GraphStyle::Bars => "bars",
+            GraphStyle::Blocks => "blocks",
+        }
+    }
+}
+
+// What an OSD reports, which decides its icon.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OsdKind {
+    Volume,
+    Microphone,
+    Brightness,
+}
+
+impl FromStr for OsdKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "volume" | "audio" | "sound" => Ok(OsdKind::Volume),
+            "microphone" | "mic" | "input" => Ok(OsdKind::Microphone),
+            "brightness" | "backlight" => Ok(OsdKind::Brightness),
+            _ => Err(()),
+        }
+    }
+}
+
+impl OsdKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OsdKind::Volume => "volume",
+            OsdKind::Microphone => "microphone",
+            OsdKind::Brightness => "brightness",
+        }
+    }
+
+    // Nerd font glyphs for the value in percent.
+    pub fn icon(&self, value: f64, muted: bool) -> &'static str {
+        let levels = match self {
+            OsdKind::Volume if muted => return "󰖁",
+            OsdKind::Volume => ["󰕿", "󰖀", "󰕾"],
+            OsdKind::Microphone if muted => return "󰍭",
+            OsdKind::Microphone => return "󰍬",
+            OsdKind::Brightness => ["󰃞", "󰃟", "󰃠"],
+        };
+        match value {
+            value if value < 34.0 => levels[0],
+            value if value < 67.0 => levels[1],
+            _ => levels[2],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OsdPosition {
+    Top,
+    #[default]
+    Bottom,
+    Left,
+    Right,
+    Center,
+}
+
+impl FromStr for OsdPosition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top" => Ok(OsdPosition::Top),
+            "bottom" => Ok(OsdPosition::Bottom),
+            "left" => Ok(OsdPosition::Left),
+            "right" => Ok(OsdPosition::Right),
+            "center" | "centre" => Ok(OsdPosition::Center),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MouseAction {
+    Command(String),
+    Dispatch(String),
+    // The widget's own click behavior, e.g. opening its panel.
+    Activate,
+    // The widget's panel where it has one besides its click behavior, like
+    // the volume mixer, otherwise the same as Activate.
+    Panel,
+    ToggleAlt,
+    // Steps a value such as the volume up or down.
+    Increase,
+    Decrease,
+    Reload,
+    Nothing,
+}
+
+impl FromStr for MouseAction {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(dispatcher) = s.strip_prefix("dispatch:") {
+            return Ok(MouseAction::Dispatch(dispatcher.trim().to_string()));
+        }
+        let Some(action) = s.strip_prefix("hybar:") else {
+            return Ok(MouseAction::Command(s.to_string()));
+        };
+
+        match action.trim().to_lowercase().as_str() {
+            "activate" => Ok(MouseAction::Activate),
+            "panel" | "open-panel" => Ok(MouseAction::Panel),
+            "alt" | "toggle-alt" | "format-alt" => Ok(MouseAction::ToggleAlt),
+            "increase" | "up" => Ok(MouseAction::Increase),
+            "decrease" | "down" => Ok(MouseAction::Decrease),
+            "reload" => Ok(MouseAction::Reload),
+            "none" | "" => Ok(MouseAction::Nothing),
+            _ => Err(()),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use panels::launcher::{LauncherAction, LauncherItem, traits::HasLauncherEvent};
+
+use crate::utils::{app_launch::AppLauncher, icons::IconResolver, search::DesktopIndex};
+
+#[derive(Clone)]
+pub struct DesktopLauncher {
+    pub index: DesktopIndex,
+    pub launcher: AppLauncher,
+    pub icons: IconResolver,
+}
+
+impl HasLauncherEvent for DesktopLauncher {
+    fn entries(&self) -> Vec<LauncherItem> {
+        self.index
+            .entries()
+            .into_iter()
+            .map(|entry| LauncherItem {
+                frecency: self.launcher.history().frecency(&entry.id),
+                id: entry.id,
+                name: entry.name,
+                generic_name: entry.generic_name,
+                comment: entry.comment,
+                keywords: entry.keywords,
+                categories: entry.categories,
+                actions: entry
+                    .actions
+                    .into_iter()
+                    .map(|action| LauncherAction {
+                        id: action.id,
+                        name: action.name,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn icon(&self, item: &LauncherItem, size: i32, scale: i32) -> gtk::Widget {
+        self.icons.widget(&item.id, size, scale)
+    }
+
+    fn launch(
+        &self,
+        id: &str,
+        action: Option<&str>,
+        token: Option<String>,
+        on_error: Box<dyn FnOnce(String)>,
+    ) {
+        let Some(entry) = self.index.find(id) else {
+            on_error(format!("{} is no longer installed", id));
+            return;
+        };
+
+        let on_error = move |e: crate::utils::app_launch::LaunchError| {
+            eprintln!("{}", e);
+            on_error(e.to_string());
+        };
+        match action {
+            Some(action) => self.launcher.launch_action(&entry, action, token, on_error),
+            None => self.launcher.launch(&entry, token, on_error),
+        }
+    }
+}
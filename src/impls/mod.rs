@@ -1 +1,2 @@
+pub mod launcher;
 pub mod settings;
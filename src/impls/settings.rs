@@ -41,4 +41,10 @@ impl HasSettingsEvent for UiEventState {
     fn get_bar_position(&self) -> String {
         self.preferences.bar_position.clone()
     }
+
+    fn clear_history(&self) {
+        self.sender
+            .try_send(UiEvent::ClearHistory)
+            .unwrap_or_else(|e| eprintln!("Failed to send clear history event: {}", e));
+    }
 }
@@ -4,12 +4,16 @@ mod config;
 mod enums;
 mod impls;
 mod models;
+mod remote;
 mod ui;
 mod user;
 mod utils;
 
 use bar::{BarPreferences, Hybar};
-use gtk::{Application, prelude::*};
+use gtk::{Application, gio, prelude::*};
+use remote::RemoteCommand;
+use std::{cell::RefCell, rc::Rc};
+use utils::launcher_entry::LauncherEntry;
 
 pub const BACKGROUND_COLOR: &str = "#1a202c";
 const HYPRLAND_SUBSCRIPTION: &str = r#"["subscribe", ["workspace", "fullscreen"]]"#;
@@ -20,10 +24,16 @@ pub enum UiEvent {
     WorkspaceUrgent(String),
     FullscreenChanged(bool),
     TitleChanged(String),
+    ActiveWindowChanged(String),
     ReloadSettings,
     WindowOpened((String, String)),
     WindowClosed(String),
+    DockPinChanged((String, bool)),
+    DockChanged,
+    LauncherEntryUpdated((String, LauncherEntry)),
     ThemeChanged(String),
+    ClearHistory,
+    Remote(RemoteCommand),
     PreferencesChanged(PreferencesEvent),
 }
 
@@ -55,11 +65,54 @@ impl EventState {
 
 #[tokio::main]
 async fn main() {
-    let app = Application::builder().application_id("com.hybar").build();
+    // Arguments are checked here so mistakes are reported by the invoking
+    // process, even when the primary instance handles the command.
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Err(e) = remote::parse(&args) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let app = Application::builder()
+        .application_id("com.hybar")
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
+        .build();
 
-    app.connect_activate(|app| {
-        let hybar = Hybar::new(app);
-        hybar.build()
+    // Only the primary instance builds the bar; later invocations forward their
+    // arguments to it as remote commands.
+    let running: Rc<RefCell<Option<async_channel::Sender<UiEvent>>>> = Rc::default();
+    app.connect_command_line(move |app, command_line| {
+        let args = command_line
+            .arguments()
+            .iter()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        let sender = running.borrow().clone();
+
+        match (remote::parse(&args), sender) {
+            (Err(e), _) => {
+                eprintln!("{}", e);
+                1
+            }
+            (Ok(None), Some(_)) => 0,
+            (Ok(None), None) => {
+                let hybar = Hybar::new(app);
+                *running.borrow_mut() = Some(hybar.sender());
+                hybar.build();
+                0
+            }
+            (Ok(Some(command)), Some(sender)) => {
+                sender
+                    .try_send(UiEvent::Remote(command))
+                    .unwrap_or_else(|e| eprintln!("Failed to send remote command: {}", e));
+                0
+            }
+            (Ok(Some(_)), None) => {
+                eprintln!("hybar is not running");
+                1
+            }
+        }
     });
 
     app.run();
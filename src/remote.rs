@@ -0,0 +1,94 @@
+// Commands forwarded to the running bar, e.g. `hybar refresh vpn`. A second
+// hybar process hands its arguments to the primary instance over D-Bus.
+use crate::enums::widgets::OsdKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteCommand {
+    Refresh(String),
+    // `hybar osd volume 40 [muted]`, for keybinds that change a value
+    // outside of the bar.
+    Osd {
+        kind: OsdKind,
+        value: f64,
+        muted: bool,
+    },
+}
+
+pub const USAGE: &str =
+    "Usage: hybar [refresh <widget> | osd <volume|microphone|brightness> <percent> [muted]]";
+
+pub fn parse(args: &[String]) -> Result<Option<RemoteCommand>, String> {
+    let mut args = args.iter().map(String::as_str).peekable();
+    let Some(command) = args.next() else {
+        return Ok(None);
+    };
+
+    let command = match (command, args.next()) {
+        ("refresh", Some(name)) => RemoteCommand::Refresh(name.to_string()),
+        ("osd", Some(kind)) => {
+            let kind = kind
+                .parse::<OsdKind>()
+                .map_err(|_| format!("Unknown OSD: {}\n{}", kind, USAGE))?;
+            let value = args.next().unwrap_or_default();
+            let value = value
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("Invalid OSD value: {}\n{}", value, USAGE))?;
+            let muted = args.next_if_eq(&"muted").is_some();
+            RemoteCommand::Osd { kind, value, muted }
+        }
+        ("help" | "--help" | "-h", _) => return Err(USAGE.to_string()),
+        _ => return Err(format!("Unknown command: {}\n{}", command, USAGE)),
+    };
+
+    match args.next() {
+        Some(extra) => Err(format!("Unexpected argument: {}\n{}", extra, USAGE)),
+        None => Ok(Some(command)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_reads_refresh_commands() {
+        assert_eq!(parse(&[]), Ok(None));
+        assert_eq!(
+            parse(&args(&["refresh", "vpn"])),
+            Ok(Some(RemoteCommand::Refresh("vpn".to_string())))
+        );
+        assert!(parse(&args(&["refresh"])).is_err());
+        assert!(parse(&args(&["refresh", "vpn", "now"])).is_err());
+        assert!(parse(&args(&["reload"])).is_err());
+    }
+
+    #[test]
+    fn parse_reads_osd_commands() {
+        assert_eq!(
+            parse(&args(&["osd", "volume", "40"])),
+            Ok(Some(RemoteCommand::Osd {
+                kind: OsdKind::Volume,
+                value: 40.0,
+                muted: false,
+            }))
+        );
+        assert_eq!(
+            parse(&args(&["osd", "mic", "0", "muted"])),
+            Ok(Some(RemoteCommand::Osd {
+                kind: OsdKind::Microphone,
+                value: 0.0,
+                muted: true,
+            }))
+        );
+        assert!(parse(&args(&["osd", "volume"])).is_err());
+        assert!(parse(&args(&["osd", "volume", "loud"])).is_err());
+        assert!(parse(&args(&["osd", "keyboard", "40"])).is_err());
+        assert!(parse(&args(&["osd", "volume", "40", "now"])).is_err());
+    }
+}
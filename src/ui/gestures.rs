@@ -0,0 +1,263 @@
+use gtk::{EventControllerScroll, EventControllerScrollFlags, GestureClick, gdk, glib, prelude::*};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use crate::{
+    UiEvent,
+    enums::widgets::MouseAction,
+    user::models::MouseConfig,
+    utils::{clients, format::Format, script},
+};
+
+const DEFAULT_DOUBLE_CLICK_MS: i32 = 400;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MouseBindings {
+    pub click: Option<MouseAction>,
+    pub middle_click: Option<MouseAction>,
+    pub right_click: Option<MouseAction>,
+    pub double_click: Option<MouseAction>,
+    pub scroll_up: Option<MouseAction>,
+    pub scroll_down: Option<MouseAction>,
+}
+
+impl MouseBindings {
+    // Without configuration a click activates the widget and a right click
+    // switches to its alt format, or opens the panel of widgets that have a
+    // separate one. Widgets with a value scroll through it.
+    pub fn new(config: Option<&MouseConfig>, actions: &WidgetActions) -> Self {
+        let binding = |value: Option<&String>| {
+            value.and_then(|value| {
+                value
+                    .parse::<MouseAction>()
+                    .inspect_err(|_| eprintln!("Unknown mouse action: {}", value))
+                    .ok()
+            })
+        };
+        let config = config.cloned().unwrap_or_default();
+        let right_click = match actions.panel {
+            Some(_) => MouseAction::Panel,
+            None => MouseAction::ToggleAlt,
+        };
+        let (scroll_up, scroll_down) = match (&actions.increase, &actions.decrease) {
+            (Some(_), Some(_)) => (Some(MouseAction::Increase), Some(MouseAction::Decrease)),
+            _ => (None, None),
+        };
+
+        Self {
+            click: binding(config.on_click.as_ref()).or(Some(MouseAction::Activate)),
+            middle_click: binding(config.on_click_middle.as_ref()),
+            right_click: binding(config.on_click_right.as_ref()).or(Some(right_click)),
+            double_click: binding(config.on_double_click.as_ref()),
+            scroll_up: binding(config.on_scroll_up.as_ref()).or(scroll_up),
+            scroll_down: binding(config.on_scroll_down.as_ref()).or(scroll_down),
+        }
+    }
+}
+
+// What the internal actions do for a given widget. Missing ones are no-ops.
+#[derive(Clone, Default)]
+pub struct WidgetActions {
+    pub activate: Option<Rc<dyn Fn()>>,
+    pub toggle_alt: Option<Rc<dyn Fn()>>,
+    pub panel: Option<Rc<dyn Fn()>>,
+    pub increase: Option<Rc<dyn Fn()>>,
+    pub decrease: Option<Rc<dyn Fn()>>,
+    // Runs after a bound command, e.g. so a script widget shows its effect.
+    pub after_command: Option<Rc<dyn Fn()>>,
+}
+
+impl WidgetActions {
+    pub fn activate(activate: impl Fn() + 'static) -> Self {
+        Self {
+            activate: Some(Rc::new(activate)),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_alt(mut self, format: &Format, redraw: impl Fn() + 'static) -> Self {
+        let format = format.clone();
+        self.toggle_alt = Some(Rc::new(move || {
+            if format.toggle_alt() {
+                redraw();
+            }
+        }));
+        self
+    }
+
+    pub fn with_panel(mut self, panel: impl Fn() + 'static) -> Self {
+        self.panel = Some(Rc::new(panel));
+        self
+    }
+
+    pub fn with_steps(
+        mut self,
+        increase: impl Fn() + 'static,
+        decrease: impl Fn() + 'static,
+    ) -> Self {
+        self.increase = Some(Rc::new(increase));
+        self.decrease = Some(Rc::new(decrease));
+        self
+    }
+}
+
+pub fn attach(
+    widget: &impl IsA<gtk::Widget>,
+    bindings: MouseBindings,
+    actions: WidgetActions,
+    sender: async_channel::Sender<UiEvent>,
+) {
+    let run = Rc::new(move |action: &MouseAction| run_action(action, &actions, &sender));
+
+    let gesture = GestureClick::new();
+    gesture.set_button(0);
+    // Buttons claim presses in their own gesture, so bindings have to see
+    // them first. Other widgets leave clicks to children that handle them,
+    // like workspace labels.
+    if widget.is::<gtk::Button>() {
+        gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
+    }
+
+    let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::default();
+    let bindings = Rc::new(bindings);
+    gesture.connect_released({
+        let (bindings, run) = (Rc::clone(&bindings), Rc::clone(&run));
+        move |gesture, n_press, _, _| {
+            let action = match gesture.current_button() {
+                gdk::BUTTON_PRIMARY => &bindings.click,
+                gdk::BUTTON_MIDDLE => &bindings.middle_click,
+                gdk::BUTTON_SECONDARY => &bindings.right_click,
+                _ => return,
+            };
+            if gesture.current_button() != gdk::BUTTON_PRIMARY || bindings.double_click.is_none() {
+                if let Some(action) = action {
+                    run(action);
+                }
+                return;
+            }
+
+            // With a double click binding the single click waits until it
+            // can no longer become a double one.
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+            if n_press >= 2 {
+                if let Some(action) = &bindings.double_click {
+                    run(action);
+                }
+                return;
+            }
+            let Some(action) = action.clone() else {
+                return;
+            };
+            let (run, cleared) = (Rc::clone(&run), Rc::clone(&pending));
+            let source = glib::timeout_add_local_once(double_click_time(), move || {
+                cleared.borrow_mut().take();
+                run(&action);
+            });
+            *pending.borrow_mut() = Some(source);
+        }
+    });
+    widget.add_controller(gesture);
+
+    if bindings.scroll_up.is_none() && bindings.scroll_down.is_none() {
+        return;
+    }
+    let scroll = EventControllerScroll::new(
+        EventControllerScrollFlags::VERTICAL | EventControllerScrollFlags::DISCRETE,
+    );
+    scroll.connect_scroll(move |_, _, dy| {
+        let action = match dy {
+            dy if dy < 0.0 => &bindings.scroll_up,
+            dy if dy > 0.0 => &bindings.scroll_down,
+            _ => &None,
+        };
+        let Some(action) = action else {
+            return glib::Propagation::Proceed;
+        };
+        run(action);
+        glib::Propagation::Stop
+    });
+    widget.add_controller(scroll);
+}
+
+fn run_action(
+    action: &MouseAction,
+    actions: &WidgetActions,
+    sender: &async_channel::Sender<UiEvent>,
+) {
+    let callback = match action {
+        MouseAction::Command(command) => {
+            script::spawn(command);
+            &actions.after_command
+        }
+        MouseAction::Dispatch(dispatcher) => {
+            clients::dispatch(dispatcher);
+            &None
+        }
+        MouseAction::Activate => &actions.activate,
+        MouseAction::Panel => match actions.panel {
+            Some(_) => &actions.panel,
+            None => &actions.activate,
+        },
+        MouseAction::ToggleAlt => &actions.toggle_alt,
+        MouseAction::Increase => &actions.increase,
+        MouseAction::Decrease => &actions.decrease,
+        MouseAction::Reload => {
+            if let Err(e) = sender.try_send(UiEvent::ReloadSettings) {
+                eprintln!("Failed to reload: {}", e);
+            }
+            &None
+        }
+        MouseAction::Nothing => &None,
+    };
+    if let Some(callback) = callback {
+        callback();
+    }
+}
+
+fn double_click_time() -> Duration {
+    let millis = gtk::Settings::default()
+        .map(|settings| settings.gtk_double_click_time())
+        .unwrap_or(DEFAULT_DOUBLE_CLICK_MS);
+    Duration::from_millis(millis.max(0) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_parse_actions_over_the_defaults() {
+        let config = MouseConfig {
+            on_click: Some("pavucontrol".to_string()),
+            on_click_right: Some("hybar:none".to_string()),
+            on_scroll_up: Some("dispatch: workspace e-1".to_string()),
+            on_scroll_down: Some("hybar:shout".to_string()),
+            ..Default::default()
+        };
+        let bindings = MouseBindings::new(Some(&config), &WidgetActions::default());
+
+        assert_eq!(
+            bindings.click,
+            Some(MouseAction::Command("pavucontrol".to_string()))
+        );
+        assert_eq!(bindings.right_click, Some(MouseAction::Nothing));
+        assert_eq!(
+            bindings.scroll_up,
+            Some(MouseAction::Dispatch("workspace e-1".to_string()))
+        );
+        assert_eq!(bindings.scroll_down, None);
+
+        let defaults = MouseBindings::new(None, &WidgetActions::default());
+        assert_eq!(defaults.click, Some(MouseAction::Activate));
+        assert_eq!(defaults.right_click, Some(MouseAction::ToggleAlt));
+        assert_eq!(defaults.scroll_up, None);
+
+        let actions = WidgetActions::activate(|| {})
+            .with_panel(|| {})
+            .with_steps(|| {}, || {});
+        let defaults = MouseBindings::new(None, &actions);
+        assert_eq!(defaults.right_click, Some(MouseAction::Panel));
+        assert_eq!(defaults.scroll_down, Some(MouseAction::Decrease));
+    }
+}
@@ -0,0 +1,274 @@
+use gtk::{cairo, gdk, glib, prelude::*};
+use std::{cell::RefCell, collections::VecDeque, rc::Rc};
+
+use crate::{enums::widgets::GraphStyle, user::models::WidgetsConfig};
+
+const DEFAULT_HISTORY: usize = 60;
+const INLINE_SIZE: (i32, i32) = (40, 16);
+const EXPANDED_SIZE: (i32, i32) = (240, 80);
+const FILL_ALPHA: f64 = 0.3;
+const GAP: f64 = 1.0;
+const MAX_BLOCK_ROWS: f64 = 8.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct History {
+    values: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(2);
+        Self {
+            values: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        if self.values.len() == self.capacity {
+            self.values.pop_front();
+        }
+        self.values.push_back(value);
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().copied().reduce(f64::max)
+    }
+
+    pub fn average(&self) -> Option<f64> {
+        match self.values.len() {
+            0 => None,
+            len => Some(self.values.iter().sum::<f64>() / len as f64),
+        }
+    }
+
+    // Samples scaled to 0..=1 against `max`, or against the largest sample
+    // for values without a natural ceiling such as throughput.
+    pub fn scaled(&self, max: Option<f64>) -> Vec<f64> {
+        let max = max.or_else(|| self.max()).filter(|max| *max > 0.0);
+        self.values
+            .iter()
+            .map(|value| max.map_or(0.0, |max| (value / max).clamp(0.0, 1.0)))
+            .collect()
+    }
+}
+
+// A history of one number, drawn by any number of views: the small graph in
+// the bar and the expanded one in the widget's popover. The color is the CSS
+// `color` of the `.graph` node.
+#[derive(Clone)]
+pub struct Graph {
+    history: Rc<RefCell<History>>,
+    style: Option<GraphStyle>,
+    max: Option<f64>,
+    label: fn(f64) -> String,
+    areas: Rc<RefCell<Vec<glib::WeakRef<gtk::DrawingArea>>>>,
+    stats: Rc<RefCell<Vec<glib::WeakRef<gtk::Label>>>>,
+}
+
+impl Graph {
+    // `max` is the value drawn at full height, None scales to the history.
+    pub fn new(config: Option<&WidgetsConfig>, max: Option<f64>, label: fn(f64) -> String) -> Self {
+        let style = config.and_then(|c| c.graph.as_deref()).and_then(|style| {
+            style
+                .parse::<GraphStyle>()
+                .inspect_err(|_| eprintln!("Unknown graph style: {}", style))
+                .ok()
+        });
+        let history = config.and_then(|c| c.history).unwrap_or(DEFAULT_HISTORY);
+
+        Self {
+            history: Rc::new(RefCell::new(History::new(history))),
+            style,
+            max,
+            label,
+            areas: Rc::default(),
+            stats: Rc::default(),
+        }
+    }
+
+    pub fn push(&self, value: f64) {
+        self.history.borrow_mut().push(value);
+
+        self.areas.borrow_mut().retain(|area| match area.upgrade() {
+            Some(area) => {
+                area.queue_draw();
+                true
+            }
+            None => false,
+        });
+        let stats = self.stats_text();
+        self.stats
+            .borrow_mut()
+            .retain(|label| match label.upgrade() {
+                Some(label) => {
+                    label.set_text(&stats);
+                    true
+                }
+                None => false,
+            });
+    }
+
+    // The graph shown next to the value, only when the config asks for one.
+    pub fn inline(&self) -> Option<gtk::Widget> {
+        let style = self.style?;
+        Some(self.area(style, INLINE_SIZE).into())
+    }
+
+    // The whole history with its range, for the widget's popover.
+    pub fn expanded(&self) -> gtk::Widget {
+        let container = gtk::Box::new(gtk::Orientation::Vertical, 6);
+        container.add_css_class("graph-expanded");
+
+        let stats = gtk::Label::new(Some(&self.stats_text()));
+        stats.add_css_class("graph-stats");
+        stats.set_halign(gtk::Align::Start);
+        self.stats.borrow_mut().push(stats.downgrade());
+
+        container.append(&self.area(self.style.unwrap_or_default(), EXPANDED_SIZE));
+        container.append(&stats);
+        container.into()
+    }
+
+    fn area(&self, style: GraphStyle, (width, height): (i32, i32)) -> gtk::DrawingArea {
+        let area = gtk::DrawingArea::builder()
+            .content_width(width)
+            .content_height(height)
+            .valign(gtk::Align::Center)
+            .build();
+        area.add_css_class("graph");
+        area.add_css_class(style.as_str());
+
+        let (history, max) = (Rc::clone(&self.history), self.max);
+        area.set_draw_func(move |area, cr, width, height| {
+            #[allow(deprecated)]
+            let color = area.style_context().color();
+            let history = history.borrow();
+            let size = (width as f64, height as f64);
+            if let Err(e) = draw(
+                cr,
+                style,
+                &history.scaled(max),
+                history.capacity,
+                size,
+                &color,
+            ) {
+                eprintln!("Failed to draw graph: {}", e);
+            }
+        });
+        self.areas.borrow_mut().push(area.downgrade());
+        area
+    }
+
+    fn stats_text(&self) -> String {
+        let history = self.history.borrow();
+        match (history.min(), history.average(), history.max()) {
+            (Some(min), Some(average), Some(max)) => format!(
+                "min {} · avg {} · max {}",
+                (self.label)(min),
+                (self.label)(average),
+                (self.label)(max)
+            ),
+            _ => "No samples yet".to_string(),
+        }
+    }
+}
+
+// Newest sample on the right, so a history that is not full yet grows in
+// from that side.
+fn draw(
+    cr: &cairo::Context,
+    style: GraphStyle,
+    values: &[f64],
+    capacity: usize,
+    (width, height): (f64, f64),
+    color: &gdk::RGBA,
+) -> Result<(), cairo::Error> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let step = width / capacity as f64;
+    let x = |index: usize| width - (values.len() - index) as f64 * step;
+    let (red, green, blue, alpha) = (
+        color.red() as f64,
+        color.green() as f64,
+        color.blue() as f64,
+        color.alpha() as f64,
+    );
+    cr.set_source_rgba(red, green, blue, alpha);
+
+    match style {
+        GraphStyle::Line => {
+            let point = |index: usize| (x(index) + step / 2.0, height - values[index] * height);
+            let (first, last) = (point(0), point(values.len() - 1));
+
+            cr.move_to(first.0, height);
+            for index in 0..values.len() {
+                let (x, y) = point(index);
+                cr.line_to(x, y);
+            }
+            cr.line_to(last.0, height);
+            cr.close_path();
+            cr.set_source_rgba(red, green, blue, alpha * FILL_ALPHA);
+            cr.fill()?;
+
+            cr.move_to(first.0, first.1);
+            for index in 1..values.len() {
+                let (x, y) = point(index);
+                cr.line_to(x, y);
+            }
+            cr.set_source_rgba(red, green, blue, alpha);
+            cr.set_line_width(1.0);
+            cr.stroke()?;
+        }
+        GraphStyle::Bars => {
+            let bar = (step - GAP).max(1.0);
+            for (index, value) in values.iter().enumerate() {
+                cr.rectangle(x(index), height - value * height, bar, value * height);
+            }
+            cr.fill()?;
+        }
+        GraphStyle::Blocks => {
+            // Square-ish cells, but never so many that they blur together.
+            let rows = (height / step.max(1.0)).floor().clamp(1.0, MAX_BLOCK_ROWS);
+            let cell = height / rows;
+            let bar = (step - GAP).max(1.0);
+            for (index, value) in values.iter().enumerate() {
+                for row in 0..(value * rows).round() as u32 {
+                    let top = height - (row + 1) as f64 * cell;
+                    cr.rectangle(x(index), top + GAP, bar, (cell - GAP).max(1.0));
+                }
+            }
+            cr.fill()?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_keeps_the_latest_samples() {
+        let mut history = History::new(3);
+        assert_eq!(history.average(), None);
+        for value in [10.0, 50.0, 20.0, 40.0] {
+            history.push(value);
+        }
+
+        assert_eq!(history.min(), Some(20.0));
+        assert_eq!(history.max(), Some(50.0));
+        assert_eq!(history.average(), Some(110.0 / 3.0));
+        assert_eq!(history.scaled(Some(100.0)), vec![0.5, 0.2, 0.4]);
+        assert_eq!(history.scaled(None), vec![1.0, 0.4, 0.8]);
+
+        history.push(250.0);
+        assert_eq!(history.scaled(Some(100.0))[2], 1.0);
+    }
+}
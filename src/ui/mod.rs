@@ -1,3 +1,6 @@
+pub mod gestures;
+pub mod graph;
+pub mod osd;
 pub mod sections;
 pub mod widgets;
 pub mod windows;
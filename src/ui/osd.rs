@@ -0,0 +1,160 @@
+use gtk::{glib, prelude::*};
+use gtk4_layer_shell::{Edge, KeyboardMode, Layer, LayerShell};
+use std::{cell::RefCell, rc::Rc, time::Duration};
+
+use crate::{
+    enums::widgets::{OsdKind, OsdPosition},
+    utils::css::theme_variable,
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(1500);
+const DEFAULT_MARGIN: i32 = 80;
+const KINDS: [OsdKind; 3] = [OsdKind::Volume, OsdKind::Microphone, OsdKind::Brightness];
+
+// The transient window that reports a volume or brightness change. Where it
+// shows and for how long come from the theme: `--osd-position`,
+// `--osd-margin` and `--osd-timeout` on any rule.
+#[derive(Clone)]
+pub struct Osd {
+    window: gtk::ApplicationWindow,
+    icon: gtk::Label,
+    bar: gtk::ProgressBar,
+    label: gtk::Label,
+    hide: Rc<RefCell<Option<glib::SourceId>>>,
+}
+
+impl Osd {
+    pub fn new() -> Self {
+        let window = gtk::ApplicationWindow::builder().title("OSD").build();
+        LayerShell::init_layer_shell(&window);
+        window.set_layer(Layer::Overlay);
+        window.set_keyboard_mode(KeyboardMode::None);
+        window.set_namespace(Some("hybar:osd"));
+        window.add_css_class("osd-window");
+
+        let content = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        content.add_css_class("osd");
+        let icon = gtk::Label::new(None);
+        icon.add_css_class("osd-icon");
+        let bar = gtk::ProgressBar::new();
+        bar.add_css_class("osd-bar");
+        bar.set_hexpand(true);
+        bar.set_valign(gtk::Align::Center);
+        let label = gtk::Label::new(None);
+        label.add_css_class("osd-label");
+        label.set_width_chars(4);
+        content.append(&icon);
+        content.append(&bar);
+        content.append(&label);
+        window.set_child(Some(&content));
+
+        Self {
+            window,
+            icon,
+            bar,
+            label,
+            hide: Rc::default(),
+        }
+    }
+
+    // `value` is in percent. Showing it again while visible restarts the
+    // timeout, so holding a key keeps the OSD up.
+    pub fn show(&self, kind: OsdKind, value: f64, muted: bool) {
+        let value = value.clamp(0.0, 100.0);
+        self.icon.set_text(kind.icon(value, muted));
+        self.bar.set_fraction(value / 100.0);
+        self.label.set_text(&match muted {
+            true => "Muted".to_string(),
+            false => format!("{:.0}%", value),
+        });
+        for other in KINDS {
+            self.window.remove_css_class(other.as_str());
+        }
+        self.window.add_css_class(kind.as_str());
+        match muted {
+            true => self.window.add_css_class("muted"),
+            false => self.window.remove_css_class("muted"),
+        }
+
+        self.place();
+        self.window.present();
+
+        if let Some(source) = self.hide.borrow_mut().take() {
+            source.remove();
+        }
+        let timeout = theme_variable("osd-timeout")
+            .and_then(|timeout| {
+                let parsed = parse_duration(&timeout);
+                if parsed.is_none() {
+                    eprintln!("Invalid OSD timeout: {}", timeout);
+                }
+                parsed
+            })
+            .unwrap_or(DEFAULT_TIMEOUT);
+        let (window, hide) = (self.window.clone(), Rc::clone(&self.hide));
+        let source = glib::timeout_add_local_once(timeout, move || {
+            hide.borrow_mut().take();
+            window.hide();
+        });
+        *self.hide.borrow_mut() = Some(source);
+    }
+
+    fn place(&self) {
+        let position = theme_variable("osd-position")
+            .and_then(|position| {
+                position
+                    .parse::<OsdPosition>()
+                    .inspect_err(|_| eprintln!("Unknown OSD position: {}", position))
+                    .ok()
+            })
+            .unwrap_or_default();
+        let margin = theme_variable("osd-margin")
+            .and_then(|margin| margin.trim_end_matches("px").trim().parse().ok())
+            .unwrap_or(DEFAULT_MARGIN);
+
+        let edge = match position {
+            OsdPosition::Top => Some(Edge::Top),
+            OsdPosition::Bottom => Some(Edge::Bottom),
+            OsdPosition::Left => Some(Edge::Left),
+            OsdPosition::Right => Some(Edge::Right),
+            OsdPosition::Center => None,
+        };
+        for other in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+            self.window.set_anchor(other, Some(other) == edge);
+            self.window.set_margin(other, 0);
+        }
+        if let Some(edge) = edge {
+            self.window.set_margin(edge, margin);
+        }
+    }
+}
+
+// "1500ms", "1.5s" or plain milliseconds.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Some(millis) = value.strip_suffix("ms") {
+        return millis.trim().parse().ok().map(Duration::from_millis);
+    }
+    if let Some(seconds) = value.strip_suffix('s') {
+        return seconds
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|seconds| *seconds >= 0.0)
+            .map(Duration::from_secs_f64);
+    }
+    value.parse().ok().map(Duration::from_millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_accepts_css_times() {
+        assert_eq!(parse_duration("1500ms"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_duration("800"), Some(Duration::from_millis(800)));
+        assert_eq!(parse_duration("soon"), None);
+    }
+}
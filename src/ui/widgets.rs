@@ -1,28 +1,60 @@
+pub mod apps;
+pub mod backlight;
+pub mod battery;
 pub mod clock;
+pub mod network;
+pub mod script;
 pub mod separator;
+pub mod system;
 pub mod title;
+pub mod volume;
 pub mod workspaces;
 
-use gtk::{Box as GtkBox, GestureClick, Image, gdk::Cursor, prelude::*};
+use gtk::{Box as GtkBox, GestureClick, gdk::Cursor, gio, prelude::*};
 use gtk4_layer_shell::LayerShell;
-use std::{cell::Cell, collections::HashSet, path::Path, rc::Rc, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{Notify, watch};
 
 use crate::{
     EventState, UiEventState,
-    bar::set_popover,
-    enums::widgets::BarWidget,
+    bar::{set_popover, show_error_popover},
+    enums::widgets::{BarWidget, CustomKind, CycleScope, DockIndicator, LaunchStrategy},
+    impls::launcher::DesktopLauncher,
     models::clients::Client,
-    user::models::{SectionsConfig, UserConfig},
+    ui::{
+        gestures::{self, MouseBindings, WidgetActions},
+        osd::Osd,
+    },
+    user::models::{CustomAppsConfig, SectionsConfig, UserConfig, WidgetsConfig},
     utils::{
-        app_launch::app_lauch,
-        clients::{self, focus_client},
+        app_launch::AppLauncher,
+        app_matcher::AppMatcher,
+        clients,
+        format::{Format, Values},
+        history::LaunchHistory,
+        icons::IconResolver,
+        procfs::{self, Sample},
+        search::DesktopIndex,
     },
 };
 
+const PROC_INTERVAL_SECS: u64 = 2;
+const PLAYER_FORMAT: &str = "{?status|🎵 {title|truncate(15)}|💤 No activity}";
+
+// Keyed by /proc root and interval in seconds.
+type ProcSamplers = HashMap<(PathBuf, u64), watch::Receiver<Sample>>;
+
 #[derive(Clone)]
 pub struct Widgets {
     pub workspaces: workspaces::WorkspacesWidget,
-    pub clock: gtk::Widget,
+    pub clock: clock::ClockWidget,
     pub title: title::TitleWidget,
     pub apps: gtk::Widget,
 }
@@ -37,6 +69,14 @@ pub struct WidgetsBuilder {
     pub widgets: Widgets,
     widgets_cache: Rc<std::cell::RefCell<std::collections::HashMap<String, gtk::Widget>>>,
     active_clients: Rc<std::cell::RefCell<Vec<Client>>>,
+    pub dock_state: apps::DockState,
+    pub app_matcher: AppMatcher,
+    pub icons: IconResolver,
+    desktop_index: DesktopIndex,
+    launcher: AppLauncher,
+    script_refresh: Rc<RefCell<HashMap<String, Arc<Notify>>>>,
+    proc_samplers: Rc<RefCell<ProcSamplers>>,
+    pub osd: Osd,
     sender: UiEventState,
 }
 
@@ -48,6 +88,11 @@ impl WidgetsBuilder {
         is_visible: Rc<Cell<bool>>,
         sender: UiEventState,
     ) -> Self {
+        let active_clients = clients::active_clients().unwrap_or_default();
+        let desktop_index = DesktopIndex::new();
+        desktop_index.watch();
+        let app_matcher = AppMatcher::new(desktop_index.clone(), class_overrides(&user_config));
+
         Self {
             main_window: window,
             user_config: user_config.clone(),
@@ -55,40 +100,119 @@ impl WidgetsBuilder {
             is_visible: is_visible.clone(),
             widgets: Widgets {
                 workspaces: workspaces::WorkspacesWidget::new(),
-                clock: clock::render(&is_visible),
-                title: title::TitleWidget::new(),
+                clock: clock::ClockWidget::new(&is_visible, clock::format(&user_config)),
+                title: title::TitleWidget::new(widget_format(
+                    &user_config,
+                    "title",
+                    title::DEFAULT_FORMAT,
+                )),
                 apps: gtk::Box::new(gtk::Orientation::Horizontal, 0).into(),
             },
             widgets_cache: Rc::new(std::cell::RefCell::new(std::collections::HashMap::new())),
-            active_clients: Rc::new(std::cell::RefCell::new(
-                clients::active_clients().unwrap_or_default(),
-            )),
+            dock_state: apps::DockState::new(&active_clients),
+            active_clients: Rc::new(std::cell::RefCell::new(active_clients)),
+            icons: IconResolver::new(app_matcher.clone(), icon_overrides(&user_config)),
+            desktop_index: desktop_index.clone(),
+            app_matcher,
+            launcher: AppLauncher::new(
+                terminal(&user_config),
+                launch_strategy(&user_config),
+                LaunchHistory::new(desktop_index),
+            ),
+            script_refresh: Rc::new(RefCell::new(HashMap::new())),
+            proc_samplers: Rc::new(RefCell::new(HashMap::new())),
+            osd: Osd::new(),
             sender,
         }
     }
 
     pub fn build_widget(&self, widget: BarWidget) -> gtk::Widget {
+        let widget = match widget {
+            BarWidget::Launcher if self.user_config.custom_apps.contains_key("launcher") => {
+                BarWidget::Custom("launcher".to_string())
+            }
+            widget => widget,
+        };
+        let mouse = match &widget {
+            BarWidget::Custom(name) => self.user_config.custom_apps.get(name).map(|c| &c.mouse),
+            widget => self
+                .user_config
+                .widgets
+                .get(widget.config_name())
+                .map(|w| &w.mouse),
+        };
+        let (root, actions) = self.create_widget(widget);
+        let bindings = MouseBindings::new(mouse, &actions);
+        gestures::attach(&root, bindings, actions, self.sender.sender.clone());
+        root
+    }
+
+    fn create_widget(&self, widget: BarWidget) -> (gtk::Widget, WidgetActions) {
         match widget {
-            BarWidget::Separator => separator::render(&self.user_config),
-            BarWidget::Workspaces => self.widgets.workspaces.widget().clone().into(),
-            BarWidget::Time => self.widgets.clock.clone(),
-            BarWidget::AppTitle => self.widgets.title.widget().clone(),
+            BarWidget::Separator => (separator::render(&self.user_config), Default::default()),
+            BarWidget::Workspaces => (
+                self.widgets.workspaces.widget().clone().into(),
+                Default::default(),
+            ),
+            BarWidget::Time => (self.widgets.clock.widget(), self.widgets.clock.actions()),
+            BarWidget::AppTitle => (
+                self.widgets.title.widget().clone(),
+                self.widgets.title.actions(),
+            ),
+            BarWidget::Battery => battery::render(self.user_config.widgets.get("battery")),
+            BarWidget::Volume => volume::render(self.user_config.widgets.get("volume"), &self.osd),
+            BarWidget::Backlight => {
+                backlight::render(self.user_config.widgets.get("backlight"), &self.osd)
+            }
+            BarWidget::Cpu | BarWidget::Memory | BarWidget::Load => {
+                let metric = match widget {
+                    BarWidget::Cpu => system::Metric::Cpu,
+                    BarWidget::Memory => system::Metric::Memory,
+                    _ => system::Metric::Load,
+                };
+                let config = self.user_config.widgets.get(widget.config_name());
+                system::render(metric, config, self.proc_samples(config))
+            }
+            BarWidget::Network => {
+                let config = self.user_config.widgets.get("network");
+                network::render(config, self.proc_samples(config))
+            }
             BarWidget::Shutdown => {
                 let button = gtk::Button::from_icon_name("system-shutdown");
                 let label = gtk::Label::new(Some("Shutdown"));
 
-                set_popover(&button, label.into());
+                let popover = set_popover(&button, label.into());
                 button.add_css_class("shutdown-button");
-                button.into()
+                (
+                    button.into(),
+                    WidgetActions::activate(move || popover.popup()),
+                )
             }
             BarWidget::Playback => {
-                let (window, status) = panels::player::build_ui();
-                let button = gtk::Button::with_label("💤 No activity");
-                let button_clone = button.clone();
-                status.connect_notify_local(Some("label"), move |status, _| {
-                    let chars_limit = 15;
-                    let short_text = status.text().chars().take(chars_limit).collect::<String>();
-                    button_clone.set_label(format!("🎵 {}...", short_text).as_str());
+                let (window, now_playing) = panels::player::build_ui();
+                let label = gtk::Label::new(None);
+                let button = gtk::Button::builder().child(&label).build();
+                let format = widget_format(&self.user_config, "player", PLAYER_FORMAT);
+                let redraw = {
+                    let (button, format, now_playing) =
+                        (button.clone(), format.clone(), now_playing.clone());
+                    move || {
+                        let track = now_playing.track();
+                        let values = Values::from([
+                            ("title", track.title),
+                            ("artist", track.artist),
+                            ("status", track.status),
+                        ]);
+                        set_markup(&label, &format.text(&values));
+                        if format.has_tooltip() {
+                            button.set_tooltip_markup(format.tooltip(&values).as_deref());
+                        }
+                    }
+                };
+                redraw();
+                now_playing.connect_changed({
+                    let redraw = redraw.clone();
+                    move |_| redraw()
                 });
                 button.add_css_class("player-button");
                 let parent_name = match button.parent() {
@@ -97,12 +221,12 @@ impl WidgetsBuilder {
                 };
                 determine_window_position(parent_name.as_str(), &window);
 
-                button.connect_clicked(move |_| match window.is_visible() {
+                let actions = WidgetActions::activate(move || match window.is_visible() {
                     true => window.hide(),
                     false => window.present(),
-                });
-
-                button.into()
+                })
+                .with_alt(&format, redraw);
+                (button.into(), actions)
             }
             BarWidget::Apps => {
                 let container = &self.widgets.apps;
@@ -116,9 +240,12 @@ impl WidgetsBuilder {
                     .cloned()
                     .unwrap_or_default()
                 {
-                    self.create_widget_app(app, false);
+                    self.create_widget_app(app)
+                        .add_css_class(apps::PINNED_CLASS);
                 }
-                container.clone()
+                self.add_recent_apps();
+                self.refresh_dock();
+                (container.clone(), Default::default())
             }
             BarWidget::Settings => {
                 let settings_button = gtk::Button::with_label("󰕴");
@@ -127,15 +254,44 @@ impl WidgetsBuilder {
                 let settings = panels::settings::SettingsPanel::new(self.sender.clone());
                 let window = settings.render();
 
-                settings_button.connect_clicked(move |_| match window.is_visible() {
+                let actions = WidgetActions::activate(move || match window.is_visible() {
                     true => window.hide(),
                     false => window.present(),
                 });
+                (settings_button.into(), actions)
+            }
+            BarWidget::Launcher => {
+                let icon = self
+                    .user_config
+                    .widgets
+                    .get("launcher")
+                    .and_then(|w| w.icon.clone())
+                    .unwrap_or_else(|| "\u{f303}".to_string());
+                let launcher_button = gtk::Button::with_label(&icon);
+                launcher_button.add_css_class("launcher-button");
+                launcher_button.set_cursor(Cursor::from_name("pointer", None).as_ref());
+
+                let launcher = panels::launcher::LauncherPanel::new(DesktopLauncher {
+                    index: self.desktop_index.clone(),
+                    launcher: self.launcher.clone(),
+                    icons: self.icons.clone(),
+                });
+                launcher.render();
 
-                settings_button.into()
+                (
+                    launcher_button.into(),
+                    WidgetActions::activate(move || launcher.toggle()),
+                )
             }
             BarWidget::Custom(name) => {
                 let button = self.user_config.custom_apps.get(name.as_str());
+                if let Some(button) = button
+                    && let kind @ (CustomKind::Script | CustomKind::Stream) = custom_kind(button)
+                {
+                    let (widget, actions, refresh) = script::render(&name, button, kind);
+                    self.script_refresh.borrow_mut().insert(name, refresh);
+                    return (widget, actions);
+                }
                 if let Some(button) = button {
                     let btn = match button.icon.as_deref() {
                         Some(icon_name) => gtk::Button::with_label(icon_name),
@@ -148,10 +304,23 @@ impl WidgetsBuilder {
                     let cursor = Cursor::from_name("pointer", None);
                     btn.set_cursor(cursor.as_ref());
 
+                    let mut actions = WidgetActions::default();
                     if let Some(cmd) = &button.cmd {
                         let cmd = cmd.clone();
-                        btn.connect_clicked(move |_| {
-                            app_lauch(&cmd);
+                        let launcher = self.launcher.clone();
+                        let btn = btn.downgrade();
+                        actions = WidgetActions::activate(move || {
+                            let Some(btn) = btn.upgrade() else {
+                                return;
+                            };
+                            let token = apps::activation_token(btn.upcast_ref());
+                            let btn = btn.downgrade();
+                            launcher.launch_command(&cmd, token, move |e| {
+                                eprintln!("{}", e);
+                                if let Some(btn) = btn.upgrade() {
+                                    show_error_popover(btn.upcast_ref(), &e.to_string());
+                                }
+                            });
                         });
                     }
                     if button.tooltip.unwrap_or(true) {
@@ -161,9 +330,13 @@ impl WidgetsBuilder {
 
                     let name = button.name.as_deref().unwrap_or("custom-app");
                     btn.set_widget_name(name);
-                    btn.into()
+                    // A configured right click replaces the desktop actions menu.
+                    if button.mouse.on_click_right.is_none() {
+                        self.attach_desktop_actions(&btn, name);
+                    }
+                    (btn.into(), actions)
                 } else {
-                    gtk::Label::new(Some("")).into()
+                    (gtk::Label::new(Some("")).into(), Default::default())
                 }
             }
         }
@@ -181,6 +354,15 @@ impl WidgetsBuilder {
             center,
         } = &self.user_config.sections;
 
+        // Widgets that left the layout are dropped, which stops their scripts,
+        // and built again if they come back.
+        self.widgets_cache
+            .borrow_mut()
+            .retain(|name, _| self.widget_exists(name));
+        self.script_refresh
+            .borrow_mut()
+            .retain(|name, _| self.widget_exists(name));
+
         let left = self.sync_section_widgets(&section_left, left.as_slice());
         let center = self.sync_section_widgets(&section_center, center.as_slice());
         let right = self.sync_section_widgets(&section_right, right.as_slice());
@@ -245,63 +427,126 @@ impl WidgetsBuilder {
     }
 
     pub fn update_config(&mut self, user_config: Rc<UserConfig>) {
+        self.app_matcher
+            .set_overrides(class_overrides(&user_config));
+        self.icons.set_overrides(icon_overrides(&user_config));
+        self.launcher
+            .configure(terminal(&user_config), launch_strategy(&user_config));
         self.user_config = user_config;
     }
 
-    pub fn create_widget_app(&self, app_name: &str, is_opened: bool) {
+    pub fn create_widget_app(&self, app_name: &str) -> gtk::Button {
         let button = gtk::Button::new();
         button.set_cursor(Cursor::from_name("pointer", None).as_ref());
         button.set_tooltip_text(Some(app_name));
         button.set_widget_name(app_name);
         button.add_css_class("app-button");
 
-        let clients = self.active_clients.borrow();
-        let app_clients_active = clients
-            .iter()
-            .filter(|c| c.class.to_lowercase().contains(app_name));
-
-        let has_active_client = app_clients_active.clone().count() >= 1;
-
-        if has_active_client || is_opened {
-            button.add_css_class("opened");
+        if let Some(desktop_file) = self.app_matcher.resolve(app_name) {
+            self.dock_state
+                .desktop_ids
+                .borrow_mut()
+                .insert(app_name.to_string(), desktop_file.id);
         }
-
-        let mut exec = None;
-        use crate::utils::search::search_desktop_file;
-        let icon_name = if let Some(desktop_file) = search_desktop_file(app_name) {
-            exec = Some(desktop_file.exec.clone());
-            desktop_file
-                .icon
-                .unwrap_or_else(|| "application-x-executable".to_string())
-        } else {
-            "application-x-executable".to_string()
-        };
-        button.set_child(Some(&self.load_icon(&icon_name, 20)));
+        button.set_child(Some(&apps::app_icon(
+            &self
+                .icons
+                .widget(app_name, 20, self.main_window.scale_factor()),
+            self.dock_indicator(),
+        )));
+
+        let launch = apps::launch_handler(
+            &button,
+            self.launcher.clone(),
+            self.app_matcher.clone(),
+            self.dock_state.clone(),
+            app_name,
+        );
+        button.insert_action_group(
+            "dock",
+            Some(&apps::menu_actions(
+                app_name,
+                Rc::clone(&launch),
+                self.sender.sender.clone(),
+            )),
+        );
+        let menu = apps::context_menu(&button);
+        let max_workspaces = self
+            .user_config
+            .widgets
+            .get("workspaces")
+            .and_then(|w| w.max_workspaces)
+            .unwrap_or(10) as i64;
+
+        let cycle_scope = self
+            .user_config
+            .widgets
+            .get("apps")
+            .and_then(|w| w.cycle_scope.as_deref())
+            .and_then(|scope| scope.parse::<CycleScope>().ok())
+            .unwrap_or_default();
+        let windows = apps::windows_popover(
+            &button,
+            Rc::clone(&self.active_clients),
+            self.app_matcher.clone(),
+            app_name,
+        );
 
         let app_clone = app_name.to_string();
         let app_clients = Rc::clone(&self.active_clients);
+        let matcher = self.app_matcher.clone();
+        let dock_state = self.dock_state.clone();
+        let button_weak = button.downgrade();
 
         let gesture = GestureClick::new();
+        gesture.set_button(0);
         gesture.set_propagation_phase(gtk::PropagationPhase::Capture);
-        gesture.connect_pressed(move |gesture, _, _, _| match gesture.current_button() {
-            1 => {
-                let exec_cmd = exec.clone().unwrap_or(app_clone.clone());
-                let clients = app_clients.borrow();
-                let mut iter = clients
-                    .iter()
-                    .filter(|c| c.class.to_lowercase().contains(&app_clone.to_lowercase()));
-
-                match (iter.next(), iter.next()) {
-                    (Some(client), Some(_)) => focus_client(client),
-                    (Some(client), None) => focus_client(client),
-                    (None, _) => app_lauch(&exec_cmd),
+        gesture.connect_released(move |gesture, _, _, _| {
+            // Keeps the dock's own bindings from also reaching the container.
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            windows.popdown();
+            match gesture.current_button() {
+                1 => {
+                    let (launch, dock_state) = (Rc::clone(&launch), dock_state.clone());
+                    let app_name = app_clone.clone();
+                    apps::with_app_windows(&app_clients, &matcher, &app_clone, move |windows| {
+                        // Only a plain click waits for a pending launch, the
+                        // middle click and "New window" always open another.
+                        if !apps::cycle_windows(&windows, cycle_scope)
+                            && !dock_state.is_launching(&app_name)
+                        {
+                            launch(None);
+                        }
+                    });
                 }
+                2 => launch(None),
+                3 => {
+                    let (menu, button_weak) = (menu.clone(), button_weak.clone());
+                    let actions = matcher
+                        .resolve(&app_clone)
+                        .map(|entry| entry.actions)
+                        .unwrap_or_default();
+
+                    apps::with_app_windows(&app_clients, &matcher, &app_clone, move |windows| {
+                        let is_pinned = button_weak
+                            .upgrade()
+                            .is_some_and(|button| button.has_css_class(apps::PINNED_CLASS));
+
+                        menu.set_menu_model(Some(&apps::build_menu(
+                            &windows.iter().collect::<Vec<_>>(),
+                            &actions,
+                            is_pinned,
+                            max_workspaces,
+                        )));
+                        menu.popup();
+                    });
+                }
+                _ => {}
             }
-            2 => {}
-            _ => println!("Other mouse button pressed"),
         });
 
         button.add_controller(gesture);
+        apps::reorder_controllers(&button, self.sender.sender.clone());
 
         self.widgets
             .apps
@@ -309,6 +554,153 @@ impl WidgetsBuilder {
             .downcast::<gtk::Box>()
             .unwrap()
             .append(&button);
+
+        button
+    }
+
+    fn attach_desktop_actions(&self, button: &gtk::Button, app_name: &str) {
+        let Some(entry) = self
+            .app_matcher
+            .resolve(app_name)
+            .filter(|entry| !entry.actions.is_empty())
+        else {
+            return;
+        };
+
+        let launch = apps::launch_handler(
+            button,
+            self.launcher.clone(),
+            self.app_matcher.clone(),
+            self.dock_state.clone(),
+            app_name,
+        );
+        let group = gio::SimpleActionGroup::new();
+        group.add_action(&apps::desktop_action(launch));
+        button.insert_action_group("custom", Some(&group));
+
+        let menu = apps::context_menu(button);
+        menu.set_menu_model(Some(&apps::actions_menu(&entry.actions, "custom.action")));
+
+        let gesture = GestureClick::new();
+        gesture.set_button(3);
+        gesture.connect_released(move |_, _, _, _| menu.popup());
+        button.add_controller(gesture);
+    }
+
+    pub fn refresh_dock(&self) {
+        apps::refresh_dock(
+            &self.widgets.apps,
+            &self.active_clients.borrow(),
+            &self.dock_state,
+            &self.app_matcher,
+            self.dock_indicator(),
+        );
+    }
+
+    // Up to `widgets.apps.recent` of the most frecent apps that are not
+    // already in the dock.
+    fn add_recent_apps(&self) {
+        let limit = self
+            .user_config
+            .widgets
+            .get("apps")
+            .and_then(|w| w.recent)
+            .unwrap_or(0) as usize;
+        if limit == 0 {
+            return;
+        }
+
+        let mut docked = HashSet::new();
+        let mut child = self.widgets.apps.first_child();
+        while let Some(button) = child {
+            child = button.next_sibling();
+            if let Some(id) = self.app_matcher.resolve_id(&button.widget_name()) {
+                docked.insert(id);
+            }
+        }
+
+        let recent = self
+            .launcher
+            .history()
+            .recent(usize::MAX)
+            .into_iter()
+            .filter_map(|app| self.app_matcher.resolve(&app))
+            .filter(|entry| docked.insert(entry.id.clone()))
+            .take(limit);
+        for entry in recent {
+            let app_name = entry.id.strip_suffix(".desktop").unwrap_or(&entry.id);
+            self.create_widget_app(app_name)
+                .add_css_class(apps::RECENT_CLASS);
+        }
+    }
+
+    pub fn clear_history(&self) {
+        self.launcher.history().clear();
+
+        let mut child = self.widgets.apps.first_child();
+        while let Some(button) = child {
+            child = button.next_sibling();
+            if !button.has_css_class(apps::RECENT_CLASS) {
+                continue;
+            }
+            match button.has_css_class(apps::OPENED_CLASS) {
+                true => button.remove_css_class(apps::RECENT_CLASS),
+                false => self.remove_widget_app(&button),
+            }
+        }
+    }
+
+    fn dock_indicator(&self) -> DockIndicator {
+        self.user_config
+            .widgets
+            .get("apps")
+            .and_then(|w| w.indicator.as_deref())
+            .and_then(|indicator| indicator.parse::<DockIndicator>().ok())
+            .unwrap_or_default()
+    }
+
+    pub fn title_icons(&self) -> bool {
+        self.user_config
+            .widgets
+            .get("title")
+            .and_then(|w| w.show_icons)
+            .unwrap_or(false)
+    }
+
+    // Widgets reading the same root at the same interval share one sampler.
+    fn proc_samples(&self, config: Option<&WidgetsConfig>) -> watch::Receiver<Sample> {
+        let root = PathBuf::from(
+            config
+                .and_then(|c| c.root.as_deref())
+                .unwrap_or(procfs::DEFAULT_ROOT),
+        );
+        let interval = config
+            .and_then(|c| c.interval)
+            .unwrap_or(PROC_INTERVAL_SECS)
+            .max(1);
+
+        // A sampler whose widgets were all removed has stopped, the next
+        // widget starts a new one.
+        let mut samplers = self.proc_samplers.borrow_mut();
+        let key = (root, interval);
+        if let Some(receiver) = samplers.get(&key)
+            && receiver.has_changed().is_ok()
+        {
+            return receiver.clone();
+        }
+        let receiver = procfs::spawn_sampler(&key.0, Duration::from_secs(interval));
+        samplers.insert(key, receiver.clone());
+        receiver
+    }
+
+    pub fn refresh_script(&self, name: &str) -> bool {
+        match self.script_refresh.borrow().get(name) {
+            Some(refresh) => {
+                refresh.notify_one();
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn update_active_clients(&self) {
@@ -327,26 +719,66 @@ impl WidgetsBuilder {
             .unwrap()
             .remove(widget);
     }
+}
+fn widget_format(user_config: &UserConfig, widget: &str, default_format: &str) -> Format {
+    Format::new(
+        user_config.widgets.get(widget).map(|c| &c.formats),
+        default_format,
+    )
+}
 
-    pub fn load_icon(&self, icon_name: &str, size: i32) -> Image {
-        let image = if icon_name.contains('/')
-            || icon_name.ends_with(".png")
-            || icon_name.ends_with(".svg")
-            || icon_name.ends_with(".xpm")
-        {
-            if Path::new(icon_name).exists() {
-                Image::from_file(icon_name)
-            } else {
-                Image::new()
-            }
-        } else {
-            Image::from_icon_name(icon_name)
-        };
+// Labels fall back to plain text when the formatted text isn't valid markup,
+// e.g. a format written with a bare `&`.
+pub fn set_markup(label: &gtk::Label, text: &str) {
+    match gtk::pango::parse_markup(text, '\0') {
+        Ok(_) => label.set_markup(text),
+        Err(_) => label.set_text(text),
+    }
+}
 
-        image.set_pixel_size(size);
-        image
+fn custom_kind(config: &CustomAppsConfig) -> CustomKind {
+    match config.kind.as_deref() {
+        Some(kind) => kind.parse().unwrap_or_else(|_| {
+            eprintln!("Unknown custom widget kind: {}", kind);
+            CustomKind::default()
+        }),
+        None if config.exec.is_some() => CustomKind::Script,
+        None => CustomKind::Button,
     }
 }
+
+fn icon_overrides(user_config: &UserConfig) -> HashMap<String, String> {
+    user_config
+        .widgets
+        .get("apps")
+        .and_then(|w| w.icon_overrides.clone())
+        .unwrap_or_default()
+}
+
+fn class_overrides(user_config: &UserConfig) -> HashMap<String, String> {
+    user_config
+        .widgets
+        .get("apps")
+        .and_then(|w| w.class_overrides.clone())
+        .unwrap_or_default()
+}
+
+fn terminal(user_config: &UserConfig) -> Option<String> {
+    user_config
+        .widgets
+        .get("apps")
+        .and_then(|w| w.terminal.clone())
+}
+
+fn launch_strategy(user_config: &UserConfig) -> LaunchStrategy {
+    user_config
+        .widgets
+        .get("apps")
+        .and_then(|w| w.launch.as_deref())
+        .and_then(|strategy| strategy.parse::<LaunchStrategy>().ok())
+        .unwrap_or_default()
+}
+
 pub fn determine_window_position(parent_name: &str, window: &gtk::ApplicationWindow) {
     match parent_name {
         "section-left" => {
@@ -1,13 +1,666 @@
-pub fn window_clients() -> gtk::ApplicationWindow {
-    let window = gtk::ApplicationWindow::builder()
-        .title("Clients")
-        .default_width(400)
-        .default_height(300)
+use gtk::{gdk, gio, glib, pango, prelude::*};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    UiEvent,
+    bar::{find_widget_child, show_error_popover},
+    enums::widgets::{CycleScope, DockIndicator},
+    models::clients::Client,
+    ui::widgets::workspaces::get_active_workspace,
+    utils::{
+        app_launch::{AppLauncher, LaunchError},
+        app_matcher::AppMatcher,
+        clients::{self, WindowAction, cycle_target, focus_client, window_action},
+        desktop_entry::DesktopAction,
+        launcher_entry::LauncherEntry,
+    },
+};
+
+pub const PINNED_CLASS: &str = "pinned";
+pub const OPENED_CLASS: &str = "opened";
+pub const RECENT_CLASS: &str = "recent";
+const FOCUSED_CLASS: &str = "focused";
+const URGENT_CLASS: &str = "urgent";
+const ON_WORKSPACE_CLASS: &str = "on-workspace";
+const INDICATOR_NAME: &str = "dock-indicator";
+const BADGE_NAME: &str = "dock-badge";
+const PROGRESS_NAME: &str = "dock-progress";
+const MAX_DOTS: usize = 4;
+const TITLE_CHARS_LIMIT: usize = 40;
+const HOVER_HIDE_DELAY_MS: u64 = 250;
+const LAUNCHING_CLASS: &str = "launching";
+const LAUNCH_TIMEOUT_SECS: u64 = 15;
+
+pub type LaunchHandler = Rc<dyn Fn(Option<&str>)>;
+type PendingLaunch = (glib::WeakRef<gtk::Button>, glib::SourceId);
+
+#[derive(Debug, Clone, Default)]
+pub struct DockState {
+    pub urgent: Rc<RefCell<HashSet<String>>>,
+    pub active_window: Rc<RefCell<Option<String>>>,
+    pub active_workspace: Rc<Cell<Option<i64>>>,
+    pub desktop_ids: Rc<RefCell<HashMap<String, String>>>,
+    pub launcher_entries: Rc<RefCell<HashMap<String, LauncherEntry>>>,
+    launching: Rc<RefCell<HashMap<String, PendingLaunch>>>,
+}
+
+impl DockState {
+    pub fn new(clients: &[Client]) -> Self {
+        let active_window = clients
+            .iter()
+            .find(|c| c.focus_history_id == 0)
+            .map(|c| c.address.clone());
+
+        Self {
+            urgent: Rc::new(RefCell::new(HashSet::new())),
+            active_window: Rc::new(RefCell::new(active_window)),
+            active_workspace: Rc::new(Cell::new(get_active_workspace().map(|ws| ws.id as i64))),
+            desktop_ids: Rc::new(RefCell::new(HashMap::new())),
+            launcher_entries: Rc::new(RefCell::new(HashMap::new())),
+            launching: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    // Launching again while pending restarts the timeout.
+    pub fn start_launch(&self, button: &gtk::Button, app_name: &str) {
+        if let Some((_, timeout)) = self.launching.borrow_mut().remove(app_name) {
+            timeout.remove();
+        }
+
+        button.add_css_class(LAUNCHING_CLASS);
+        let timeout = glib::timeout_add_local_once(Duration::from_secs(LAUNCH_TIMEOUT_SECS), {
+            let launching = Rc::clone(&self.launching);
+            let app_name = app_name.to_string();
+            move || {
+                let launch = launching.borrow_mut().remove(&app_name);
+                if let Some(button) = launch.and_then(|(button, _)| button.upgrade()) {
+                    button.remove_css_class(LAUNCHING_CLASS);
+                }
+            }
+        });
+        self.launching
+            .borrow_mut()
+            .insert(app_name.to_string(), (button.downgrade(), timeout));
+    }
+
+    pub fn is_launching(&self, app_name: &str) -> bool {
+        self.launching.borrow().contains_key(app_name)
+    }
+
+    pub fn finish_launch(&self, app_name: &str) {
+        let Some((button, timeout)) = self.launching.borrow_mut().remove(app_name) else {
+            return;
+        };
+
+        timeout.remove();
+        if let Some(button) = button.upgrade() {
+            button.remove_css_class(LAUNCHING_CLASS);
+        }
+    }
+
+    pub fn launching_apps(&self) -> Vec<String> {
+        self.launching.borrow().keys().cloned().collect()
+    }
+
+    fn launcher_entry(&self, app_name: &str) -> Option<LauncherEntry> {
+        let desktop_ids = self.desktop_ids.borrow();
+        let desktop_id = desktop_ids.get(app_name)?;
+        self.launcher_entries.borrow().get(desktop_id).cloned()
+    }
+}
+
+// `hyprctl clients` runs on a worker thread so the dock never stalls the
+// main loop, `then` gets the windows of `app_name` once it is back.
+pub fn with_app_windows<F: FnOnce(Vec<Client>) + 'static>(
+    active_clients: &Rc<RefCell<Vec<Client>>>,
+    matcher: &AppMatcher,
+    app_name: &str,
+    then: F,
+) {
+    let active_clients = Rc::clone(active_clients);
+    let matcher = matcher.clone();
+    let app_name = app_name.to_string();
+    glib::MainContext::default().spawn_local(async move {
+        if let Ok(Some(clients)) = gio::spawn_blocking(clients::active_clients).await {
+            *active_clients.borrow_mut() = clients;
+        }
+        let windows = matcher
+            .windows(&active_clients.borrow(), &app_name)
+            .into_iter()
+            .cloned()
+            .collect();
+        then(windows);
+    });
+}
+
+pub fn app_icon(icon: &gtk::Widget, indicator: DockIndicator) -> gtk::Overlay {
+    let label = gtk::Label::new(None);
+    label.set_widget_name(INDICATOR_NAME);
+    label.add_css_class(INDICATOR_NAME);
+    label.set_can_target(false);
+    label.set_visible(false);
+
+    match indicator {
+        DockIndicator::Number => {
+            label.add_css_class("number");
+            label.set_halign(gtk::Align::End);
+            label.set_valign(gtk::Align::End);
+        }
+        _ => {
+            label.add_css_class("dots");
+            label.set_halign(gtk::Align::Center);
+            label.set_valign(gtk::Align::End);
+        }
+    }
+
+    let badge = gtk::Label::new(None);
+    badge.set_widget_name(BADGE_NAME);
+    badge.add_css_class(BADGE_NAME);
+    badge.set_can_target(false);
+    badge.set_visible(false);
+    badge.set_halign(gtk::Align::End);
+    badge.set_valign(gtk::Align::Start);
+
+    let progress = gtk::ProgressBar::new();
+    progress.set_widget_name(PROGRESS_NAME);
+    progress.add_css_class(PROGRESS_NAME);
+    progress.set_can_target(false);
+    progress.set_visible(false);
+    progress.set_valign(gtk::Align::End);
+
+    let overlay = gtk::Overlay::builder().child(icon).build();
+    overlay.add_overlay(&label);
+    overlay.add_overlay(&badge);
+    overlay.add_overlay(&progress);
+    overlay
+}
+
+pub fn refresh_dock(
+    container: &gtk::Widget,
+    clients: &[Client],
+    state: &DockState,
+    matcher: &AppMatcher,
+    indicator: DockIndicator,
+) {
+    let active_workspace = state.active_workspace.get();
+    let urgent = state.urgent.borrow();
+    let active_window = state.active_window.borrow();
+
+    let mut child = container.first_child();
+    while let Some(button) = child {
+        child = button.next_sibling();
+
+        let name = button.widget_name();
+        let windows = matcher.windows(clients, &name);
+        let entry = state.launcher_entry(&name).unwrap_or_default();
+
+        set_class(&button, OPENED_CLASS, !windows.is_empty());
+        set_class(
+            &button,
+            FOCUSED_CLASS,
+            windows
+                .iter()
+                .any(|c| active_window.as_deref() == Some(c.address.as_str())),
+        );
+        set_class(
+            &button,
+            URGENT_CLASS,
+            entry.is_urgent() || windows.iter().any(|c| urgent.contains(&c.address)),
+        );
+        set_class(
+            &button,
+            ON_WORKSPACE_CLASS,
+            windows
+                .iter()
+                .any(|c| Some(c.workspace.id) == active_workspace),
+        );
+        update_indicator(&button, windows.len(), indicator);
+        update_launcher_entry(&button, &entry);
+    }
+}
+
+fn set_class(widget: &gtk::Widget, class: &str, enabled: bool) {
+    match enabled {
+        true => widget.add_css_class(class),
+        false => widget.remove_css_class(class),
+    }
+}
+
+fn update_launcher_entry(button: &gtk::Widget, entry: &LauncherEntry) {
+    let Some(overlay) = button.first_child() else {
+        return;
+    };
+
+    if let Some(badge) =
+        find_widget_child(&overlay, BADGE_NAME).and_then(|w| w.downcast::<gtk::Label>().ok())
+    {
+        let text = entry.badge();
+        badge.set_visible(text.is_some());
+        badge.set_text(text.as_deref().unwrap_or(""));
+    }
+
+    if let Some(progress_bar) = find_widget_child(&overlay, PROGRESS_NAME)
+        .and_then(|w| w.downcast::<gtk::ProgressBar>().ok())
+    {
+        let progress = entry.progress();
+        progress_bar.set_visible(progress.is_some());
+        progress_bar.set_fraction(progress.unwrap_or(0.0));
+    }
+}
+
+fn update_indicator(button: &gtk::Widget, count: usize, indicator: DockIndicator) {
+    let Some(label) = button
+        .first_child()
+        .and_then(|overlay| find_widget_child(&overlay, INDICATOR_NAME))
+        .and_then(|widget| widget.downcast::<gtk::Label>().ok())
+    else {
+        return;
+    };
+
+    let text = match indicator {
+        DockIndicator::Dots => "•".repeat(count.min(MAX_DOTS)),
+        DockIndicator::Number if count > 1 => count.to_string(),
+        _ => String::new(),
+    };
+
+    label.set_visible(!text.is_empty());
+    label.set_text(&text);
+}
+
+pub fn cycle_windows(windows: &[Client], scope: CycleScope) -> bool {
+    let all = windows.iter().collect::<Vec<_>>();
+    let scoped = match scope {
+        CycleScope::All => all.clone(),
+        CycleScope::Workspace => {
+            let active = get_active_workspace().map(|ws| ws.id as i64);
+            all.iter()
+                .copied()
+                .filter(|c| Some(c.workspace.id) == active)
+                .collect()
+        }
+    };
+
+    match cycle_target(&scoped).or_else(|| cycle_target(&all)) {
+        Some(client) => {
+            focus_client(client);
+            true
+        }
+        None => false,
+    }
+}
+
+pub fn windows_popover(
+    button: &gtk::Button,
+    active_clients: Rc<RefCell<Vec<Client>>>,
+    matcher: AppMatcher,
+    app_name: &str,
+) -> gtk::Popover {
+    let list = gtk::Box::new(gtk::Orientation::Vertical, 2);
+    let popover = gtk::Popover::builder()
+        .child(&list)
+        .has_arrow(true)
+        .autohide(false)
+        .position(gtk::PositionType::Bottom)
         .build();
+    popover.add_css_class("popover");
+    popover.add_css_class("dock-windows");
+    popover.set_parent(button);
+
+    let over_button = Rc::new(Cell::new(false));
+    let over_popover = Rc::new(Cell::new(false));
+
+    let hide_later = {
+        let popover = popover.clone();
+        let over_button = Rc::clone(&over_button);
+        let over_popover = Rc::clone(&over_popover);
+        move || {
+            let popover = popover.clone();
+            let over_button = Rc::clone(&over_button);
+            let over_popover = Rc::clone(&over_popover);
+            glib::timeout_add_local_once(Duration::from_millis(HOVER_HIDE_DELAY_MS), move || {
+                if !over_button.get() && !over_popover.get() {
+                    popover.popdown();
+                }
+            });
+        }
+    };
+
+    let button_motion = gtk::EventControllerMotion::new();
+    let app_name = app_name.to_string();
+    button_motion.connect_enter({
+        let popover = popover.clone();
+        let over_button = Rc::clone(&over_button);
+        move |_, _, _| {
+            over_button.set(true);
+            let (list, popover, over_button) =
+                (list.clone(), popover.clone(), Rc::clone(&over_button));
+            with_app_windows(&active_clients, &matcher, &app_name, move |mut windows| {
+                // The pointer may have moved on while the list was fetched.
+                if windows.len() < 2 || !over_button.get() {
+                    return;
+                }
+
+                windows.sort_by_key(|c| c.focus_history_id);
+                while let Some(child) = list.first_child() {
+                    list.remove(&child);
+                }
+                for window in windows {
+                    list.append(&window_row(&window, &popover));
+                }
+                popover.popup();
+            });
+        }
+    });
+    button_motion.connect_leave({
+        let over_button = Rc::clone(&over_button);
+        let hide_later = hide_later.clone();
+        move |_| {
+            over_button.set(false);
+            hide_later();
+        }
+    });
+    button.add_controller(button_motion);
+
+    let popover_motion = gtk::EventControllerMotion::new();
+    popover_motion.connect_enter({
+        let over_popover = Rc::clone(&over_popover);
+        move |_, _, _| over_popover.set(true)
+    });
+    popover_motion.connect_leave(move |_| {
+        over_popover.set(false);
+        hide_later();
+    });
+    popover.add_controller(popover_motion);
+
+    popover
+}
+
+fn window_row(client: &Client, popover: &gtk::Popover) -> gtk::Button {
+    let label = gtk::Label::new(Some(&section_label(client)));
+    label.set_ellipsize(pango::EllipsizeMode::End);
+    label.set_max_width_chars(TITLE_CHARS_LIMIT as i32);
+    label.set_halign(gtk::Align::Start);
+
+    let row = gtk::Button::builder().child(&label).build();
+    row.add_css_class("dock-window");
+    if client.focus_history_id == 0 {
+        row.add_css_class("focused");
+    }
+
+    let address = client.address.clone();
+    let popover = popover.clone();
+    row.connect_clicked(move |_| {
+        window_action(&address, WindowAction::Focus);
+        popover.popdown();
+    });
+    row
+}
+
+pub fn context_menu(button: &gtk::Button) -> gtk::PopoverMenu {
+    let popover = gtk::PopoverMenu::from_model(None::<&gio::MenuModel>);
+    popover.set_has_arrow(true);
+    popover.set_position(gtk::PositionType::Bottom);
+    popover.add_css_class("dock-menu");
+    popover.set_parent(button);
+    popover
+}
+
+pub fn launch_handler(
+    button: &gtk::Button,
+    launcher: AppLauncher,
+    matcher: AppMatcher,
+    state: DockState,
+    app_name: &str,
+) -> LaunchHandler {
+    let button = button.downgrade();
+    let app_name = app_name.to_string();
+
+    Rc::new(move |action| {
+        let Some(widget) = button.upgrade() else {
+            return;
+        };
+        state.start_launch(&widget, &app_name);
+
+        let token = activation_token(widget.upcast_ref());
+        let on_error = {
+            let button = button.clone();
+            let state = state.clone();
+            let app_name = app_name.clone();
+            move |e: LaunchError| {
+                eprintln!("{}", e);
+                state.finish_launch(&app_name);
+                if let Some(button) = button.upgrade() {
+                    show_error_popover(button.upcast_ref(), &e.to_string());
+                }
+            }
+        };
+
+        match (matcher.resolve(&app_name), action) {
+            (Some(entry), Some(action)) => launcher.launch_action(&entry, action, token, on_error),
+            (Some(entry), None) => launcher.launch(&entry, token, on_error),
+            (None, _) => launcher.launch_command(&app_name, token, on_error),
+        }
+    })
+}
+
+pub fn activation_token(widget: &gtk::Widget) -> Option<String> {
+    widget
+        .display()
+        .app_launch_context()
+        .startup_notify_id(None::<&gio::AppInfo>, &[])
+        .map(String::from)
+}
+
+pub fn desktop_action(launch: LaunchHandler) -> gio::SimpleAction {
+    let action = gio::SimpleAction::new("action", Some(glib::VariantTy::STRING));
+    action.connect_activate(move |_, param| {
+        if let Some(id) = param.and_then(|p| p.get::<String>()) {
+            launch(Some(&id));
+        }
+    });
+    action
+}
+
+pub fn actions_menu(actions: &[DesktopAction], action_name: &str) -> gio::Menu {
+    let menu = gio::Menu::new();
+    for action in actions {
+        let item = gio::MenuItem::new(Some(&action.name), None);
+        item.set_action_and_target_value(Some(action_name), Some(&action.id.to_variant()));
+        menu.append_item(&item);
+    }
+    menu
+}
+
+pub fn menu_actions(
+    app_name: &str,
+    launch: LaunchHandler,
+    sender: async_channel::Sender<UiEvent>,
+) -> gio::SimpleActionGroup {
+    let group = gio::SimpleActionGroup::new();
+    group.add_action(&desktop_action(Rc::clone(&launch)));
+
+    let window_actions = [
+        ("focus", WindowAction::Focus),
+        ("close", WindowAction::Close),
+        ("float", WindowAction::ToggleFloating),
+        ("pin", WindowAction::Pin),
+        ("fullscreen", WindowAction::Fullscreen),
+    ];
+
+    for (name, action) in window_actions {
+        let simple_action = gio::SimpleAction::new(name, Some(glib::VariantTy::STRING));
+        simple_action.connect_activate(move |_, param| {
+            if let Some(address) = param.and_then(|p| p.get::<String>()) {
+                window_action(&address, action);
+            }
+        });
+        group.add_action(&simple_action);
+    }
+
+    let move_action = gio::SimpleAction::new("move", glib::VariantTy::new("(sx)").ok());
+    move_action.connect_activate(|_, param| {
+        if let Some((address, workspace)) = param.and_then(|p| p.get::<(String, i64)>()) {
+            window_action(&address, WindowAction::MoveToWorkspace(workspace));
+        }
+    });
+    group.add_action(&move_action);
+
+    let new_window = gio::SimpleAction::new("new-window", None);
+    new_window.connect_activate(move |_, _| launch(None));
+    group.add_action(&new_window);
+
+    for (name, pinned) in [("pin-dock", true), ("unpin-dock", false)] {
+        let app_name = app_name.to_string();
+        let sender = sender.clone();
+        let dock_action = gio::SimpleAction::new(name, None);
+        dock_action.connect_activate(move |_, _| {
+            sender
+                .try_send(UiEvent::DockPinChanged((app_name.clone(), pinned)))
+                .unwrap_or_else(|e| eprintln!("Failed to send dock pin event: {}", e));
+        });
+        group.add_action(&dock_action);
+    }
+
+    group
+}
+
+pub fn reorder_controllers(button: &gtk::Button, sender: async_channel::Sender<UiEvent>) {
+    let drag_source = gtk::DragSource::new();
+    drag_source.set_actions(gdk::DragAction::MOVE);
+    drag_source.connect_prepare(|source, _, _| {
+        let name = source.widget()?.widget_name().to_string();
+        Some(gdk::ContentProvider::for_value(&name.to_value()))
+    });
+    drag_source.connect_drag_begin(|source, _| {
+        if let Some(widget) = source.widget() {
+            let icon = gtk::WidgetPaintable::new(Some(&widget));
+            source.set_icon(Some(&icon), widget.width() / 2, widget.height() / 2);
+        }
+    });
+    button.add_controller(drag_source);
+
+    let drop_target = gtk::DropTarget::new(glib::Type::STRING, gdk::DragAction::MOVE);
+    drop_target.connect_drop(move |target, value, x, _| {
+        let Ok(name) = value.get::<String>() else {
+            return false;
+        };
+        let Some(target_widget) = target.widget() else {
+            return false;
+        };
+        let Some(container) = target_widget
+            .parent()
+            .and_then(|parent| parent.downcast::<gtk::Box>().ok())
+        else {
+            return false;
+        };
+        let Some(dragged) = find_widget_child(container.upcast_ref(), &name) else {
+            return false;
+        };
+        if dragged == target_widget {
+            return false;
+        }
+
+        let sibling = match x > f64::from(target_widget.width()) / 2.0 {
+            true => Some(target_widget),
+            false => target_widget.prev_sibling(),
+        };
+        if sibling.as_ref() == Some(&dragged) {
+            return false;
+        }
+
+        container.reorder_child_after(&dragged, sibling.as_ref());
+        sender
+            .try_send(UiEvent::DockChanged)
+            .unwrap_or_else(|e| eprintln!("Failed to send dock changed event: {}", e));
+        true
+    });
+    button.add_controller(drop_target);
+}
+
+pub fn build_menu(
+    clients: &[&Client],
+    actions: &[DesktopAction],
+    is_pinned: bool,
+    max_workspaces: i64,
+) -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    for client in clients {
+        let address = client.address.as_str();
+        let section = gio::Menu::new();
+
+        section.append_item(&window_item("Focus", "dock.focus", address));
+        section.append_item(&window_item(
+            if client.floating { "Tile" } else { "Float" },
+            "dock.float",
+            address,
+        ));
+        section.append_item(&window_item(
+            if client.pinned { "Unpin" } else { "Pin" },
+            "dock.pin",
+            address,
+        ));
+        section.append_item(&window_item(
+            if client.fullscreen != 0 {
+                "Exit fullscreen"
+            } else {
+                "Fullscreen"
+            },
+            "dock.fullscreen",
+            address,
+        ));
+
+        let move_menu = gio::Menu::new();
+        for workspace in (1..=max_workspaces).filter(|id| *id != client.workspace.id) {
+            let item = gio::MenuItem::new(Some(&format!("Workspace {}", workspace)), None);
+            item.set_action_and_target_value(
+                Some("dock.move"),
+                Some(&(address.to_string(), workspace).to_variant()),
+            );
+            move_menu.append_item(&item);
+        }
+        section.append_submenu(Some("Move to workspace"), &move_menu);
+        section.append_item(&window_item("Close", "dock.close", address));
+
+        menu.append_section(Some(&section_label(client)), &section);
+    }
+
+    if !actions.is_empty() {
+        menu.append_section(None, &actions_menu(actions, "dock.action"));
+    }
+
+    let app_section = gio::Menu::new();
+    app_section.append(Some("New window"), Some("dock.new-window"));
+    match is_pinned {
+        true => app_section.append(Some("Unpin from dock"), Some("dock.unpin-dock")),
+        false => app_section.append(Some("Pin to dock"), Some("dock.pin-dock")),
+    }
+    menu.append_section(None, &app_section);
+
+    menu
+}
+
+fn window_item(label: &str, action: &str, address: &str) -> gio::MenuItem {
+    let item = gio::MenuItem::new(Some(label), None);
+    item.set_action_and_target_value(Some(action), Some(&address.to_variant()));
+    item
+}
+
+fn section_label(client: &Client) -> String {
+    let mut title = client
+        .title
+        .chars()
+        .take(TITLE_CHARS_LIMIT)
+        .collect::<String>();
 
-    let list_box = gtk::ListBox::new();
-    list_box.set_selection_mode(gtk::SelectionMode::Single);
-    window.set_child(Some(&list_box));
+    if client.title.chars().count() > TITLE_CHARS_LIMIT {
+        title.push('…');
+    }
 
-    window
+    format!("{} — {}", title, client.workspace.name)
 }
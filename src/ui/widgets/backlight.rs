@@ -0,0 +1,212 @@
+use gtk::{glib, prelude::*};
+use std::{
+    cell::{Cell, RefCell},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use crate::{
+    bar::set_popover,
+    enums::widgets::OsdKind,
+    ui::{gestures::WidgetActions, osd::Osd, widgets::set_markup},
+    user::models::WidgetsConfig,
+    utils::{
+        backlight::{self, Backlight},
+        format::{Format, Values},
+    },
+};
+
+const DEFAULT_FORMAT: &str = "{icon} {percent}%";
+const DEFAULT_TOOLTIP: &str = "{device}: {percent}%";
+const DEFAULT_STEP: f64 = 5.0;
+
+#[derive(Clone)]
+struct BacklightWidget {
+    button: gtk::Button,
+    label: gtk::Label,
+    format: Format,
+    scale: gtk::Scale,
+    popover: gtk::Popover,
+    osd: Osd,
+    root: PathBuf,
+    device: Option<String>,
+    current: Rc<RefCell<Option<Backlight>>>,
+    // Values written through logind that sysfs hasn't reported yet, oldest
+    // first.
+    pending: Rc<RefCell<Vec<u32>>>,
+    // Set while the slider follows sysfs, so that does not echo back.
+    updating: Rc<Cell<bool>>,
+}
+
+// Reads sysfs on every inotify event and writes through logind, so the
+// bar follows changes made by keybinds and other tools too.
+pub fn render(config: Option<&WidgetsConfig>, osd: &Osd) -> (gtk::Widget, WidgetActions) {
+    let label = gtk::Label::new(None);
+    let button = gtk::Button::builder().child(&label).build();
+    button.add_css_class("backlight");
+
+    let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 1.0, 100.0, 1.0);
+    scale.add_css_class("backlight-scale");
+    scale.set_draw_value(true);
+    scale.set_digits(0);
+    scale.set_value_pos(gtk::PositionType::Right);
+    let popover = set_popover(&button, scale.clone().into());
+
+    let widget = BacklightWidget {
+        button: button.clone(),
+        label,
+        format: Format::new(config.map(|c| &c.formats), DEFAULT_FORMAT)
+            .with_default_tooltip(DEFAULT_TOOLTIP),
+        scale: scale.clone(),
+        popover: popover.clone(),
+        osd: osd.clone(),
+        root: config
+            .and_then(|c| c.root.clone())
+            .unwrap_or_else(|| backlight::DEFAULT_ROOT.to_string())
+            .into(),
+        device: config.and_then(|c| c.device.clone()),
+        current: Rc::default(),
+        pending: Rc::default(),
+        updating: Rc::default(),
+    };
+    widget.refresh();
+
+    let name = widget.current.borrow().as_ref().map(|b| b.name.clone());
+    match name.map(|name| backlight::watch(&widget.root.join(name))) {
+        Some(Ok(changes)) => {
+            let widget = widget.clone();
+            glib::MainContext::default().spawn_local(async move {
+                while changes.recv().await.is_ok() {
+                    // Fading drivers write several steps for one change.
+                    while changes.try_recv().is_ok() {}
+                    widget.refresh();
+                }
+            });
+        }
+        Some(Err(e)) => eprintln!("Failed to watch the backlight: {}", e),
+        None => {}
+    }
+
+    scale.connect_value_changed({
+        let widget = widget.clone();
+        move |scale| {
+            if !widget.updating.get() {
+                widget.set_percent(scale.value());
+            }
+        }
+    });
+
+    let step = config.and_then(|c| c.step).unwrap_or(DEFAULT_STEP);
+    let format = widget.format.clone();
+    let actions = WidgetActions::activate(move || popover.popup())
+        .with_steps(
+            {
+                let widget = widget.clone();
+                move || widget.change(step)
+            },
+            {
+                let widget = widget.clone();
+                move || widget.change(-step)
+            },
+        )
+        .with_alt(&format, move || widget.redraw());
+    (button.into(), actions)
+}
+
+impl BacklightWidget {
+    fn refresh(&self) {
+        let backlight = backlight::read(&self.root, self.device.as_deref());
+        // One of the widget's own writes, maybe an older step of a quick
+        // scroll; `current` already holds the latest one.
+        if let Some(backlight) = &backlight {
+            let mut pending = self.pending.borrow_mut();
+            if let Some(index) = pending
+                .iter()
+                .position(|value| *value == backlight.brightness)
+            {
+                pending.drain(..=index);
+                return;
+            }
+            pending.clear();
+        }
+        let previous = self.current.replace(backlight.clone());
+        self.redraw();
+
+        // Only changes made outside the bar.
+        let (Some(previous), Some(backlight)) = (previous, backlight) else {
+            return;
+        };
+        if previous.name == backlight.name
+            && previous.brightness != backlight.brightness
+            && !self.popover.is_visible()
+        {
+            self.osd
+                .show(OsdKind::Brightness, backlight.percent(), false);
+        }
+    }
+
+    fn redraw(&self) {
+        let current = self.current.borrow().clone();
+        let Some(backlight) = current else {
+            self.button.set_visible(false);
+            return;
+        };
+        self.button.set_visible(true);
+
+        let percent = backlight.percent().round();
+        let values = Values::from([
+            ("percent", format!("{:.0}", percent)),
+            ("icon", OsdKind::Brightness.icon(percent, false).to_string()),
+            ("device", backlight.name.clone()),
+            ("brightness", backlight.brightness.to_string()),
+            ("max", backlight.max.to_string()),
+        ]);
+        set_markup(&self.label, &self.format.text(&values));
+        self.button
+            .set_tooltip_markup(self.format.tooltip(&values).as_deref());
+
+        self.updating.set(true);
+        self.scale.set_value(percent);
+        self.updating.set(false);
+    }
+
+    // Scrolling shows the OSD right away, sysfs only confirms the value
+    // that is already kept.
+    fn change(&self, step: f64) {
+        let Some(percent) = self.current.borrow().as_ref().map(Backlight::percent) else {
+            return;
+        };
+        self.set_percent(percent + step);
+        self.redraw();
+        if let Some(backlight) = self.current.borrow().as_ref() {
+            self.osd
+                .show(OsdKind::Brightness, backlight.percent(), false);
+        }
+    }
+
+    // The new value is kept right away, so steps made before sysfs reports
+    // the previous one add up.
+    fn set_percent(&self, percent: f64) {
+        let mut current = self.current.borrow_mut();
+        let Some(backlight) = current.as_mut() else {
+            return;
+        };
+        let value = backlight.value_for(percent);
+        if value == backlight.brightness {
+            return;
+        }
+        backlight.brightness = value;
+        self.pending.borrow_mut().push(value);
+        let (widget, name) = (self.clone(), backlight.name.clone());
+        glib::MainContext::default().spawn_local(async move {
+            if let Err(e) = backlight::set_brightness(&name, value).await {
+                eprintln!("Failed to set the brightness of {}: {}", name, e);
+                widget
+                    .pending
+                    .borrow_mut()
+                    .retain(|pending| *pending != value);
+                widget.refresh();
+            }
+        });
+    }
+}
@@ -0,0 +1,163 @@
+use gtk::{glib, prelude::*};
+use std::{cell::RefCell, path::PathBuf, rc::Rc, time::Duration};
+
+use crate::{
+    bar::set_popover,
+    ui::{gestures::WidgetActions, graph::Graph, widgets::set_markup},
+    user::models::WidgetsConfig,
+    utils::{
+        battery::{self, BatteryState, BatteryStatus},
+        format::{Format, Values},
+        uevent,
+    },
+};
+
+const DEFAULT_FORMAT: &str = "{percent}%{?charging| ⚡}";
+const DEFAULT_TOOLTIP: &str = "{state}{?time| · {time}}{?power| · {power} W}";
+const DEFAULT_INTERVAL_SECS: u64 = 60;
+const DEFAULT_WARNING: f64 = 30.0;
+const DEFAULT_CRITICAL: f64 = 15.0;
+const WARNING_CLASS: &str = "warning";
+const CRITICAL_CLASS: &str = "critical";
+
+#[derive(Clone)]
+struct BatteryWidget {
+    button: gtk::Button,
+    label: gtk::Label,
+    format: Format,
+    graph: Graph,
+    root: PathBuf,
+    names: Vec<String>,
+    warning: f64,
+    critical: f64,
+    classes: Rc<RefCell<Vec<&'static str>>>,
+}
+
+// Refreshes on power_supply uevents, with a slow poll since not every
+// battery reports capacity changes.
+pub fn render(config: Option<&WidgetsConfig>) -> (gtk::Widget, WidgetActions) {
+    let label = gtk::Label::new(None);
+    let graph = Graph::new(config, Some(100.0), |value| format!("{:.0}%", value));
+    let content = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    content.append(&label);
+    if let Some(inline) = graph.inline() {
+        content.append(&inline);
+    }
+    let button = gtk::Button::builder().child(&content).build();
+    button.add_css_class("battery");
+
+    let widget = BatteryWidget {
+        button: button.clone(),
+        label,
+        format: Format::new(config.map(|c| &c.formats), DEFAULT_FORMAT)
+            .with_default_tooltip(DEFAULT_TOOLTIP),
+        graph: graph.clone(),
+        root: config
+            .and_then(|c| c.root.clone())
+            .unwrap_or_else(|| battery::DEFAULT_ROOT.to_string())
+            .into(),
+        names: config.and_then(|c| c.batteries.clone()).unwrap_or_default(),
+        warning: config.and_then(|c| c.warning).unwrap_or(DEFAULT_WARNING),
+        critical: config.and_then(|c| c.critical).unwrap_or(DEFAULT_CRITICAL),
+        classes: Rc::default(),
+    };
+    widget.update(true);
+
+    let interval = config
+        .and_then(|c| c.interval)
+        .unwrap_or(DEFAULT_INTERVAL_SECS);
+    glib::timeout_add_local(Duration::from_secs(interval.max(1)), {
+        let widget = widget.clone();
+        move || {
+            widget.update(true);
+            glib::ControlFlow::Continue
+        }
+    });
+
+    match uevent::watch("power_supply") {
+        Ok(events) => {
+            let widget = widget.clone();
+            glib::MainContext::default().spawn_local(async move {
+                while events.recv().await.is_ok() {
+                    // Plugging in sends a burst of events, one read is enough.
+                    while events.try_recv().is_ok() {}
+                    widget.update(true);
+                }
+            });
+        }
+        Err(e) => eprintln!("Failed to watch power supply events: {}", e),
+    }
+
+    let format = widget.format.clone();
+    let history = set_popover(&button, graph.expanded());
+    let actions = WidgetActions::activate(move || history.popup())
+        .with_alt(&format, move || widget.update(false));
+    (button.into(), actions)
+}
+
+impl BatteryWidget {
+    // `record` adds the reading to the graph, redraws for the alt format
+    // should not.
+    fn update(&self, record: bool) {
+        let Some(status) = battery::read(&self.root, &self.names) else {
+            self.button.set_visible(false);
+            return;
+        };
+        self.button.set_visible(true);
+        if record {
+            self.graph.push(status.capacity);
+        }
+
+        let values = values(&status);
+        set_markup(&self.label, &self.format.text(&values));
+        self.button
+            .set_tooltip_markup(self.format.tooltip(&values).as_deref());
+
+        let mut classes = self.classes.borrow_mut();
+        for class in classes.drain(..) {
+            self.button.remove_css_class(class);
+        }
+        classes.push(status.state.as_str());
+        if status.state != BatteryState::Charging && !status.plugged {
+            match status.capacity {
+                capacity if capacity <= self.critical => classes.push(CRITICAL_CLASS),
+                capacity if capacity <= self.warning => classes.push(WARNING_CLASS),
+                _ => {}
+            }
+        }
+        for class in classes.iter() {
+            self.button.add_css_class(class);
+        }
+    }
+}
+
+fn values(status: &BatteryStatus) -> Values<'static> {
+    let charging = status.state == BatteryState::Charging;
+    Values::from([
+        ("percent", format!("{:.0}", status.capacity)),
+        ("state", status.state.as_str().replace('-', " ")),
+        (
+            "charging",
+            if charging { "charging" } else { "" }.to_string(),
+        ),
+        (
+            "plugged",
+            if status.plugged { "plugged" } else { "" }.to_string(),
+        ),
+        (
+            "time",
+            status
+                .time
+                .map(battery::format_duration)
+                .unwrap_or_default(),
+        ),
+        (
+            "power",
+            status
+                .power
+                .map(|power| format!("{:.1}", power))
+                .unwrap_or_default(),
+        ),
+        ("batteries", status.batteries.to_string()),
+    ])
+}
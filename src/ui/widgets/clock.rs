@@ -1,37 +1,122 @@
-use chrono::Local;
+use chrono::{
+    DateTime, Local,
+    format::{Item, StrftimeItems},
+};
 use glib::ControlFlow;
 use gtk::{Button, prelude::*};
 use std::{cell::Cell, rc::Rc};
 
-use crate::bar::set_popover;
-
-pub fn render(is_visible: &Rc<Cell<bool>>) -> gtk::Widget {
-    let fomat = "%I:%M %P\n%d/%m/%Y";
-    let clock_label = gtk::Label::new(Some(Local::now().format(fomat).to_string().as_str()));
-
-    let clock_container = Button::builder()
-        .halign(gtk::Align::Center)
-        .valign(gtk::Align::Center)
-        .build();
-    clock_container.add_css_class("clock-container");
-    clock_container.set_child(Some(&clock_label));
-
-    let clock_label = Rc::new(clock_label);
-    let is_visible = Rc::clone(is_visible);
-    glib::timeout_add_local(std::time::Duration::from_secs(1), {
-        let clock_label = Rc::clone(&clock_label);
-        move || {
-            if is_visible.get() {
-                let now = chrono::Local::now();
-                clock_label.set_label(&now.format(fomat).to_string());
+use crate::{
+    bar::set_popover,
+    ui::{gestures::WidgetActions, widgets::set_markup},
+    user::models::UserConfig,
+    utils::format::{Format, Values},
+};
+
+// The rendered template is passed through strftime, so both `{weekday}` style
+// placeholders and `%H:%M` specifiers work.
+pub const DEFAULT_FORMAT: &str = "%I:%M %P\n%d/%m/%Y";
+const DEFAULT_TOOLTIP: &str = "%A, %B %d, %Y";
+const LEGACY_TOKENS: [&str; 4] = ["HH", "hh", "mm", "ss"];
+
+pub fn format(user_config: &UserConfig) -> Format {
+    let config = user_config.widgets.get("clock").map(|c| &c.formats);
+    // Older configs shipped moment-style patterns such as "HH:mm", which
+    // strftime shows as written.
+    if let Some(format) = config.and_then(|c| c.format.as_deref())
+        && !format.contains(['%', '{'])
+        && LEGACY_TOKENS.iter().any(|token| format.contains(token))
+    {
+        eprintln!(
+            "Clock format \"{}\" looks like a moment pattern, use strftime specifiers such as %H:%M",
+            format
+        );
+    }
+    Format::new(config, DEFAULT_FORMAT)
+}
+
+#[derive(Clone)]
+pub struct ClockWidget {
+    root: Button,
+    calendar: gtk::Popover,
+    format: Format,
+    redraw: Rc<dyn Fn()>,
+}
+
+impl ClockWidget {
+    pub fn new(is_visible: &Rc<Cell<bool>>, format: Format) -> Self {
+        let clock_label = gtk::Label::new(None);
+
+        let clock_container = Button::builder()
+            .halign(gtk::Align::Center)
+            .valign(gtk::Align::Center)
+            .build();
+        clock_container.add_css_class("clock-container");
+        clock_container.set_child(Some(&clock_label));
+
+        let redraw: Rc<dyn Fn()> = Rc::new({
+            let clock_label = clock_label.clone();
+            let clock_container = clock_container.clone();
+            let format = format.clone();
+            move || {
+                let now = Local::now();
+                let values = values(&now);
+                set_markup(&clock_label, &strftime(&now, &format.text(&values)));
+
+                let tooltip = format
+                    .tooltip(&values)
+                    .unwrap_or_else(|| DEFAULT_TOOLTIP.to_string());
+                clock_container.set_tooltip_markup(Some(&strftime(&now, &tooltip)));
             }
-            ControlFlow::Continue
+        });
+        redraw();
+
+        let is_visible = Rc::clone(is_visible);
+        glib::timeout_add_local(std::time::Duration::from_secs(1), {
+            let redraw = Rc::clone(&redraw);
+            move || {
+                if is_visible.get() {
+                    redraw();
+                }
+                ControlFlow::Continue
+            }
+        });
+
+        let calendar = set_popover(&clock_container, panels::calendar::render());
+
+        Self {
+            root: clock_container,
+            calendar,
+            format,
+            redraw,
         }
-    });
+    }
 
-    clock_container.set_tooltip_markup(Some(&Local::now().format("%A, %B %d, %Y").to_string()));
-    let calendar_window = panels::calendar::render();
-    set_popover(&clock_container, calendar_window);
+    pub fn widget(&self) -> gtk::Widget {
+        self.root.clone().into()
+    }
+
+    pub fn actions(&self) -> WidgetActions {
+        let calendar = self.calendar.clone();
+        let redraw = Rc::clone(&self.redraw);
+        WidgetActions::activate(move || calendar.popup()).with_alt(&self.format, move || redraw())
+    }
+}
+
+fn values(now: &DateTime<Local>) -> Values<'static> {
+    Values::from([
+        ("hour", now.format("%H").to_string()),
+        ("minute", now.format("%M").to_string()),
+        ("weekday", now.format("%A").to_string().to_lowercase()),
+    ])
+}
 
-    clock_container.into()
+// Invalid specifiers would make chrono fail while formatting, so the pattern
+// is shown as is instead.
+fn strftime(now: &DateTime<Local>, pattern: &str) -> String {
+    let items = StrftimeItems::new(pattern).collect::<Vec<_>>();
+    if items.contains(&Item::Error) {
+        return pattern.to_string();
+    }
+    now.format_with_items(items.into_iter()).to_string()
 }
@@ -0,0 +1,355 @@
+use gtk::{glib, prelude::*};
+use std::{cell::RefCell, path::Path, rc::Rc, time::Duration};
+use tokio::sync::watch;
+
+use crate::{
+    bar::set_popover,
+    ui::{gestures::WidgetActions, graph::Graph, widgets::set_markup},
+    user::models::WidgetsConfig,
+    utils::{
+        format::{Format, Values},
+        network_manager::{Change, Connection, NetworkManager, NetworkState},
+        procfs::{Sample, Throughput},
+        rtnetlink::{self, Snapshot},
+    },
+};
+
+const DEFAULT_FORMAT: &str = "{?ssid|{ssid} {signal}%|{?ifname|{ifname}|Disconnected}}";
+const DEFAULT_TOOLTIP: &str = "{?ifname|{ifname}{?ipaddr| · {ipaddr}/{cidr}}{?gateway|\nvia {gateway}}\n↓ {down} ↑ {up}|No connection}";
+const SYS_CLASS_NET: &str = "/sys/class/net";
+// Switching connections sends a burst of PropertiesChanged, so reads are
+// batched.
+const MANAGER_REFRESH_DELAY: Duration = Duration::from_millis(500);
+const KINDS: [&str; 3] = ["wifi", "ethernet", "disconnected"];
+
+#[derive(Debug, Clone, Default)]
+struct NetworkInfo {
+    snapshot: Snapshot,
+    manager: NetworkState,
+    throughput: Throughput,
+}
+
+#[derive(Clone)]
+struct NetworkWidget {
+    button: gtk::Button,
+    label: gtk::Label,
+    format: Format,
+    graph: Graph,
+    // A fixed interface instead of the one holding the default route.
+    interface: Option<String>,
+    info: Rc<RefCell<NetworkInfo>>,
+    manager: Rc<RefCell<Option<NetworkManager>>>,
+    manager_refresh: Rc<RefCell<Option<glib::SourceId>>>,
+    connections: gtk::ListBox,
+}
+
+pub fn render(
+    config: Option<&WidgetsConfig>,
+    mut samples: watch::Receiver<Sample>,
+) -> (gtk::Widget, WidgetActions) {
+    let label = gtk::Label::new(None);
+    let graph = Graph::new(config, None, format_rate);
+    let content = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    content.append(&label);
+    if let Some(inline) = graph.inline() {
+        content.append(&inline);
+    }
+    let button = gtk::Button::builder().child(&content).build();
+    button.add_css_class("network");
+
+    let connections = gtk::ListBox::new();
+    connections.set_selection_mode(gtk::SelectionMode::None);
+    connections.add_css_class("network-connections");
+
+    let widget = NetworkWidget {
+        button: button.clone(),
+        label,
+        format: Format::new(config.map(|c| &c.formats), DEFAULT_FORMAT)
+            .with_default_tooltip(DEFAULT_TOOLTIP),
+        graph: graph.clone(),
+        interface: config.and_then(|c| c.interface.clone()),
+        info: Rc::default(),
+        manager: Rc::default(),
+        manager_refresh: Rc::default(),
+        connections: connections.clone(),
+    };
+    widget.read_links();
+    widget.fill_connections();
+
+    match rtnetlink::watch() {
+        Ok(changes) => {
+            let widget = widget.clone();
+            glib::MainContext::default().spawn_local(async move {
+                while changes.recv().await.is_ok() {
+                    // Bringing a link up sends a burst of messages.
+                    while changes.try_recv().is_ok() {}
+                    widget.read_links();
+                }
+            });
+        }
+        Err(e) => eprintln!("Failed to watch network changes: {}", e),
+    }
+
+    glib::MainContext::default().spawn_local({
+        let widget = widget.clone();
+        async move {
+            while samples.changed().await.is_ok() {
+                let network = samples.borrow().network.clone();
+                widget.update_throughput(|name| network.get(name).copied());
+            }
+        }
+    });
+
+    glib::MainContext::default().spawn_local({
+        let widget = widget.clone();
+        async move {
+            let manager = match NetworkManager::system().await {
+                Ok(manager) => manager,
+                Err(e) => return eprintln!("Failed to connect to the system bus: {}", e),
+            };
+            manager.watch({
+                let widget = widget.clone();
+                move |change| widget.manager_changed(change)
+            });
+            *widget.manager.borrow_mut() = Some(manager);
+            widget.refresh_manager();
+        }
+    });
+
+    let panel = gtk::Box::new(gtk::Orientation::Vertical, 6);
+    panel.add_css_class("network-panel");
+    panel.append(&graph.expanded());
+    panel.append(&gtk::Separator::new(gtk::Orientation::Horizontal));
+    panel.append(&connections);
+    let popover = set_popover(&button, panel.into());
+    popover.connect_visible_notify({
+        let widget = widget.clone();
+        move |popover| {
+            if popover.is_visible() {
+                widget.refresh_manager();
+            }
+        }
+    });
+
+    let format = widget.format.clone();
+    let actions =
+        WidgetActions::activate(move || popover.popup()).with_alt(&format, move || widget.redraw());
+    (button.into(), actions)
+}
+
+impl NetworkWidget {
+    fn read_links(&self) {
+        match Snapshot::read() {
+            Ok(snapshot) => self.info.borrow_mut().snapshot = snapshot,
+            Err(e) => eprintln!("Failed to read network interfaces: {}", e),
+        }
+        self.redraw();
+    }
+
+    fn update_throughput(&self, throughput: impl Fn(&str) -> Option<Throughput>) {
+        let name = self.interface_name();
+        let current = name.as_deref().and_then(throughput).unwrap_or_default();
+        self.info.borrow_mut().throughput = current;
+        self.graph.push(current.rx + current.tx);
+        self.redraw();
+    }
+
+    fn manager_changed(&self, change: Change) {
+        if let Change::AccessPoint(path) = change {
+            let info = self.info.borrow();
+            let wireless = info.manager.wireless.as_ref();
+            if wireless.is_none_or(|wireless| wireless.path != path) {
+                return;
+            }
+        }
+        self.schedule_manager_refresh();
+    }
+
+    fn schedule_manager_refresh(&self) {
+        if self.manager_refresh.borrow().is_some() {
+            return;
+        }
+        let widget = self.clone();
+        let source = glib::timeout_add_local_once(MANAGER_REFRESH_DELAY, move || {
+            widget.manager_refresh.borrow_mut().take();
+            widget.refresh_manager();
+        });
+        *self.manager_refresh.borrow_mut() = Some(source);
+    }
+
+    fn refresh_manager(&self) {
+        let Some(manager) = self.manager.borrow().clone() else {
+            return;
+        };
+        let widget = self.clone();
+        glib::MainContext::default().spawn_local(async move {
+            match manager.state().await {
+                Ok(state) => widget.info.borrow_mut().manager = state,
+                // Without NetworkManager the widget still works from netlink.
+                Err(e) if manager.is_gone(&e) => {
+                    eprintln!("NetworkManager is not available: {}", e);
+                    widget.manager.borrow_mut().take();
+                }
+                // The next change reads everything again.
+                Err(e) => eprintln!("Failed to read NetworkManager state: {}", e),
+            }
+            widget.redraw();
+            widget.fill_connections();
+        });
+    }
+
+    fn interface_name(&self) -> Option<String> {
+        if let Some(interface) = &self.interface {
+            return Some(interface.clone());
+        }
+        let info = self.info.borrow();
+        let route = info.snapshot.default_route()?;
+        info.snapshot
+            .link(route.index)
+            .map(|link| link.name.clone())
+    }
+
+    fn redraw(&self) {
+        let name = self.interface_name();
+        let info = self.info.borrow();
+        let values = values(&info, name.as_deref());
+        set_markup(&self.label, &self.format.text(&values));
+        self.button
+            .set_tooltip_markup(self.format.tooltip(&values).as_deref());
+
+        for kind in KINDS {
+            self.button.remove_css_class(kind);
+        }
+        self.button.add_css_class(&values["kind"]);
+    }
+
+    fn fill_connections(&self) {
+        while let Some(row) = self.connections.first_child() {
+            self.connections.remove(&row);
+        }
+
+        let Some(manager) = self.manager.borrow().clone() else {
+            let label = gtk::Label::new(Some("NetworkManager is not available"));
+            label.add_css_class("network-unavailable");
+            self.connections.append(&label);
+            return;
+        };
+        let connections = self.info.borrow().manager.connections.clone();
+        if connections.is_empty() {
+            self.connections
+                .append(&gtk::Label::new(Some("No saved connections")));
+        }
+        for connection in connections {
+            self.connections
+                .append(&self.connection_row(&manager, connection));
+        }
+    }
+
+    fn connection_row(&self, manager: &NetworkManager, connection: Connection) -> gtk::Box {
+        let row = gtk::Box::new(gtk::Orientation::Horizontal, 8);
+        row.add_css_class("network-connection");
+        if connection.active.is_some() {
+            row.add_css_class("active");
+        }
+
+        let icon = if connection.is_wireless() {
+            "󰖩"
+        } else {
+            "󰈀"
+        };
+        let name = gtk::Label::new(Some(&format!("{} {}", icon, connection.id)));
+        name.set_hexpand(true);
+        name.set_xalign(0.0);
+        let toggle = gtk::Button::with_label(match connection.active {
+            Some(_) => "Disconnect",
+            None => "Connect",
+        });
+        toggle.connect_clicked({
+            let (manager, widget) = (manager.clone(), self.clone());
+            move |toggle| {
+                toggle.set_sensitive(false);
+                let (manager, widget, connection) =
+                    (manager.clone(), widget.clone(), connection.clone());
+                glib::MainContext::default().spawn_local(async move {
+                    let result = match connection.active {
+                        Some(_) => manager.disconnect(&connection).await,
+                        None => manager.connect(&connection).await,
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Failed to switch {}: {}", connection.id, e);
+                    }
+                    widget.refresh_manager();
+                });
+            }
+        });
+
+        row.append(&name);
+        row.append(&toggle);
+        row
+    }
+}
+
+fn values(info: &NetworkInfo, name: Option<&str>) -> Values<'static> {
+    let snapshot = &info.snapshot;
+    let link = name.and_then(|name| snapshot.links.iter().find(|link| link.name == name));
+    let Some(link) = link.filter(|link| link.up) else {
+        return Values::from([("kind", "disconnected".to_string())]);
+    };
+
+    let mut values = Values::new();
+    let wireless = info.manager.wireless.clone().or_else(|| {
+        Path::new(SYS_CLASS_NET)
+            .join(&link.name)
+            .join("wireless")
+            .exists()
+            .then(Default::default)
+    });
+    values.insert(
+        "kind",
+        if wireless.is_some() {
+            "wifi"
+        } else {
+            "ethernet"
+        }
+        .to_string(),
+    );
+    if let Some(wireless) = wireless.filter(|wireless| !wireless.ssid.is_empty()) {
+        values.insert("ssid", wireless.ssid);
+        values.insert("signal", wireless.strength.to_string());
+    }
+    if let Some(connection) = info.manager.primary() {
+        values.insert("connection", connection.id.clone());
+    }
+
+    values.insert("ifname", link.name.clone());
+    if let Some(address) = snapshot.address(link.index) {
+        values.insert("ipaddr", address.address.to_string());
+        values.insert("cidr", address.prefix.to_string());
+    }
+    let gateway = snapshot
+        .routes
+        .iter()
+        .filter(|route| route.index == link.index)
+        .min_by_key(|route| (route.ipv6, route.metric))
+        .and_then(|route| route.gateway);
+    if let Some(gateway) = gateway {
+        values.insert("gateway", gateway.to_string());
+    }
+    values.insert("down", format_rate(info.throughput.rx));
+    values.insert("up", format_rate(info.throughput.tx));
+    values
+}
+
+fn format_rate(bytes: f64) -> String {
+    const UNITS: [&str; 4] = ["B/s", "KB/s", "MB/s", "GB/s"];
+    let mut value = bytes.max(0.0);
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{:.0} {}", value, UNITS[unit]),
+        _ => format!("{:.1} {}", value, UNITS[unit]),
+    }
+}
@@ -0,0 +1,251 @@
+use gtk::{glib, pango, prelude::*};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    signal::unix::{SignalKind, signal},
+    sync::Notify,
+    task::JoinHandle,
+};
+
+use crate::{
+    enums::widgets::CustomKind,
+    ui::{gestures::WidgetActions, widgets::set_markup},
+    user::models::CustomAppsConfig,
+    utils::{
+        format::{Format, Values},
+        script::{self, Backoff, ScriptError, ScriptOutput, ScriptResult},
+    },
+};
+
+// Scripts print markup of their own, as in Waybar.
+const DEFAULT_FORMAT: &str = "{?text|{text|raw}|{?percentage|{percentage}%}}";
+const DEFAULT_TOOLTIP: &str = "{tooltip|raw}";
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+const ERROR_CLASS: &str = "error";
+const ERROR_TEXT: &str = "⚠";
+
+// The returned handle re-runs the script, or restarts a streaming one.
+pub fn render(
+    name: &str,
+    config: &CustomAppsConfig,
+    kind: CustomKind,
+) -> (gtk::Widget, WidgetActions, Arc<Notify>) {
+    let label = gtk::Label::new(None);
+    let button = gtk::Button::builder().child(&label).build();
+    button.add_css_class("custom-app");
+    button.add_css_class("script");
+    button.set_widget_name(name);
+    button.set_visible(false);
+
+    let refresh = Arc::new(Notify::new());
+    let format =
+        Format::new(Some(&config.formats), DEFAULT_FORMAT).with_default_tooltip(DEFAULT_TOOLTIP);
+    let output = Rc::new(RefCell::new(None::<ScriptOutput>));
+    // Weak, the actions end up on the button itself.
+    let redraw = {
+        let (button, label) = (button.downgrade(), label.downgrade());
+        let (format, output) = (format.clone(), Rc::clone(&output));
+        move || {
+            let (Some(button), Some(label)) = (button.upgrade(), label.upgrade()) else {
+                return;
+            };
+            if let Some(output) = output.borrow().as_ref() {
+                show_output(&button, &label, &format, output);
+            }
+        }
+    };
+    let actions = WidgetActions {
+        after_command: Some(Rc::new({
+            let refresh = Arc::clone(&refresh);
+            move || refresh.notify_one()
+        })),
+        ..Default::default()
+    }
+    .with_alt(&format, redraw);
+
+    let Some(exec) = config.exec.clone() else {
+        eprintln!("Script widget {} has no exec command", name);
+        return (button.into(), actions, refresh);
+    };
+
+    let (sender, receiver) = async_channel::bounded::<ScriptResult>(1);
+    let mut tasks = vec![match kind {
+        CustomKind::Stream => tokio::spawn(watch(exec, sender, Arc::clone(&refresh))),
+        _ => {
+            let interval = config.interval.map(Duration::from_secs);
+            let timeout = Duration::from_secs(config.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+            tokio::spawn(poll(exec, interval, timeout, sender, Arc::clone(&refresh)))
+        }
+    }];
+    if let Some(offset) = config.signal {
+        tasks.extend(listen_signal(name, offset, &refresh));
+    }
+    // The script and its signal listener stop with the widget.
+    button.connect_destroy({
+        let tasks = RefCell::new(tasks);
+        move |_| {
+            for task in tasks.take() {
+                task.abort();
+            }
+        }
+    });
+
+    let name = name.to_string();
+    let button_weak = button.downgrade();
+    let label_weak = label.downgrade();
+    glib::MainContext::default().spawn_local(async move {
+        let mut classes = Vec::new();
+        while let Ok(result) = receiver.recv().await {
+            let (Some(button), Some(label)) = (button_weak.upgrade(), label_weak.upgrade()) else {
+                break;
+            };
+            match result {
+                Ok(result) => {
+                    apply_classes(&button, &mut classes, &result);
+                    show_output(&button, &label, &format, &result);
+                    *output.borrow_mut() = Some(result);
+                }
+                Err(e) => apply_error(&button, &label, &name, &e),
+            }
+        }
+    });
+
+    (button.into(), actions, refresh)
+}
+
+// Runs the script every `interval`, or once when there is none, and again
+// whenever a refresh is requested.
+async fn poll(
+    exec: String,
+    interval: Option<Duration>,
+    timeout: Duration,
+    sender: async_channel::Sender<ScriptResult>,
+    refresh: Arc<Notify>,
+) {
+    loop {
+        let result = script::run(&exec, timeout).await;
+        if sender.send(result).await.is_err() {
+            return;
+        }
+
+        match interval {
+            Some(interval) => tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = refresh.notified() => {}
+            },
+            None => refresh.notified().await,
+        }
+    }
+}
+
+// Keeps the script running, restarting it with a growing delay whenever it
+// exits. A refresh restarts it right away.
+async fn watch(exec: String, sender: async_channel::Sender<ScriptResult>, refresh: Arc<Notify>) {
+    let mut backoff = Backoff::default();
+    loop {
+        let started = Instant::now();
+        tokio::select! {
+            error = script::stream(&exec, &sender) => {
+                let Some(error) = error else {
+                    return;
+                };
+                if sender.send(Err(error)).await.is_err() {
+                    return;
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff.next(started.elapsed())) => {}
+                    _ = refresh.notified() => backoff = Backoff::default(),
+                }
+            }
+            _ = refresh.notified() => backoff = Backoff::default(),
+        }
+    }
+}
+
+// SIGRTMIN+N refreshes the widget, as in Waybar: `pkill -RTMIN+N hybar`.
+fn listen_signal(name: &str, offset: i32, refresh: &Arc<Notify>) -> Option<JoinHandle<()>> {
+    let signum = libc::SIGRTMIN() + offset;
+    if offset < 0 || signum > libc::SIGRTMAX() {
+        eprintln!("Invalid signal RTMIN+{} for script widget {}", offset, name);
+        return None;
+    }
+
+    let name = name.to_string();
+    let refresh = Arc::downgrade(refresh);
+    let task = tokio::spawn(async move {
+        let mut signals = match signal(SignalKind::from_raw(signum)) {
+            Ok(signals) => signals,
+            Err(e) => {
+                eprintln!("Failed to listen for RTMIN+{} for {}: {}", offset, name, e);
+                return;
+            }
+        };
+        while signals.recv().await.is_some() {
+            let Some(refresh) = refresh.upgrade() else {
+                return;
+            };
+            refresh.notify_one();
+        }
+    });
+    Some(task)
+}
+
+fn apply_classes(button: &gtk::Button, classes: &mut Vec<String>, output: &ScriptOutput) {
+    button.remove_css_class(ERROR_CLASS);
+    for class in classes.drain(..) {
+        button.remove_css_class(&class);
+    }
+    classes.extend(output.class.iter().chain(&output.alt).cloned());
+    for class in classes.iter() {
+        button.add_css_class(class);
+    }
+}
+
+fn show_output(button: &gtk::Button, label: &gtk::Label, format: &Format, output: &ScriptOutput) {
+    let values = values(output);
+    let text = format.text(&values);
+    set_markup(label, &text);
+    match format
+        .tooltip(&values)
+        .filter(|tooltip| !tooltip.is_empty())
+    {
+        Some(tooltip) if pango::parse_markup(&tooltip, '\0').is_ok() => {
+            button.set_tooltip_markup(Some(&tooltip))
+        }
+        tooltip => button.set_tooltip_text(tooltip.as_deref()),
+    }
+    button.set_visible(!text.is_empty());
+}
+
+fn values(output: &ScriptOutput) -> Values<'static> {
+    let mut values = Values::from([
+        ("text", output.text.clone()),
+        ("class", output.class.join(" ")),
+    ]);
+    if let Some(percentage) = output.percentage {
+        values.insert("percentage", format!("{:.0}", percentage));
+    }
+    if let Some(alt) = &output.alt {
+        values.insert("alt", alt.clone());
+    }
+    if let Some(tooltip) = &output.tooltip {
+        values.insert("tooltip", tooltip.clone());
+    }
+    values
+}
+
+// Errors keep the last output on screen so a flaky script doesn't make the
+// widget flicker, and explain themselves in the tooltip.
+fn apply_error(button: &gtk::Button, label: &gtk::Label, name: &str, error: &ScriptError) {
+    eprintln!("Script widget {}: {}", name, error);
+    button.add_css_class(ERROR_CLASS);
+    button.set_tooltip_text(Some(&error.to_string()));
+    if label.text().is_empty() {
+        label.set_text(ERROR_TEXT);
+    }
+    button.set_visible(true);
+}
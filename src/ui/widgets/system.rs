@@ -0,0 +1,178 @@
+use gtk::{glib, prelude::*};
+use tokio::sync::watch;
+
+use crate::{
+    bar::set_popover,
+    ui::{gestures::WidgetActions, graph::Graph, widgets::set_markup},
+    user::models::WidgetsConfig,
+    utils::{
+        format::{Format, Values},
+        procfs::Sample,
+    },
+};
+
+const DEFAULT_WARNING: f64 = 70.0;
+const DEFAULT_CRITICAL: f64 = 90.0;
+const WARNING_CLASS: &str = "warning";
+const CRITICAL_CLASS: &str = "critical";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Cpu,
+    Memory,
+    Load,
+}
+
+impl Metric {
+    fn class(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "cpu",
+            Metric::Memory => "memory",
+            Metric::Load => "load",
+        }
+    }
+
+    fn default_format(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "CPU {usage}%",
+            Metric::Memory => "RAM {percent}%",
+            Metric::Load => "LOAD {load1}",
+        }
+    }
+
+    fn default_tooltip(&self) -> &'static str {
+        match self {
+            Metric::Cpu => "{usage}% on {cores} cores, busiest at {max}%",
+            Metric::Memory => {
+                "{used} / {total} GiB used\n{available} GiB available{?swap_total|\nSwap {swap_used} / {swap_total} GiB}"
+            }
+            Metric::Load => "{load1} {load5} {load15}\n{running} of {processes} processes running",
+        }
+    }
+
+    // What the thresholds compare against. Load is relative to the number
+    // of cores, so 100 means every core is busy.
+    fn level(&self, sample: &Sample) -> Option<f64> {
+        match self {
+            Metric::Cpu => Some(sample.cpu.total),
+            Metric::Memory => sample.memory.as_ref().map(|memory| memory.percent()),
+            Metric::Load => {
+                let cores = sample.cpu.cores.len().max(1) as f64;
+                sample.load.as_ref().map(|load| load.one / cores * 100.0)
+            }
+        }
+    }
+}
+
+pub fn render(
+    metric: Metric,
+    config: Option<&WidgetsConfig>,
+    mut samples: watch::Receiver<Sample>,
+) -> (gtk::Widget, WidgetActions) {
+    let label = gtk::Label::new(None);
+    let graph = Graph::new(config, Some(100.0), |value| format!("{:.0}%", value));
+    let content = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+    content.append(&label);
+    if let Some(inline) = graph.inline() {
+        content.append(&inline);
+    }
+    let button = gtk::Button::builder().child(&content).build();
+    button.add_css_class("system-metric");
+    button.add_css_class(metric.class());
+
+    let format = Format::new(config.map(|c| &c.formats), metric.default_format())
+        .with_default_tooltip(metric.default_tooltip());
+    let warning = config.and_then(|c| c.warning).unwrap_or(DEFAULT_WARNING);
+    let critical = config.and_then(|c| c.critical).unwrap_or(DEFAULT_CRITICAL);
+
+    let redraw = {
+        let (button, format, samples) = (button.clone(), format.clone(), samples.clone());
+        move || {
+            let sample = samples.borrow().clone();
+            let core_keys = (0..sample.cpu.cores.len())
+                .map(|core| format!("core{}", core))
+                .collect::<Vec<_>>();
+            let values = values(metric, &sample, &core_keys);
+            set_markup(&label, &format.text(&values));
+            button.set_tooltip_markup(format.tooltip(&values).as_deref());
+
+            button.remove_css_class(WARNING_CLASS);
+            button.remove_css_class(CRITICAL_CLASS);
+            match metric.level(&sample) {
+                Some(level) if level >= critical => button.add_css_class(CRITICAL_CLASS),
+                Some(level) if level >= warning => button.add_css_class(WARNING_CLASS),
+                _ => {}
+            }
+        }
+    };
+    let record = {
+        let (graph, samples) = (graph.clone(), samples.clone());
+        move || {
+            if let Some(level) = metric.level(&samples.borrow()) {
+                graph.push(level);
+            }
+        }
+    };
+    redraw();
+    record();
+
+    glib::MainContext::default().spawn_local({
+        let redraw = redraw.clone();
+        async move {
+            while samples.changed().await.is_ok() {
+                redraw();
+                record();
+            }
+        }
+    });
+
+    let history = set_popover(&button, graph.expanded());
+    let actions = WidgetActions::activate(move || history.popup()).with_alt(&format, redraw);
+    (button.into(), actions)
+}
+
+fn values<'a>(metric: Metric, sample: &Sample, core_keys: &'a [String]) -> Values<'a> {
+    let mut values = Values::new();
+    match metric {
+        Metric::Cpu => {
+            let cores = &sample.cpu.cores;
+            values.insert("usage", format!("{:.0}", sample.cpu.total));
+            values.insert("cores", cores.len().to_string());
+            values.insert(
+                "max",
+                format!("{:.0}", cores.iter().copied().fold(0.0, f64::max)),
+            );
+            for (key, usage) in core_keys.iter().zip(cores) {
+                values.insert(key.as_str(), format!("{:.0}", usage));
+            }
+        }
+        Metric::Memory => {
+            let Some(memory) = &sample.memory else {
+                return values;
+            };
+            values.insert("percent", format!("{:.0}", memory.percent()));
+            values.insert("used", format!("{:.1}", memory.used()));
+            values.insert("total", format!("{:.1}", memory.total));
+            values.insert("available", format!("{:.1}", memory.available));
+            values.insert("swap_percent", format!("{:.0}", memory.swap_percent()));
+            values.insert("swap_used", format!("{:.1}", memory.swap_used()));
+            if memory.swap_total > 0.0 {
+                values.insert("swap_total", format!("{:.1}", memory.swap_total));
+            }
+        }
+        Metric::Load => {
+            let Some(load) = &sample.load else {
+                return values;
+            };
+            values.insert("load1", format!("{:.2}", load.one));
+            values.insert("load5", format!("{:.2}", load.five));
+            values.insert("load15", format!("{:.2}", load.fifteen));
+            values.insert("running", load.running.to_string());
+            values.insert("processes", load.processes.to_string());
+            if let Some(level) = metric.level(sample) {
+                values.insert("percent", format!("{:.0}", level));
+            }
+        }
+    }
+    values
+}
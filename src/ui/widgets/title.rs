@@ -1,16 +1,33 @@
 use gtk::{Orientation, pango, prelude::*};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    ui::{gestures::WidgetActions, widgets::set_markup},
+    utils::format::{Format, Values},
+};
+
+// Matches the old lowercased `class,title` label.
+pub const DEFAULT_FORMAT: &str = "{?class|{class|lower},{title|lower}}";
 
 #[derive(Debug, Clone)]
 pub struct TitleWidget {
     root: gtk::Widget,
+    icon: gtk::Box,
     title_label: gtk::Label,
+    format: Format,
+    values: Rc<RefCell<Values<'static>>>,
 }
 
 impl TitleWidget {
-    pub fn new() -> Self {
+    pub fn new(format: Format) -> Self {
         let title_container = gtk::Box::new(Orientation::Horizontal, 5);
         title_container.add_css_class("title-container");
 
+        let icon = gtk::Box::new(Orientation::Horizontal, 0);
+        icon.add_css_class("title-icon");
+        icon.set_visible(false);
+        title_container.append(&icon);
+
         let title_label = gtk::Label::new(Some(""));
         title_label.set_ellipsize(pango::EllipsizeMode::End);
         title_label.set_max_width_chars(100);
@@ -18,15 +35,44 @@ impl TitleWidget {
 
         Self {
             root: title_container.into(),
+            icon,
             title_label,
+            format,
+            values: Rc::default(),
         }
     }
 
+    pub fn actions(&self) -> WidgetActions {
+        let this = self.clone();
+        WidgetActions::default().with_alt(&self.format, move || this.redraw())
+    }
+
     pub fn widget(&self) -> &gtk::Widget {
         &self.root
     }
 
-    pub fn set_title(&self, title: &str) {
-        self.title_label.set_text(title);
+    pub fn set_title(&self, class: &str, title: &str) {
+        *self.values.borrow_mut() =
+            Values::from([("class", class.to_string()), ("title", title.to_string())]);
+        self.redraw();
+    }
+
+    fn redraw(&self) {
+        let values = self.values.borrow();
+        set_markup(&self.title_label, &self.format.text(&values));
+        if self.format.has_tooltip() {
+            self.root
+                .set_tooltip_markup(self.format.tooltip(&values).as_deref());
+        }
+    }
+
+    pub fn set_icon(&self, icon: Option<gtk::Widget>) {
+        while let Some(child) = self.icon.first_child() {
+            self.icon.remove(&child);
+        }
+        self.icon.set_visible(icon.is_some());
+        if let Some(icon) = icon {
+            self.icon.append(&icon);
+        }
     }
 }
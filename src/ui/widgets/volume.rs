@@ -0,0 +1,138 @@
+use gtk::prelude::*;
+use panels::mixer::{self, Mixer, State, pulse::Target};
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    enums::widgets::OsdKind,
+    ui::{
+        gestures::WidgetActions,
+        osd::Osd,
+        widgets::{determine_window_position, set_markup},
+    },
+    user::models::WidgetsConfig,
+    utils::format::{Format, Values},
+};
+
+const DEFAULT_FORMAT: &str = "{icon} {volume}%";
+const DEFAULT_TOOLTIP: &str = "{?device|{device}\n}{volume}%{?muted| (muted)}";
+const DEFAULT_STEP: f64 = 5.0;
+
+// The default sink: a click mutes it, scrolling changes its volume and a
+// right click opens the mixer with every device and application.
+pub fn render(config: Option<&WidgetsConfig>, osd: &Osd) -> (gtk::Widget, WidgetActions) {
+    let mixer = Mixer::new(None);
+    let window = mixer::build_ui(&mixer);
+    let label = gtk::Label::new(None);
+    let button = gtk::Button::builder().child(&label).build();
+    button.add_css_class("volume");
+    let format = Format::new(config.map(|c| &c.formats), DEFAULT_FORMAT)
+        .with_default_tooltip(DEFAULT_TOOLTIP);
+
+    let redraw = {
+        let (button, format, mixer) = (button.clone(), format.clone(), mixer.clone());
+        move || {
+            let state = mixer.state();
+            let values = values(state.as_ref());
+            set_markup(&label, &format.text(&values));
+            button.set_tooltip_markup(format.tooltip(&values).as_deref());
+            button.set_visible(state.is_some());
+            match values.contains_key("muted") {
+                true => button.add_css_class("muted"),
+                false => button.remove_css_class("muted"),
+            }
+        }
+    };
+    redraw();
+    mixer.connect_changed({
+        let redraw = redraw.clone();
+        move |_| redraw()
+    });
+
+    // Changes of the default sink, wherever they come from, except the
+    // mixer's own sliders which show the value already.
+    let shown: Rc<RefCell<Option<(String, f64, bool)>>> = Rc::default();
+    mixer.connect_changed({
+        let (osd, window) = (osd.clone(), window.clone());
+        move |state| {
+            let current = state.and_then(State::default_sink).map(|sink| {
+                let volume = sink.volume.percent().round();
+                (sink.name.clone(), volume, sink.muted)
+            });
+            let previous = shown.replace(current.clone());
+            let Some((name, volume, muted)) = &current else {
+                return;
+            };
+            let same_sink = previous.as_ref().is_some_and(|(before, ..)| before == name);
+            if same_sink && previous != current && !window.is_visible() {
+                osd.show(OsdKind::Volume, *volume, *muted);
+            }
+        }
+    });
+
+    let parent_name = match button.parent() {
+        Some(parent) => parent.widget_name().to_string(),
+        None => "".to_string(),
+    };
+    determine_window_position(parent_name.as_str(), &window);
+
+    let step = config.and_then(|c| c.step).unwrap_or(DEFAULT_STEP);
+    let sink = {
+        let mixer = mixer.clone();
+        move || {
+            mixer
+                .state()
+                .and_then(|state| state.default_sink().map(|sink| Target::Sink(sink.index)))
+        }
+    };
+    let actions = WidgetActions::activate({
+        let (mixer, sink) = (mixer.clone(), sink.clone());
+        move || {
+            if let Some(sink) = sink() {
+                mixer.toggle_mute(sink);
+            }
+        }
+    })
+    .with_panel(move || match window.is_visible() {
+        true => window.hide(),
+        false => window.present(),
+    })
+    .with_steps(
+        {
+            let (mixer, sink) = (mixer.clone(), sink.clone());
+            move || {
+                if let Some(sink) = sink() {
+                    mixer.change_volume(sink, step);
+                }
+            }
+        },
+        move || {
+            if let Some(sink) = sink() {
+                mixer.change_volume(sink, -step);
+            }
+        },
+    )
+    .with_alt(&format, redraw);
+    (button.into(), actions)
+}
+
+fn values(state: Option<&State>) -> Values<'static> {
+    let Some(sink) = state.and_then(State::default_sink) else {
+        return Values::new();
+    };
+    let volume = sink.volume.percent().round();
+    let mut values = Values::from([
+        ("volume", format!("{:.0}", volume)),
+        ("icon", OsdKind::Volume.icon(volume, sink.muted).to_string()),
+        ("device", sink.description.clone()),
+    ]);
+    if sink.muted {
+        values.insert("muted", "muted".to_string());
+    }
+    if let Some(source) = state.and_then(State::default_source) {
+        values.insert("input_volume", format!("{:.0}", source.volume.percent()));
+        if source.muted {
+            values.insert("input_muted", "muted".to_string());
+        }
+    }
+    values
+}
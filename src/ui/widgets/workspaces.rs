@@ -9,7 +9,6 @@ use std::collections::HashMap;
 use std::process::Command;
 use std::rc::Rc;
 
-const ANY_BUTTON: u32 = 0;
 const URGENT_CLASS: &str = "workspace-urgent";
 const ACTIVE_CLASS: &str = "workspace-active";
 const DEFAULT_CLASS: &str = "workspace";
@@ -187,7 +186,7 @@ pub fn update_workspaces(
     }
 }
 
-fn get_active_workspace() -> Option<Workspace> {
+pub fn get_active_workspace() -> Option<Workspace> {
     let output = std::process::Command::new("hyprctl")
         .args(["activeworkspace", "-j"])
         .output()
@@ -243,9 +242,11 @@ fn workspace_gesture(label: &Label, ws_name: String, window: &ApplicationWindow)
         println!("Mouse entered workspace {}", ws_clone);
     });
 
+    // Other buttons and scrolling are left to the widget's mouse bindings.
     let gesture = GestureClick::new();
-    gesture.set_button(ANY_BUTTON);
-    gesture.connect_pressed(move |_, _, _, _| {
+    gesture.set_button(gtk::gdk::BUTTON_PRIMARY);
+    gesture.connect_pressed(move |gesture, _, _, _| {
+        gesture.set_state(gtk::EventSequenceState::Claimed);
         let _ = Command::new("hyprctl")
             .args(["dispatch", "workspace", &ws_name])
             .output();
@@ -1,15 +1,9 @@
 use crate::user::models::{SectionsConfig, UserConfig};
-use std::{collections::HashMap, fs};
+use serde_json::{Value, json};
+use std::{collections::HashMap, error::Error, fs, path::PathBuf};
 
 pub fn load_config() -> Option<UserConfig> {
-    let config_path = dirs_next::config_dir().map(|dir| dir.join("hybar").join("config.json"));
-    let project_path = std::env::current_dir()
-        .ok()
-        .map(|dir| dir.join("config.json"));
-
-    let raw = config_path
-        .and_then(|path| fs::read_to_string(path).ok())
-        .or_else(|| project_path.and_then(|path| fs::read_to_string(path).ok()))?;
+    let raw = fs::read_to_string(config_path()?).ok()?;
 
     let config: Option<UserConfig> = serde_json::from_str(&raw).ok();
 
@@ -28,6 +22,60 @@ pub fn load_config() -> Option<UserConfig> {
     }
 }
 
+pub fn save_favorites(favorites: &[String]) -> Result<(), Box<dyn Error>> {
+    let path = config_path().ok_or("The config file could not be found.")?;
+    let raw = fs::read_to_string(&path)?;
+    let updated = with_favorites(&raw, favorites)?;
+
+    // The temporary file goes next to the real file, so renaming it keeps a
+    // symlinked config a symlink.
+    let path = fs::canonicalize(path)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, updated)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+fn config_path() -> Option<PathBuf> {
+    let config_path = dirs_next::config_dir().map(|dir| dir.join("hybar").join("config.json"));
+    let project_path = std::env::current_dir()
+        .ok()
+        .map(|dir| dir.join("config.json"));
+
+    config_path
+        .filter(|path| path.is_file())
+        .or_else(|| project_path.filter(|path| path.is_file()))
+}
+
+fn with_favorites(raw: &str, favorites: &[String]) -> Result<String, Box<dyn Error>> {
+    let mut config: Value = serde_json::from_str(raw)?;
+    let widgets = object_entry(&mut config, "widgets")?;
+    let apps = object_entry(widgets, "apps")?;
+    apps.as_object_mut()
+        .ok_or("`apps` in the config is not an object.")?
+        .insert("favorites".to_string(), json!(favorites));
+
+    let mut updated = serde_json::to_string_pretty(&config)?;
+    updated.push('\n');
+    Ok(updated)
+}
+
+// The object under `key`, created when missing or null.
+fn object_entry<'a>(parent: &'a mut Value, key: &str) -> Result<&'a mut Value, Box<dyn Error>> {
+    let child = parent
+        .as_object_mut()
+        .ok_or("The config is not an object.")?
+        .entry(key)
+        .or_insert_with(|| json!({}));
+    if child.is_null() {
+        *child = json!({});
+    }
+    match child.is_object() {
+        true => Ok(child),
+        false => Err(format!("`{}` in the config is not an object.", key).into()),
+    }
+}
+
 fn normalize_duplicate_keys(
     keys: Vec<String>,
     seen: &mut std::collections::HashMap<String, usize>,
@@ -49,3 +97,50 @@ fn normalize_duplicate_keys(
 
     normalized
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn with_favorites_keeps_the_rest_of_the_config() {
+        let raw = r#"{
+  "theme": "default",
+  "widgets": {
+    "separator": { "icon": "•" },
+    "apps": { "favorites": ["firefox"], "cycle_scope": "all" }
+  },
+  "custom_apps": {}
+}"#;
+        let favorites = vec!["kitty".to_string(), "firefox".to_string()];
+        let updated: Value =
+            serde_json::from_str(&with_favorites(raw, &favorites).unwrap()).unwrap();
+
+        assert_eq!(
+            updated["widgets"]["apps"]["favorites"],
+            json!(["kitty", "firefox"])
+        );
+        assert_eq!(updated["widgets"]["apps"]["cycle_scope"], "all");
+        assert_eq!(updated["widgets"]["separator"]["icon"], "•");
+        let keys = updated.as_object().unwrap().keys().collect::<Vec<_>>();
+        assert_eq!(keys, vec!["theme", "widgets", "custom_apps"]);
+    }
+
+    #[test]
+    fn with_favorites_creates_missing_apps_widget() {
+        let updated: Value = serde_json::from_str(
+            &with_favorites(r#"{"theme": "white"}"#, &["code".into()]).unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(updated["widgets"]["apps"]["favorites"], json!(["code"]));
+        assert_eq!(updated["theme"], "white");
+    }
+
+    #[test]
+    fn with_favorites_rejects_mismatched_types() {
+        assert!(with_favorites(r#"{"widgets": "x"}"#, &["code".into()]).is_err());
+        assert!(with_favorites(r#"{"widgets": {"apps": []}}"#, &["code".into()]).is_err());
+        assert!(with_favorites(r#"{"widgets": {"apps": null}}"#, &["code".into()]).is_ok());
+    }
+}
@@ -1,6 +1,8 @@
 use serde::Deserialize;
 use std::collections::HashMap;
 
+use crate::utils::format::FormatIcons;
+
 #[allow(dead_code)]
 #[derive(Debug, Deserialize, Default, Clone)]
 pub struct UserConfig {
@@ -40,10 +42,33 @@ pub struct WidgetsConfig {
     pub favorites: Option<Vec<String>>,
     pub icon: Option<String>,
     pub size: Option<u32>,
-    pub format: Option<String>,
     pub timezone: Option<String>,
     pub show_icons: Option<bool>,
     pub max_workspaces: Option<u32>,
+    pub cycle_scope: Option<String>,
+    pub indicator: Option<String>,
+    pub class_overrides: Option<HashMap<String, String>>,
+    pub icon_overrides: Option<HashMap<String, String>>,
+    pub terminal: Option<String>,
+    pub launch: Option<String>,
+    pub recent: Option<u32>,
+    pub root: Option<String>,
+    pub batteries: Option<Vec<String>>,
+    pub interval: Option<u64>,
+    pub warning: Option<f64>,
+    pub critical: Option<f64>,
+    // Draws a "line", "bars" or "blocks" graph next to numeric widgets.
+    pub graph: Option<String>,
+    pub history: Option<usize>,
+    pub interface: Option<String>,
+    // The backlight to control, the first one otherwise.
+    pub device: Option<String>,
+    // How far one scroll moves a value, in percent.
+    pub step: Option<f64>,
+    #[serde(flatten)]
+    pub formats: FormatConfig,
+    #[serde(flatten)]
+    pub mouse: MouseConfig,
 }
 
 #[derive(Debug, Deserialize, Default, Clone)]
@@ -52,4 +77,43 @@ pub struct CustomAppsConfig {
     pub icon: Option<String>,
     pub cmd: Option<String>,
     pub tooltip: Option<bool>,
+    pub kind: Option<String>,
+    pub exec: Option<String>,
+    pub interval: Option<u64>,
+    pub timeout: Option<u64>,
+    pub signal: Option<i32>,
+    #[serde(flatten)]
+    pub formats: FormatConfig,
+    #[serde(flatten)]
+    pub mouse: MouseConfig,
+}
+
+// The templates of a widget's label and tooltip, see `Format`.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct FormatConfig {
+    pub format: Option<String>,
+    #[serde(alias = "format-alt")]
+    pub format_alt: Option<String>,
+    #[serde(alias = "tooltip-format")]
+    pub tooltip_format: Option<String>,
+    #[serde(alias = "format-icons")]
+    pub format_icons: Option<FormatIcons>,
+}
+
+// Each binding is a shell command, `dispatch:<dispatcher>` or one of the
+// `hybar:` actions, see `MouseAction`.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct MouseConfig {
+    #[serde(alias = "on-click")]
+    pub on_click: Option<String>,
+    #[serde(alias = "on-click-middle", alias = "on_middle_click")]
+    pub on_click_middle: Option<String>,
+    #[serde(alias = "on-click-right", alias = "on_right_click")]
+    pub on_click_right: Option<String>,
+    #[serde(alias = "on-double-click", alias = "on_click_double")]
+    pub on_double_click: Option<String>,
+    #[serde(alias = "on-scroll-up")]
+    pub on_scroll_up: Option<String>,
+    #[serde(alias = "on-scroll-down")]
+    pub on_scroll_down: Option<String>,
 }
@@ -1,57 +1,814 @@
-use std::process::Command;
-fn needs_shell(cmd: &str) -> bool {
-    cmd.contains('|')
-        || cmd.contains("&&")
-        || cmd.contains("||")
-        || cmd.contains('>')
-        || cmd.contains('<')
+use gtk::{gio, glib, prelude::*};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    fs::File,
+    io::{self, Read},
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::process::CommandExt,
+    },
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::{
+    enums::widgets::LaunchStrategy,
+    utils::{
+        desktop_entry::{DesktopAction, DesktopEntry},
+        history::LaunchHistory,
+    },
+};
+
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+const ACTIVATION_TOKEN_ENV: &str = "XDG_ACTIVATION_TOKEN";
+const STARTUP_ID_ENV: &str = "DESKTOP_STARTUP_ID";
+const SYSTEMD_SERVICE: &str = "org.freedesktop.systemd1";
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+const SYSTEMD_MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+// The app starts outside its scope rather than never when systemd is stuck.
+const SCOPE_TIMEOUT: Duration = Duration::from_secs(5);
+const TERMINALS: [(&str, &[&str]); 7] = [
+    ("xdg-terminal-exec", &[]),
+    ("kitty", &[]),
+    ("foot", &[]),
+    ("alacritty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("gnome-terminal", &["--"]),
+    ("konsole", &["-e"]),
+];
+
+#[derive(Debug)]
+pub enum LaunchError {
+    EmptyCommand,
+    InvalidExec(String),
+    UnknownFieldCode(char),
+    NotInstalled(String),
+    NoTerminal,
+    Spawn(String, std::io::Error),
+    UnknownAction(String),
+    Hyprctl(String),
+    DBus(String, glib::Error),
 }
 
-fn clean_exec(exec: &str) -> String {
-    exec.split_whitespace()
-        .filter(|arg| !arg.starts_with('%'))
-        .collect::<Vec<_>>()
-        .join(" ")
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::EmptyCommand => write!(f, "Nothing to launch"),
+            LaunchError::InvalidExec(exec) => write!(f, "Invalid Exec line: {}", exec),
+            LaunchError::UnknownFieldCode(code) => write!(f, "Unknown field code %{}", code),
+            LaunchError::NotInstalled(program) => write!(f, "{} is not installed", program),
+            LaunchError::NoTerminal => write!(f, "No terminal emulator configured"),
+            LaunchError::Spawn(program, e) => write!(f, "Failed to start {}: {}", program, e),
+            LaunchError::UnknownAction(action) => write!(f, "Unknown desktop action {}", action),
+            LaunchError::Hyprctl(response) => write!(f, "hyprctl exec failed: {}", response),
+            LaunchError::DBus(app_id, e) => write!(f, "Failed to activate {}: {}", app_id, e),
+        }
+    }
 }
 
-pub fn app_lauch(exec: &str) {
-    println!("Launching app with exec: {}", exec);
-    let exec = exec.trim();
+impl std::error::Error for LaunchError {}
 
-    if exec.is_empty() {
-        return;
+#[derive(Debug, Clone)]
+pub struct AppLauncher {
+    terminal: Rc<RefCell<Option<String>>>,
+    strategy: Rc<Cell<LaunchStrategy>>,
+    history: LaunchHistory,
+}
+
+impl AppLauncher {
+    pub fn new(terminal: Option<String>, strategy: LaunchStrategy, history: LaunchHistory) -> Self {
+        Self {
+            terminal: Rc::new(RefCell::new(terminal)),
+            strategy: Rc::new(Cell::new(strategy)),
+            history,
+        }
     }
 
-    if needs_shell(exec) {
-        let _ = Command::new("sh")
-            .arg("-c")
-            .arg(format!("({}) &", exec))
-            .current_dir(std::env::var("HOME").unwrap())
-            .spawn();
-        return;
+    pub fn history(&self) -> &LaunchHistory {
+        &self.history
     }
 
-    let cleaned = clean_exec(exec);
+    pub fn configure(&self, terminal: Option<String>, strategy: LaunchStrategy) {
+        *self.terminal.borrow_mut() = terminal;
+        self.strategy.set(strategy);
+    }
 
-    let parts = match shell_words::split(&cleaned) {
-        Ok(p) => p,
-        Err(_) => return,
-    };
+    pub fn launch(
+        &self,
+        entry: &DesktopEntry,
+        token: Option<String>,
+        on_error: impl FnOnce(LaunchError) + 'static,
+    ) {
+        self.start(entry, None, token, self.recorded(&entry.id, on_error));
+    }
+
+    pub fn launch_action(
+        &self,
+        entry: &DesktopEntry,
+        action_id: &str,
+        token: Option<String>,
+        on_error: impl FnOnce(LaunchError) + 'static,
+    ) {
+        match entry.actions.iter().find(|action| action.id == action_id) {
+            Some(action) => {
+                let done = self.recorded(&entry.id, on_error);
+                self.start(entry, Some(action), token, done)
+            }
+            None => on_error(LaunchError::UnknownAction(action_id.to_string())),
+        }
+    }
+
+    // Only launches that started count for the history.
+    fn recorded(
+        &self,
+        app: &str,
+        on_error: impl FnOnce(LaunchError) + 'static,
+    ) -> impl FnOnce(Result<(), LaunchError>) + 'static {
+        let history = self.history.clone();
+        let app = app.to_string();
+        move |result| match result {
+            Ok(()) => history.record(&app),
+            Err(e) => on_error(e),
+        }
+    }
+
+    fn start(
+        &self,
+        entry: &DesktopEntry,
+        action: Option<&DesktopAction>,
+        token: Option<String>,
+        done: impl FnOnce(Result<(), LaunchError>) + 'static,
+    ) {
+        let mut target = entry.clone();
+        if let Some(action) = action {
+            target.exec = action.exec.clone();
+            target.icon = action.icon.clone().or(target.icon);
+        }
+
+        if entry.dbus_activatable {
+            let launcher = self.clone();
+            let action = action.map(|action| action.id.as_str());
+            activate(entry, action, token.clone(), move |result| {
+                let Err(e) = result else {
+                    return done(Ok(()));
+                };
+                eprintln!("{}", e);
+                if target.exec.is_none() {
+                    return done(Err(e));
+                }
+                launcher.spawn_entry(&target, token.as_deref(), done);
+            });
+            return;
+        }
+
+        self.spawn_entry(&target, token.as_deref(), done);
+    }
+
+    pub fn launch_command(
+        &self,
+        command: &str,
+        token: Option<String>,
+        on_error: impl FnOnce(LaunchError) + 'static,
+    ) {
+        let (argv, app_id) = match command_args(command) {
+            Ok(command) => command,
+            Err(e) => return on_error(e),
+        };
+        let done = self.recorded(&app_id, on_error);
+        self.run(argv, None, &app_id, token.as_deref(), done);
+    }
+
+    fn spawn_entry(
+        &self,
+        entry: &DesktopEntry,
+        token: Option<&str>,
+        done: impl FnOnce(Result<(), LaunchError>) + 'static,
+    ) {
+        let argv = match self.entry_args(entry) {
+            Ok(argv) => argv,
+            Err(e) => return done(Err(e)),
+        };
+        let app_id = entry.id.strip_suffix(".desktop").unwrap_or(&entry.id);
+        let working_dir = entry.working_dir.as_deref().map(Path::new);
+        self.run(argv, working_dir, app_id, token, done);
+    }
+
+    fn entry_args(&self, entry: &DesktopEntry) -> Result<Vec<String>, LaunchError> {
+        if let Some(try_exec) = &entry.try_exec
+            && !command_exists(try_exec)
+        {
+            return Err(LaunchError::NotInstalled(try_exec.clone()));
+        }
+
+        let mut argv = exec_args(entry)?;
+        if entry.terminal {
+            let mut command = terminal_command(self.terminal.borrow().as_deref())?;
+            command.append(&mut argv);
+            argv = command;
+        }
+        Ok(argv)
+    }
+
+    // hyprctl and systemd answer after this returns, `done` gets the result
+    // either way.
+    fn run(
+        &self,
+        argv: Vec<String>,
+        working_dir: Option<&Path>,
+        app_id: &str,
+        token: Option<&str>,
+        done: impl FnOnce(Result<(), LaunchError>) + 'static,
+    ) {
+        let prefix = match self.strategy.get() {
+            LaunchStrategy::Direct => Vec::new(),
+            LaunchStrategy::Uwsm => ["uwsm", "app", "--"].map(String::from).to_vec(),
+            LaunchStrategy::Systemd => {
+                let (command, program) = match command(&argv, working_dir, token) {
+                    Ok(command) => command,
+                    Err(e) => return done(Err(e)),
+                };
+                let app_id = app_id.to_string();
+                glib::MainContext::default().spawn_local(async move {
+                    done(spawn_in_scope(command, program, app_id).await)
+                });
+                return;
+            }
+            LaunchStrategy::Hyprctl => {
+                let command = hyprctl_command(&argv, working_dir, token);
+                glib::MainContext::default()
+                    .spawn_local(async move { done(hyprctl_exec(command).await) });
+                return;
+            }
+        };
+        let argv = prefix.into_iter().chain(argv).collect::<Vec<_>>();
+        done(spawn(&argv, working_dir, token));
+    }
+}
+
+// Commands written by the user run through `sh -c` when they use shell
+// syntax, Exec lines from desktop entries never do. The history records them
+// by their program name.
+fn command_args(command: &str) -> Result<(Vec<String>, String), LaunchError> {
+    let command = command.trim();
+    if command.contains(['|', '&', ';', '<', '>', '$', '`', '~']) {
+        let program = command.split_whitespace().next().unwrap_or(command);
+        let argv = ["sh", "-c", command].map(String::from).to_vec();
+        return Ok((argv, file_name(program)));
+    }
+
+    let argv =
+        shell_words::split(command).map_err(|_| LaunchError::InvalidExec(command.to_string()))?;
+    let app_id = argv
+        .first()
+        .map(|program| file_name(program))
+        .ok_or(LaunchError::EmptyCommand)?;
+    Ok((argv, app_id))
+}
+
+pub fn exec_args(entry: &DesktopEntry) -> Result<Vec<String>, LaunchError> {
+    let exec = entry.exec.as_deref().ok_or(LaunchError::EmptyCommand)?;
+    let mut args = Vec::new();
+
+    for (arg, quoted) in split_exec(exec)? {
+        if quoted {
+            args.push(arg);
+            continue;
+        }
+
+        match arg.as_str() {
+            "%i" => {
+                if let Some(icon) = &entry.icon {
+                    args.extend(["--icon".to_string(), icon.clone()]);
+                }
+            }
+            "%f" | "%F" | "%u" | "%U" => {}
+            _ => {
+                let expanded = expand_field_codes(&arg, entry)?;
+                if !expanded.is_empty() || !arg.starts_with('%') {
+                    args.push(expanded);
+                }
+            }
+        }
+    }
+
+    match args.is_empty() {
+        true => Err(LaunchError::EmptyCommand),
+        false => Ok(args),
+    }
+}
+
+// Files and URLs are never passed from the bar, so their codes expand to nothing.
+fn expand_field_codes(arg: &str, entry: &DesktopEntry) -> Result<String, LaunchError> {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(&entry.name),
+            Some('k') => expanded.push_str(&entry.path.to_string_lossy()),
+            Some('f' | 'F' | 'u' | 'U' | 'i' | 'd' | 'D' | 'n' | 'N' | 'v' | 'm') => {}
+            Some(code) => return Err(LaunchError::UnknownFieldCode(code)),
+            None => return Err(LaunchError::InvalidExec(arg.to_string())),
+        }
+    }
+
+    Ok(expanded)
+}
+
+// Exec arguments are separated by spaces and may be double quoted, in which
+// case `"`, `` ` ``, `$` and `\` are escaped with a backslash.
+fn split_exec(exec: &str) -> Result<Vec<(String, bool)>, LaunchError> {
+    let invalid = || LaunchError::InvalidExec(exec.to_string());
+    let mut args = Vec::new();
+    let mut current: Option<(String, bool)> = None;
+    let mut chars = exec.chars();
 
-    if parts.is_empty() {
-        return;
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {
+                args.extend(current.take());
+            }
+            '"' => {
+                let (arg, quoted) = current.get_or_insert_with(|| (String::new(), true));
+                *quoted = true;
+                loop {
+                    match chars.next().ok_or_else(invalid)? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or_else(invalid)? {
+                            escaped @ ('"' | '`' | '$' | '\\') => arg.push(escaped),
+                            other => {
+                                arg.push('\\');
+                                arg.push(other);
+                            }
+                        },
+                        other => arg.push(other),
+                    }
+                }
+            }
+            _ => current
+                .get_or_insert_with(|| (String::new(), false))
+                .0
+                .push(c),
+        }
     }
+    args.extend(current);
+
+    Ok(args)
+}
+
+fn terminal_command(terminal: Option<&str>) -> Result<Vec<String>, LaunchError> {
+    if let Some(terminal) = terminal.filter(|t| !t.trim().is_empty()) {
+        return shell_words::split(terminal)
+            .map_err(|_| LaunchError::InvalidExec(terminal.to_string()));
+    }
+
+    if let Ok(terminal) = std::env::var("TERMINAL")
+        && command_exists(&terminal)
+    {
+        return Ok(vec![terminal, "-e".to_string()]);
+    }
+
+    TERMINALS
+        .iter()
+        .find(|(program, _)| command_exists(program))
+        .map(|(program, args)| {
+            std::iter::once(*program)
+                .chain(args.iter().copied())
+                .map(String::from)
+                .collect()
+        })
+        .ok_or(LaunchError::NoTerminal)
+}
+
+fn spawn(
+    argv: &[String],
+    working_dir: Option<&Path>,
+    token: Option<&str>,
+) -> Result<(), LaunchError> {
+    let (mut command, program) = command(argv, working_dir, token)?;
+    let mut child = command
+        .spawn()
+        .map_err(|e| LaunchError::Spawn(program, e))?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+fn command(
+    argv: &[String],
+    working_dir: Option<&Path>,
+    token: Option<&str>,
+) -> Result<(Command, String), LaunchError> {
+    let (program, args) = argv.split_first().ok_or(LaunchError::EmptyCommand)?;
+    let working_dir = working_dir
+        .filter(|dir| dir.is_dir())
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var("HOME").ok().map(PathBuf::from));
+
+    let mut command = Command::new(program);
+    command.args(args).stdin(Stdio::null()).process_group(0);
+    if let Some(dir) = working_dir {
+        command.current_dir(dir);
+    }
+    for (key, value) in activation_env(token) {
+        command.env(key, value);
+    }
+    Ok((command, program.clone()))
+}
 
-    let program = &parts[0];
-    let args = &parts[1..];
-    let full_cmd = if args.is_empty() {
-        program.to_string()
-    } else {
-        format!("{} {}", program, args.join(" "))
+// Same as `systemd-run --user --scope`: the child sends its pid and waits
+// before exec until the scope holding it exists, so nothing it forks is left
+// in the bar's cgroup. `spawn` only returns once the child execs, so it runs
+// on a worker thread meanwhile.
+async fn spawn_in_scope(
+    mut command: Command,
+    program: String,
+    app_id: String,
+) -> Result<(), LaunchError> {
+    let spawn_error = |e| LaunchError::Spawn(program.clone(), e);
+    let (pid_read, pid_write) = pipe().map_err(spawn_error)?;
+    let (gate_read, gate_write) = pipe().map_err(spawn_error)?;
+    let fds = (
+        pid_write.as_raw_fd(),
+        gate_read.as_raw_fd(),
+        gate_write.as_raw_fd(),
+    );
+    unsafe {
+        command.pre_exec(move || {
+            wait_for_scope(fds);
+            Ok(())
+        });
+    }
+
+    let spawned = gio::spawn_blocking(move || {
+        let child = command.spawn();
+        drop((pid_write, gate_read));
+        let mut child = child?;
+        std::thread::spawn(move || child.wait());
+        Ok(())
+    });
+    let pid = gio::spawn_blocking(move || {
+        let mut pid = [0; 4];
+        File::from(pid_read)
+            .read_exact(&mut pid)
+            .map(|_| i32::from_ne_bytes(pid) as u32)
+    })
+    .await;
+    // Without a pid the child never started, `spawned` tells why.
+    if let Ok(Ok(pid)) = pid {
+        let unit = scope_name(&app_id, &format!("{:08x}", glib::random_int()));
+        if let Err(e) = start_scope(&unit, &app_id, pid).await {
+            eprintln!("Failed to create scope {}: {}", unit, e);
+        }
+    }
+    drop(gate_write);
+
+    spawned
+        .await
+        .map_err(|_| spawn_error(io::Error::other("spawn panicked")))?
+        .map_err(spawn_error)
+}
+
+// Runs in the forked child, where only async-signal-safe calls are allowed.
+// The child's copy of the write end is closed so the parent closing its own
+// ends the read.
+fn wait_for_scope((pid_write, gate_read, gate_write): (i32, i32, i32)) {
+    unsafe {
+        let pid = libc::getpid().to_ne_bytes();
+        libc::write(pid_write, pid.as_ptr().cast(), pid.len());
+        libc::close(gate_write);
+        let mut byte = 0u8;
+        while libc::read(gate_read, (&mut byte as *mut u8).cast(), 1) < 0
+            && io::Error::last_os_error().kind() == io::ErrorKind::Interrupted
+        {}
+    }
+}
+
+fn pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
+
+// systemd moves the pid once the start job runs, which JobRemoved reports;
+// the reply only means the job was queued.
+async fn start_scope(unit: &str, app_id: &str, pid: u32) -> Result<(), glib::Error> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await?;
+    let (sender, finished) = async_channel::bounded(1);
+    let subscription = connection.signal_subscribe(
+        Some(SYSTEMD_SERVICE),
+        Some(SYSTEMD_MANAGER_INTERFACE),
+        Some("JobRemoved"),
+        Some(SYSTEMD_PATH),
+        None,
+        gio::DBusSignalFlags::NONE,
+        {
+            let unit = unit.to_string();
+            move |_, _, _, _, _, parameters| {
+                if parameters.child_value(2).str() == Some(unit.as_str()) {
+                    let _ = sender.try_send(());
+                }
+            }
+        },
+    );
+
+    let result = connection
+        .call_future(
+            Some(SYSTEMD_SERVICE),
+            SYSTEMD_PATH,
+            SYSTEMD_MANAGER_INTERFACE,
+            "StartTransientUnit",
+            Some(&scope_parameters(unit, app_id, pid)),
+            glib::VariantTy::new("(o)").ok(),
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await;
+    if result.is_ok()
+        && glib::future_with_timeout(SCOPE_TIMEOUT, finished.recv())
+            .await
+            .is_err()
+    {
+        eprintln!("Timed out waiting for scope {}", unit);
+    }
+    connection.signal_unsubscribe(subscription);
+    result.map(|_| ())
+}
+
+// Hyprland runs the command through its own shell, so the app ends up as a
+// child of the compositor instead of the bar. hyprctl waits for that answer
+// on a worker thread.
+async fn hyprctl_exec(command: String) -> Result<(), LaunchError> {
+    let output = gio::spawn_blocking(move || {
+        Command::new("hyprctl")
+            .args(["dispatch", "exec", &command])
+            .output()
+    })
+    .await
+    .map_err(|_| LaunchError::Hyprctl("hyprctl exec panicked".to_string()))?
+    .map_err(|e| LaunchError::Spawn("hyprctl".to_string(), e))?;
+    let response = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    match output.status.success() && response == "ok" {
+        true => Ok(()),
+        false => Err(LaunchError::Hyprctl(response)),
+    }
+}
+
+fn hyprctl_command(argv: &[String], working_dir: Option<&Path>, token: Option<&str>) -> String {
+    let mut command = shell_words::join(argv);
+    for (key, value) in activation_env(token) {
+        command = format!("{}={} {}", key, shell_words::quote(value), command);
+    }
+    if let Some(dir) = working_dir.filter(|dir| dir.is_dir()) {
+        command = format!(
+            "cd {} && {}",
+            shell_words::quote(&dir.to_string_lossy()),
+            command
+        );
+    }
+    command
+}
+
+fn scope_parameters(unit: &str, app_id: &str, pid: u32) -> glib::Variant {
+    let properties = vec![
+        (
+            "Description".to_string(),
+            format!("Application launched by hybar: {}", app_id).to_variant(),
+        ),
+        ("PIDs".to_string(), vec![pid].to_variant()),
+        ("CollectMode".to_string(), "inactive-or-failed".to_variant()),
+    ];
+    let auxiliary: Vec<(String, Vec<(String, glib::Variant)>)> = Vec::new();
+    (unit, "fail", properties, auxiliary).to_variant()
+}
+
+fn scope_name(app_id: &str, suffix: &str) -> String {
+    let escaped = app_id
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => {
+                (byte as char).to_string()
+            }
+            _ => format!("\\x{:02x}", byte),
+        })
+        .collect::<String>();
+
+    format!("app-hybar-{}-{}.scope", escaped, suffix)
+}
+
+fn activate(
+    entry: &DesktopEntry,
+    action: Option<&str>,
+    token: Option<String>,
+    done: impl FnOnce(Result<(), LaunchError>) + 'static,
+) {
+    let app_id = entry
+        .id
+        .strip_suffix(".desktop")
+        .unwrap_or(&entry.id)
+        .to_string();
+    let object_path = format!("/{}", app_id.replace('.', "/").replace('-', "_"));
+    let platform_data = token
+        .iter()
+        .flat_map(|token| {
+            ["activation-token", "desktop-startup-id"]
+                .map(|key| (key.to_string(), token.to_variant()))
+        })
+        .collect::<HashMap<String, glib::Variant>>();
+    let (method, parameters) = match action {
+        Some(action) => (
+            "ActivateAction",
+            (action, Vec::<glib::Variant>::new(), platform_data).to_variant(),
+        ),
+        None => ("Activate", (platform_data,).to_variant()),
     };
 
-    let _ = Command::new("sh")
-        .arg("-c")
-        .arg(format!("({}) &", full_cmd))
-        .spawn();
+    gio::bus_get(
+        gio::BusType::Session,
+        gio::Cancellable::NONE,
+        move |connection| {
+            let connection = match connection {
+                Ok(connection) => connection,
+                Err(e) => return done(Err(LaunchError::DBus(app_id, e))),
+            };
+
+            connection.call(
+                Some(&app_id),
+                &object_path,
+                APPLICATION_INTERFACE,
+                method,
+                Some(&parameters),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+                gio::Cancellable::NONE,
+                {
+                    let app_id = app_id.clone();
+                    move |result| {
+                        done(result.map(|_| ()).map_err(|e| LaunchError::DBus(app_id, e)));
+                    }
+                },
+            );
+        },
+    );
+}
+
+// The token lets the compositor hand focus to the new window (xdg-activation).
+fn activation_env(token: Option<&str>) -> Vec<(&'static str, &str)> {
+    token
+        .map(|token| vec![(ACTIVATION_TOKEN_ENV, token), (STARTUP_ID_ENV, token)])
+        .unwrap_or_default()
+}
+
+fn file_name(program: &str) -> String {
+    Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn command_exists(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(exec: &str) -> DesktopEntry {
+        DesktopEntry {
+            id: "org.example.App.desktop".to_string(),
+            path: PathBuf::from("/usr/share/applications/org.example.App.desktop"),
+            name: "Example".to_string(),
+            exec: Some(exec.to_string()),
+            icon: Some("example".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn exec_args_expand_field_codes() {
+        let args = exec_args(&entry("example %U --name=%c %i --from %k --literal 100%%")).unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "example",
+                "--name=Example",
+                "--icon",
+                "example",
+                "--from",
+                "/usr/share/applications/org.example.App.desktop",
+                "--literal",
+                "100%",
+            ]
+        );
+
+        assert!(matches!(
+            exec_args(&entry("example %x")),
+            Err(LaunchError::UnknownFieldCode('x'))
+        ));
+    }
+
+    #[test]
+    fn exec_args_follow_the_spec_quoting_rules() {
+        let args = exec_args(&entry(
+            r#""/opt/My App/run" --title "say \"hi\" \$HOME" "%f" a|b"#,
+        ))
+        .unwrap();
+        assert_eq!(
+            args,
+            vec![
+                "/opt/My App/run",
+                "--title",
+                "say \"hi\" $HOME",
+                "%f",
+                "a|b"
+            ]
+        );
+
+        assert!(matches!(
+            exec_args(&entry(r#"example "unterminated"#)),
+            Err(LaunchError::InvalidExec(_))
+        ));
+    }
+
+    #[test]
+    fn commands_with_shell_syntax_run_through_sh() {
+        let (argv, app_id) = command_args("  /usr/bin/kitty --class 'scratch term'").unwrap();
+        assert_eq!(argv, vec!["/usr/bin/kitty", "--class", "scratch term"]);
+        assert_eq!(app_id, "kitty");
+
+        let (argv, app_id) = command_args("grim - | wl-copy && notify-send done").unwrap();
+        assert_eq!(
+            argv,
+            vec!["sh", "-c", "grim - | wl-copy && notify-send done"]
+        );
+        assert_eq!(app_id, "grim");
+        assert_eq!(command_args("~/bin/run $HOME").unwrap().0[0], "sh");
+
+        assert!(matches!(command_args(" "), Err(LaunchError::EmptyCommand)));
+    }
+
+    #[test]
+    fn hyprctl_command_passes_the_activation_token() {
+        let argv = vec!["my app".to_string(), "--flag".to_string()];
+        assert_eq!(
+            hyprctl_command(&argv, Some(Path::new("/")), Some("abc 123")),
+            "cd / && DESKTOP_STARTUP_ID='abc 123' XDG_ACTIVATION_TOKEN='abc 123' 'my app' --flag"
+        );
+        assert_eq!(hyprctl_command(&argv, None, None), "'my app' --flag");
+    }
+
+    #[test]
+    fn scope_names_escape_the_application_id() {
+        assert_eq!(
+            scope_name("org.gnome.Nautilus", "2a"),
+            "app-hybar-org.gnome.Nautilus-2a.scope"
+        );
+        assert_eq!(
+            scope_name("google-chrome", "07"),
+            "app-hybar-google\\x2dchrome-07.scope"
+        );
+
+        let parameters = scope_parameters("app-hybar-kitty-2a.scope", "kitty", 42);
+        assert_eq!(parameters.type_().as_str(), "(ssa(sv)a(sa(sv)))");
+        assert_eq!(
+            parameters.child_value(0).str(),
+            Some("app-hybar-kitty-2a.scope")
+        );
+        let properties = parameters
+            .child_value(2)
+            .get::<Vec<(String, glib::Variant)>>()
+            .unwrap()
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        assert_eq!(properties["PIDs"].get::<Vec<u32>>(), Some(vec![42]));
+        assert_eq!(properties["CollectMode"].str(), Some("inactive-or-failed"));
+    }
+
+    #[test]
+    fn terminal_apps_use_the_configured_terminal() {
+        assert_eq!(
+            terminal_command(Some("footclient --app-id 'scratch term'")).unwrap(),
+            vec!["footclient", "--app-id", "scratch term"]
+        );
+    }
 }
@@ -0,0 +1,173 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use crate::{
+    models::clients::Client,
+    utils::{desktop_entry::DesktopEntry, search::DesktopIndex},
+};
+
+#[derive(Debug, Clone)]
+pub struct AppMatcher {
+    index: DesktopIndex,
+    overrides: Rc<RefCell<HashMap<String, String>>>,
+}
+
+impl AppMatcher {
+    pub fn new(index: DesktopIndex, overrides: HashMap<String, String>) -> Self {
+        let matcher = Self {
+            index,
+            overrides: Rc::new(RefCell::new(HashMap::new())),
+        };
+        matcher.set_overrides(overrides);
+        matcher
+    }
+
+    pub fn set_overrides(&self, overrides: HashMap<String, String>) {
+        *self.overrides.borrow_mut() = overrides
+            .into_iter()
+            .map(|(class, app)| (class.to_lowercase(), app))
+            .collect();
+    }
+
+    pub fn resolve(&self, name: &str) -> Option<DesktopEntry> {
+        self.index.find(&self.query(name))
+    }
+
+    pub fn resolve_id(&self, name: &str) -> Option<String> {
+        self.index.find_id(&self.query(name))
+    }
+
+    pub fn matches(&self, client: &Client, app_name: &str) -> bool {
+        self.matches_id(client, app_name, self.resolve_id(app_name).as_deref())
+    }
+
+    // app_id is the resolved id of app_name, so callers checking many clients
+    // against the same app only resolve it once.
+    pub fn matches_id(&self, client: &Client, app_name: &str, app_id: Option<&str>) -> bool {
+        classes(client).any(|class| {
+            class.eq_ignore_ascii_case(app_name)
+                || app_id.is_some_and(|app_id| self.resolve_id(class).as_deref() == Some(app_id))
+        })
+    }
+
+    // The windows the dock counts for app_name.
+    pub fn windows<'a>(&self, clients: &'a [Client], app_name: &str) -> Vec<&'a Client> {
+        let app_id = self.resolve_id(app_name);
+        clients
+            .iter()
+            .filter(|c| self.matches_id(c, app_name, app_id.as_deref()))
+            .collect()
+    }
+
+    fn query(&self, name: &str) -> String {
+        self.overrides
+            .borrow()
+            .get(&name.to_lowercase())
+            .cloned()
+            .unwrap_or_else(|| name.to_string())
+    }
+}
+
+fn classes(client: &Client) -> impl Iterator<Item = &str> {
+    [client.class.as_str(), client.initial_class.as_str()]
+        .into_iter()
+        .filter(|class| !class.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn matcher(overrides: &[(&str, &str)]) -> (AppMatcher, TempDir) {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("applications");
+        fs::create_dir_all(&dir).unwrap();
+
+        let entries = [
+            (
+                "code.desktop",
+                "Name=Visual Studio Code\nExec=/usr/share/code/code %F\nStartupWMClass=Code",
+            ),
+            (
+                "codeblocks.desktop",
+                "Name=Code::Blocks IDE\nExec=codeblocks %F",
+            ),
+            (
+                "com.discordapp.Discord.desktop",
+                "Name=Discord\nExec=/usr/bin/flatpak run com.discordapp.Discord\nX-Flatpak=com.discordapp.Discord",
+            ),
+            (
+                "org.gnome.Nautilus.desktop",
+                "Name=Files\nExec=nautilus --new-window",
+            ),
+            ("steam.desktop", "Name=Steam\nExec=steam %U"),
+        ];
+        for (file, fields) in entries {
+            fs::write(
+                dir.join(file),
+                format!("[Desktop Entry]\nType=Application\n{}\n", fields),
+            )
+            .unwrap();
+        }
+
+        let overrides = overrides
+            .iter()
+            .map(|(class, app)| (class.to_string(), app.to_string()))
+            .collect();
+        (
+            AppMatcher::new(DesktopIndex::with_dirs(vec![dir]), overrides),
+            root,
+        )
+    }
+
+    fn client(class: &str, initial_class: &str) -> Client {
+        Client {
+            class: class.to_string(),
+            initial_class: initial_class.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matches_uses_desktop_entries_instead_of_substrings() {
+        let (matcher, _root) = matcher(&[]);
+
+        assert!(matcher.matches(&client("Code", "Code"), "code"));
+        assert!(!matcher.matches(&client("codeblocks", "codeblocks"), "code"));
+        assert!(matcher.matches(&client("com.discordapp.Discord", ""), "discord"));
+        assert!(matcher.matches(&client("org.gnome.Nautilus", ""), "nautilus"));
+        assert!(matcher.matches(&client("unknown-app", ""), "Unknown-App"));
+    }
+
+    #[test]
+    fn windows_counts_each_app_separately() {
+        let (matcher, _root) = matcher(&[]);
+        let clients = [
+            client("Code", "Code"),
+            client("codeblocks", "codeblocks"),
+            client("Code", "Code"),
+            client("org.gnome.Nautilus", "org.gnome.Nautilus"),
+        ];
+
+        assert_eq!(matcher.windows(&clients, "code").len(), 2);
+        assert_eq!(matcher.windows(&clients, "codeblocks").len(), 1);
+        assert_eq!(matcher.windows(&clients, "nautilus").len(), 1);
+        assert!(matcher.windows(&clients, "steam").is_empty());
+    }
+
+    #[test]
+    fn overrides_map_classes_to_applications() {
+        let (matcher, _root) = matcher(&[("steam_app_570", "steam.desktop")]);
+
+        assert!(matcher.matches(&client("steam_app_570", ""), "steam"));
+        assert!(matcher.matches(&client("", "steam_app_570"), "steam"));
+        assert_eq!(
+            matcher
+                .resolve("Steam_App_570")
+                .map(|entry| entry.name)
+                .as_deref(),
+            Some("Steam")
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use gtk::{gio, glib, prelude::*};
+use std::{
+    ffi::CString,
+    fs, io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+};
+
+pub const DEFAULT_ROOT: &str = "/sys/class/backlight";
+const LOGIND: &str = "org.freedesktop.login1";
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+const BUFFER_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backlight {
+    pub name: String,
+    pub brightness: u32,
+    pub max: u32,
+}
+
+impl Backlight {
+    pub fn percent(&self) -> f64 {
+        self.brightness as f64 / self.max as f64 * 100.0
+    }
+
+    // Never 0, which turns some panels off entirely.
+    pub fn value_for(&self, percent: f64) -> u32 {
+        let value = (percent.clamp(0.0, 100.0) / 100.0 * self.max as f64).round() as u32;
+        value.max(1)
+    }
+}
+
+// The backlight called `name`, or the first one under `root` by name.
+pub fn read(root: &Path, name: Option<&str>) -> Option<Backlight> {
+    let read_one = |name: &str| {
+        let dir = root.join(name);
+        let value = |file: &str| fs::read_to_string(dir.join(file)).ok()?.trim().parse().ok();
+        Some(Backlight {
+            name: name.to_string(),
+            brightness: value("brightness")?,
+            max: value("max_brightness").filter(|max| *max > 0)?,
+        })
+    };
+    if let Some(name) = name {
+        return read_one(name);
+    }
+
+    let mut names = fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.iter().find_map(|name| read_one(name))
+}
+
+// Writes go through logind, which lets the user of the active session set
+// the brightness without root or udev rules.
+pub async fn set_brightness(name: &str, value: u32) -> Result<(), glib::Error> {
+    let connection = gio::bus_get_future(gio::BusType::System).await?;
+    connection
+        .call_future(
+            Some(LOGIND),
+            SESSION_PATH,
+            SESSION_INTERFACE,
+            "SetBrightness",
+            Some(&("backlight", name, value).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+    Ok(())
+}
+
+// Sends a message whenever the brightness of the backlight in `dir` is
+// written. The kernel also notifies `actual_brightness` when firmware
+// changes it on its own.
+pub fn watch(dir: &Path) -> io::Result<async_channel::Receiver<()>> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let inotify = unsafe { OwnedFd::from_raw_fd(fd) };
+    for file in ["brightness", "actual_brightness"] {
+        let path = dir.join(file);
+        if !path.exists() {
+            continue;
+        }
+        add_watch(&inotify, &path)?;
+    }
+
+    let (sender, receiver) = async_channel::unbounded();
+    std::thread::spawn(move || {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        loop {
+            let read = unsafe {
+                libc::read(
+                    inotify.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                )
+            };
+            if read < 0 {
+                let error = io::Error::last_os_error();
+                if error.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                eprintln!("Failed to watch the backlight: {}", error);
+                return;
+            }
+            if sender.send_blocking(()).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+fn add_watch(inotify: &OwnedFd, path: &Path) -> io::Result<()> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let watch = unsafe {
+        libc::inotify_add_watch(
+            inotify.as_raw_fd(),
+            path.as_ptr(),
+            libc::IN_MODIFY | libc::IN_CLOSE_WRITE,
+        )
+    };
+    if watch < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(root: &Path, name: &str, brightness: u32, max: u32) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("brightness"), format!("{}\n", brightness)).unwrap();
+        fs::write(dir.join("max_brightness"), format!("{}\n", max)).unwrap();
+    }
+
+    #[test]
+    fn read_and_watch_a_backlight() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        device(root, "intel_backlight", 300, 1200);
+        device(root, "acpi_video0", 0, 0);
+
+        let backlight = read(root, None).unwrap();
+        assert_eq!(backlight.name, "intel_backlight");
+        assert_eq!(backlight.percent(), 25.0);
+        assert_eq!(backlight.value_for(50.0), 600);
+        assert_eq!(backlight.value_for(0.0), 1);
+        assert_eq!(read(root, Some("acpi_video0")), None);
+
+        let changes = watch(&root.join("intel_backlight")).unwrap();
+        fs::write(root.join("intel_backlight/brightness"), "900\n").unwrap();
+        assert!(changes.recv_blocking().is_ok());
+        assert_eq!(read(root, None).unwrap().percent(), 75.0);
+    }
+}
@@ -0,0 +1,281 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const DEFAULT_ROOT: &str = "/sys/class/power_supply";
+// sysfs reports energy in µWh, power in µW, charge in µAh and voltage in µV.
+const MICRO: f64 = 1_000_000.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BatteryState {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    #[default]
+    Unknown,
+}
+
+impl BatteryState {
+    fn parse(status: &str) -> Self {
+        match status.trim() {
+            "Charging" => BatteryState::Charging,
+            "Discharging" => BatteryState::Discharging,
+            "Full" => BatteryState::Full,
+            "Not charging" => BatteryState::NotCharging,
+            _ => BatteryState::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BatteryState::Charging => "charging",
+            BatteryState::Discharging => "discharging",
+            BatteryState::Full => "full",
+            BatteryState::NotCharging => "not-charging",
+            BatteryState::Unknown => "unknown",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Battery {
+    state: BatteryState,
+    capacity: Option<f64>,
+    // In Wh and W.
+    energy_now: Option<f64>,
+    energy_full: Option<f64>,
+    power: Option<f64>,
+}
+
+impl Battery {
+    fn read(dir: &Path) -> Self {
+        let value = |file: &str| read_number(&dir.join(file));
+        let voltage = value("voltage_now").or_else(|| value("voltage_min_design"));
+        // Charge based batteries are converted to energy through the voltage.
+        let energy = |energy: &str, charge: &str| {
+            value(energy)
+                .map(|energy| energy / MICRO)
+                .or_else(|| Some(value(charge)? * voltage? / MICRO / MICRO))
+        };
+
+        Self {
+            state: fs::read_to_string(dir.join("status"))
+                .map(|status| BatteryState::parse(&status))
+                .unwrap_or_default(),
+            capacity: value("capacity"),
+            energy_now: energy("energy_now", "charge_now"),
+            energy_full: energy("energy_full", "charge_full"),
+            power: value("power_now")
+                .map(|power| power / MICRO)
+                .or_else(|| Some(value("current_now")? * voltage? / MICRO / MICRO))
+                .map(f64::abs),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatteryStatus {
+    pub capacity: f64,
+    pub state: BatteryState,
+    pub power: Option<f64>,
+    // Until empty while discharging, until full while charging.
+    pub time: Option<Duration>,
+    pub plugged: bool,
+    pub batteries: usize,
+}
+
+// Combines every system battery under `root`, or only those in `names`.
+// Returns None when there is no battery at all.
+pub fn read(root: &Path, names: &[String]) -> Option<BatteryStatus> {
+    let mut batteries = Vec::new();
+    let mut plugged = false;
+    for dir in supplies(root) {
+        let kind = read_string(&dir.join("type")).unwrap_or_default();
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        match kind.as_str() {
+            "Battery" if names.is_empty() || names.iter().any(|n| *n == name) => {
+                // Peripherals such as mice report a Device scope.
+                if names.is_empty() && read_string(&dir.join("scope")).as_deref() == Some("Device")
+                {
+                    continue;
+                }
+                batteries.push(Battery::read(&dir));
+            }
+            "Mains" | "USB" => plugged |= read_number(&dir.join("online")) == Some(1.0),
+            _ => {}
+        }
+    }
+    if batteries.is_empty() {
+        return None;
+    }
+
+    let state = combined_state(&batteries);
+    let energy_now = batteries.iter().map(|b| b.energy_now).sum::<Option<f64>>();
+    let energy_full = batteries.iter().map(|b| b.energy_full).sum::<Option<f64>>();
+    let capacity = match (energy_now, energy_full) {
+        (Some(now), Some(full)) if full > 0.0 => now / full * 100.0,
+        _ => {
+            let capacities = batteries
+                .iter()
+                .filter_map(|b| b.capacity)
+                .collect::<Vec<_>>();
+            capacities.iter().sum::<f64>() / capacities.len().max(1) as f64
+        }
+    };
+    let power = batteries
+        .iter()
+        .filter_map(|b| b.power)
+        .reduce(|a, b| a + b)
+        .filter(|power| *power > 0.0);
+
+    let remaining = match state {
+        BatteryState::Discharging => energy_now,
+        BatteryState::Charging => energy_full.zip(energy_now).map(|(full, now)| full - now),
+        _ => None,
+    };
+    let time = remaining.zip(power).map(|(energy, power)| {
+        Duration::from_secs((energy.max(0.0) / power * 3600.0).round() as u64)
+    });
+
+    Some(BatteryStatus {
+        capacity: capacity.clamp(0.0, 100.0),
+        state,
+        power,
+        time,
+        plugged,
+        batteries: batteries.len(),
+    })
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+fn combined_state(batteries: &[Battery]) -> BatteryState {
+    let any = |state| batteries.iter().any(|b| b.state == state);
+    if any(BatteryState::Charging) {
+        BatteryState::Charging
+    } else if any(BatteryState::Discharging) {
+        BatteryState::Discharging
+    } else if batteries.iter().all(|b| b.state == BatteryState::Full) {
+        BatteryState::Full
+    } else if any(BatteryState::NotCharging) {
+        BatteryState::NotCharging
+    } else {
+        BatteryState::Unknown
+    }
+}
+
+fn supplies(root: &Path) -> Vec<PathBuf> {
+    let mut dirs = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    dirs.sort();
+    dirs
+}
+
+fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    read_string(path)?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, value) in files {
+            fs::write(dir.join(file), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    fn fake_root() -> TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        supply(root, "AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            root,
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "50"),
+                ("energy_now", "20000000"),
+                ("energy_full", "40000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        supply(
+            root,
+            "hidpp_battery_0",
+            &[("type", "Battery"), ("scope", "Device"), ("capacity", "5")],
+        );
+        dir
+    }
+
+    #[test]
+    fn read_combines_batteries_by_energy() {
+        let dir = fake_root();
+        let root = dir.path();
+        // 2Ah at 10V is 20Wh, drawing 0.5A is 5W.
+        supply(
+            root,
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("status", "Unknown"),
+                ("capacity", "100"),
+                ("charge_now", "2000000"),
+                ("charge_full", "2000000"),
+                ("current_now", "-500000"),
+                ("voltage_now", "10000000"),
+            ],
+        );
+
+        let status = read(root, &[]).unwrap();
+        assert_eq!(status.batteries, 2);
+        assert_eq!(status.state, BatteryState::Discharging);
+        assert!((status.capacity - 66.666).abs() < 0.01);
+        assert_eq!(status.power, Some(15.0));
+        assert_eq!(status.time, Some(Duration::from_secs(40 * 3600 / 15)));
+        assert!(!status.plugged);
+
+        let only = read(root, &["BAT1".to_string()]).unwrap();
+        assert_eq!(only.capacity, 100.0);
+        assert_eq!(only.state, BatteryState::Unknown);
+        assert_eq!(only.time, None);
+    }
+
+    #[test]
+    fn read_reports_time_until_full_while_charging() {
+        let dir = fake_root();
+        let root = dir.path();
+        supply(root, "AC", &[("type", "Mains"), ("online", "1")]);
+        supply(root, "BAT0", &[("status", "Charging")]);
+
+        let status = read(root, &[]).unwrap();
+        assert_eq!(status.state, BatteryState::Charging);
+        assert!(status.plugged);
+        assert_eq!(status.time, Some(Duration::from_secs(2 * 3600)));
+        assert_eq!(format_duration(status.time.unwrap()), "2h 00m");
+
+        assert_eq!(read(&root.join("missing"), &[]), None);
+    }
+}
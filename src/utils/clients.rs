@@ -1,5 +1,35 @@
 use crate::models::clients::Client;
 
+#[derive(Debug, Clone, Copy)]
+pub enum WindowAction {
+    Focus,
+    Close,
+    MoveToWorkspace(i64),
+    ToggleFloating,
+    Pin,
+    Fullscreen,
+}
+
+impl WindowAction {
+    fn dispatchers(&self, address: &str) -> Vec<String> {
+        let target = format!("address:{}", address);
+
+        match self {
+            WindowAction::Focus => vec![format!("focuswindow {}", target)],
+            WindowAction::Close => vec![format!("closewindow {}", target)],
+            WindowAction::MoveToWorkspace(id) => {
+                vec![format!("movetoworkspacesilent {},{}", id, target)]
+            }
+            WindowAction::ToggleFloating => vec![format!("togglefloating {}", target)],
+            WindowAction::Pin => vec![format!("pin {}", target)],
+            WindowAction::Fullscreen => vec![
+                format!("focuswindow {}", target),
+                "fullscreen 0".to_string(),
+            ],
+        }
+    }
+}
+
 pub fn active_clients() -> Option<Vec<Client>> {
     let output = std::process::Command::new("hyprctl")
         .args(["clients", "-j"])
@@ -11,14 +41,42 @@ pub fn active_clients() -> Option<Vec<Client>> {
 }
 
 pub fn focus_client(client: &Client) {
-    std::process::Command::new("hyprctl")
-        .args([
-            "dispatch",
-            "focuswindow",
-            format!("class:^{}$", client.class).as_str(),
-        ])
+    window_action(&client.address, WindowAction::Focus);
+}
+
+pub fn cycle_target<'a>(clients: &[&'a Client]) -> Option<&'a Client> {
+    let has_focus = clients.iter().any(|c| c.focus_history_id == 0);
+
+    let target = match has_focus {
+        true => clients.iter().max_by_key(|c| c.focus_history_id),
+        false => clients.iter().min_by_key(|c| c.focus_history_id),
+    };
+    target.copied()
+}
+
+pub fn window_action(address: &str, action: WindowAction) {
+    let batch = action
+        .dispatchers(address)
+        .iter()
+        .map(|dispatcher| format!("dispatch {}", dispatcher))
+        .collect::<Vec<_>>()
+        .join(" ; ");
+
+    if let Err(e) = std::process::Command::new("hyprctl")
+        .args(["--batch", &batch])
         .output()
-        .expect("Failed to execute command");
+    {
+        eprintln!("Failed to dispatch window action: {}", e);
+    }
+}
+
+pub fn dispatch(dispatcher: &str) {
+    let mut args = vec!["dispatch"];
+    args.extend(dispatcher.split_whitespace());
+
+    if let Err(e) = std::process::Command::new("hyprctl").args(args).output() {
+        eprintln!("Failed to dispatch {}: {}", dispatcher, e);
+    }
 }
 
 #[cfg(test)]
@@ -30,4 +88,39 @@ mod clients_tests {
         let clients = active_clients();
         assert!(clients.is_some())
     }
+
+    #[test]
+    fn window_actions_target_the_window_address() {
+        let address = "0x55d4c3a0";
+
+        assert_eq!(
+            WindowAction::Close.dispatchers(address),
+            vec!["closewindow address:0x55d4c3a0"]
+        );
+        assert_eq!(
+            WindowAction::MoveToWorkspace(3).dispatchers(address),
+            vec!["movetoworkspacesilent 3,address:0x55d4c3a0"]
+        );
+        assert_eq!(
+            WindowAction::Fullscreen.dispatchers(address),
+            vec!["focuswindow address:0x55d4c3a0", "fullscreen 0"]
+        );
+    }
+
+    #[test]
+    fn cycle_target_walks_windows_in_focus_history_order() {
+        let client = |address: &str, focus_history_id| Client {
+            address: address.to_string(),
+            focus_history_id,
+            ..Default::default()
+        };
+
+        let (a, b, c) = (client("a", 0), client("b", 3), client("c", 5));
+        assert_eq!(cycle_target(&[&a, &b, &c]).unwrap().address, "c");
+
+        let (a, b) = (client("a", 2), client("b", 4));
+        assert_eq!(cycle_target(&[&a, &b]).unwrap().address, "a");
+
+        assert!(cycle_target(&[]).is_none());
+    }
 }
@@ -1,7 +1,14 @@
 use directories::ProjectDirs;
 use gtk::CssProvider;
 use gtk::gdk::Display;
-use std::fs;
+use std::{cell::RefCell, collections::HashMap, fs};
+
+// Custom properties hybar reads itself.
+const VARIABLE_PREFIX: &str = "osd-";
+
+thread_local! {
+    static THEME_VARIABLES: RefCell<HashMap<String, String>> = RefCell::default();
+}
 
 pub fn load_css(theme: &str) {
     let provider = CssProvider::new();
@@ -9,6 +16,8 @@ pub fn load_css(theme: &str) {
     let fallback_css = include_str!("../style.css");
 
     let css_data = get_css_from_config(theme).unwrap_or_else(|| fallback_css.to_string());
+    let (css_data, variables) = split_variables(&css_data);
+    THEME_VARIABLES.with(|current| *current.borrow_mut() = variables);
 
     provider.load_from_data(&css_data);
 
@@ -21,9 +30,63 @@ pub fn load_css(theme: &str) {
     }
 }
 
+// A setting of the loaded theme that is not a style, like where the OSD
+// shows up, given as a custom property: `--osd-position: top;`.
+pub fn theme_variable(name: &str) -> Option<String> {
+    THEME_VARIABLES.with(|variables| variables.borrow().get(name).cloned())
+}
+
 fn get_css_from_config(theme: &str) -> Option<String> {
     let proj_dirs = ProjectDirs::from("com", "stron", "hybar")?;
     let css_path = proj_dirs.config_dir().join(format!("themes/{}.css", theme));
 
     fs::read_to_string(css_path).ok()
 }
+
+// Settings for hybar are custom properties prefixed with `--osd-`, taken out
+// before GTK sees the theme since versions before 4.16 reject them. Other
+// custom properties are left for the theme's own `var()` uses. Each has to be
+// on a line of its own.
+fn split_variables(css: &str) -> (String, HashMap<String, String>) {
+    let mut variables = HashMap::new();
+    let mut rest = String::with_capacity(css.len());
+    for line in css.lines() {
+        let declaration = line.trim().strip_prefix("--").and_then(|declaration| {
+            let (name, value) = declaration.split_once(':')?;
+            let name = name.trim();
+            if !name.starts_with(VARIABLE_PREFIX) {
+                return None;
+            }
+            Some((name, value.trim().trim_end_matches(';').trim()))
+        });
+        match declaration {
+            Some((name, value)) => {
+                variables.insert(name.to_string(), value.to_string());
+            }
+            None => {
+                rest.push_str(line);
+                rest.push('\n');
+            }
+        }
+    }
+    (rest, variables)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_variables_removes_osd_properties() {
+        let css = ".osd-window {\n  --osd-position: top;\n  --osd-timeout:2s;\n  --accent: #8aadf4;\n  opacity: 0.9;\n}\n";
+        let (rest, variables) = split_variables(css);
+
+        assert_eq!(
+            rest,
+            ".osd-window {\n  --accent: #8aadf4;\n  opacity: 0.9;\n}\n"
+        );
+        assert!(!variables.contains_key("accent"));
+        assert_eq!(variables["osd-position"], "top");
+        assert_eq!(variables["osd-timeout"], "2s");
+    }
+}
@@ -0,0 +1,382 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+const MAIN_GROUP: &str = "Desktop Entry";
+const ACTION_GROUP_PREFIX: &str = "Desktop Action ";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopEntry {
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub names: Vec<String>,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub icon: Option<String>,
+    pub working_dir: Option<String>,
+    pub terminal: bool,
+    pub no_display: bool,
+    pub hidden: bool,
+    pub dbus_activatable: bool,
+    pub startup_wm_class: Option<String>,
+    pub flatpak_id: Option<String>,
+    pub actions: Vec<DesktopAction>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub exec: Option<String>,
+    pub icon: Option<String>,
+}
+
+type Group = HashMap<String, String>;
+
+impl DesktopEntry {
+    pub fn parse(id: &str, path: &Path, content: &str, locale: Option<&str>) -> Option<Self> {
+        let groups = parse_groups(content);
+        let fields = groups
+            .iter()
+            .find(|(name, _)| name == MAIN_GROUP)
+            .map(|(_, fields)| fields)?;
+
+        let hidden = boolean(fields, "Hidden");
+        if !hidden && fields.get("Type").map(String::as_str) != Some("Application") {
+            return None;
+        }
+
+        let name = localized(fields, "Name", locale);
+        if name.is_none() && !hidden {
+            return None;
+        }
+
+        let mut names = fields
+            .iter()
+            .filter(|(key, _)| *key == "Name" || key.starts_with("Name["))
+            .map(|(_, value)| unescape(value))
+            .collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+
+        Some(Self {
+            id: id.to_string(),
+            path: path.to_path_buf(),
+            name: name.unwrap_or_default(),
+            names,
+            generic_name: localized(fields, "GenericName", locale),
+            comment: localized(fields, "Comment", locale),
+            keywords: localized_list(fields, "Keywords", locale),
+            categories: list(fields, "Categories"),
+            exec: string(fields, "Exec"),
+            try_exec: string(fields, "TryExec"),
+            icon: string(fields, "Icon"),
+            working_dir: string(fields, "Path"),
+            terminal: boolean(fields, "Terminal"),
+            no_display: boolean(fields, "NoDisplay"),
+            hidden,
+            dbus_activatable: boolean(fields, "DBusActivatable"),
+            startup_wm_class: string(fields, "StartupWMClass"),
+            flatpak_id: string(fields, "X-Flatpak"),
+            actions: list(fields, "Actions")
+                .into_iter()
+                .filter_map(|id| parse_action(&groups, id, locale))
+                .collect(),
+        })
+    }
+
+    pub fn executable(&self) -> Option<String> {
+        let args = shell_words::split(self.exec.as_deref()?).ok()?;
+        let mut args = args.iter().filter(|arg| !arg.starts_with('%'));
+        let mut program = args.next()?;
+
+        if file_name(program) == "env" {
+            program = args.find(|arg| !arg.starts_with('-') && !arg.contains('='))?;
+        }
+
+        Some(file_name(program)).filter(|program| !program.is_empty())
+    }
+
+    // org.gnome.Nautilus.desktop -> nautilus
+    pub fn reverse_dns_name(&self) -> Option<String> {
+        let stem = self.id.strip_suffix(".desktop").unwrap_or(&self.id);
+        let (_, name) = stem.rsplit_once('.')?;
+        Some(name.to_lowercase()).filter(|name| !name.is_empty())
+    }
+}
+
+fn parse_action(
+    groups: &[(String, Group)],
+    id: String,
+    locale: Option<&str>,
+) -> Option<DesktopAction> {
+    let group = format!("{}{}", ACTION_GROUP_PREFIX, id);
+    let fields = groups
+        .iter()
+        .find(|(name, _)| *name == group)
+        .map(|(_, fields)| fields)?;
+
+    Some(DesktopAction {
+        name: localized(fields, "Name", locale)?,
+        exec: string(fields, "Exec"),
+        icon: string(fields, "Icon"),
+        id,
+    })
+}
+
+pub fn parse_groups(content: &str) -> Vec<(String, Group)> {
+    let mut groups: Vec<(String, Group)> = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(group) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            groups.push((group.to_string(), Group::new()));
+            continue;
+        }
+
+        let (Some((_, fields)), Some((key, value))) = (groups.last_mut(), line.split_once('='))
+        else {
+            continue;
+        };
+
+        fields
+            .entry(key.trim().to_string())
+            .or_insert_with(|| value.trim().to_string());
+    }
+
+    groups
+}
+
+pub fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+}
+
+// lang_COUNTRY.ENCODING@MODIFIER matches, in order: lang_COUNTRY@MODIFIER,
+// lang_COUNTRY, lang@MODIFIER, lang.
+fn locale_keys(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or(locale);
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    let mut keys = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        keys.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        keys.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        keys.push(format!("{}@{}", lang, modifier));
+    }
+    keys.push(lang.to_string());
+    keys
+}
+
+fn localized_raw<'a>(fields: &'a Group, key: &str, locale: Option<&str>) -> Option<&'a String> {
+    locale
+        .map(locale_keys)
+        .unwrap_or_default()
+        .iter()
+        .find_map(|locale| fields.get(&format!("{}[{}]", key, locale)))
+        .or_else(|| fields.get(key))
+}
+
+fn localized(fields: &Group, key: &str, locale: Option<&str>) -> Option<String> {
+    localized_raw(fields, key, locale).map(|value| unescape(value))
+}
+
+fn localized_list(fields: &Group, key: &str, locale: Option<&str>) -> Vec<String> {
+    localized_raw(fields, key, locale)
+        .map(|value| split_list(value))
+        .unwrap_or_default()
+}
+
+fn string(fields: &Group, key: &str) -> Option<String> {
+    fields
+        .get(key)
+        .map(|value| unescape(value))
+        .filter(|value| !value.is_empty())
+}
+
+fn list(fields: &Group, key: &str) -> Vec<String> {
+    fields
+        .get(key)
+        .map(|value| split_list(value))
+        .unwrap_or_default()
+}
+
+fn boolean(fields: &Group, key: &str) -> bool {
+    fields.get(key).is_some_and(|value| value == "true")
+}
+
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+pub fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items
+        .iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn file_name(program: &str) -> String {
+    Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = "[Desktop Entry]
+Type=Application
+Name=Firefox
+Name[de]=Firefox Webbrowser
+Name[pt_BR]=Navegador Firefox
+Comment=Browse the Web
+Comment[de]=Im Internet surfen
+Keywords=Internet;WWW;Browser\\;Web;
+Exec=env MOZ_ENABLE_WAYLAND=1 /usr/lib/firefox/firefox %u
+Icon=firefox
+StartupWMClass=firefox
+Actions=new-window;new-private-window;missing;
+
+[Desktop Action new-window]
+Name=New Window
+Name[de]=Neues Fenster
+Exec=firefox --new-window %u
+
+[Desktop Action new-private-window]
+Name=New Private Window
+Exec=firefox --private-window %u
+";
+
+    fn firefox(locale: Option<&str>) -> DesktopEntry {
+        DesktopEntry::parse(
+            "firefox.desktop",
+            Path::new("firefox.desktop"),
+            FIREFOX,
+            locale,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_reads_localized_fields_from_the_main_group() {
+        let entry = firefox(Some("pt_BR.UTF-8"));
+        assert_eq!(entry.name, "Navegador Firefox");
+        assert_eq!(entry.comment.as_deref(), Some("Browse the Web"));
+        assert_eq!(entry.keywords, vec!["Internet", "WWW", "Browser;Web"]);
+        assert!(entry.names.contains(&"Firefox Webbrowser".to_string()));
+
+        let entry = firefox(Some("de_AT@euro"));
+        assert_eq!(entry.name, "Firefox Webbrowser");
+        assert_eq!(entry.comment.as_deref(), Some("Im Internet surfen"));
+        assert_eq!(entry.startup_wm_class.as_deref(), Some("firefox"));
+    }
+
+    #[test]
+    fn parse_reads_the_declared_actions() {
+        let entry = firefox(Some("de_DE.UTF-8"));
+        let actions = entry
+            .actions
+            .iter()
+            .map(|action| (action.id.as_str(), action.name.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            actions,
+            vec![
+                ("new-window", "Neues Fenster"),
+                ("new-private-window", "New Private Window")
+            ]
+        );
+        assert_eq!(
+            entry.actions[1].exec.as_deref(),
+            Some("firefox --private-window %u")
+        );
+    }
+
+    #[test]
+    fn executable_skips_env_assignments() {
+        let entry = firefox(None);
+        assert_eq!(entry.executable().as_deref(), Some("firefox"));
+    }
+
+    #[test]
+    fn parse_rejects_entries_that_are_not_applications() {
+        let link = "[Desktop Entry]\nType=Link\nName=Docs\nURL=https://example.com\n";
+        assert!(DesktopEntry::parse("docs.desktop", Path::new("docs"), link, None).is_none());
+
+        let hidden = "[Desktop Entry]\nHidden=true\n";
+        let entry = DesktopEntry::parse("gone.desktop", Path::new("gone"), hidden, None).unwrap();
+        assert!(entry.hidden);
+    }
+}
@@ -0,0 +1,436 @@
+use gtk::glib;
+use serde::Deserialize;
+use std::{cell::Cell, collections::HashMap, rc::Rc};
+
+use crate::user::models::FormatConfig;
+
+const ELLIPSIS: char = '…';
+
+pub type Values<'a> = HashMap<&'a str, String>;
+
+// Icons picked by the `icon` filter: a list is indexed by a 0-100 value, a map
+// by the value itself with "default" as the fallback.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum FormatIcons {
+    List(Vec<String>),
+    Map(HashMap<String, String>),
+}
+
+impl Default for FormatIcons {
+    fn default() -> Self {
+        FormatIcons::List(Vec::new())
+    }
+}
+
+impl FormatIcons {
+    fn get(&self, value: &str) -> Option<&str> {
+        match self {
+            FormatIcons::List(icons) if icons.is_empty() => None,
+            FormatIcons::List(icons) => {
+                let percent = value.trim().trim_end_matches('%').parse::<f64>().ok()?;
+                let last = icons.len() - 1;
+                let index = (percent.clamp(0.0, 100.0) / 100.0 * last as f64).round() as usize;
+                icons.get(index.min(last)).map(String::as_str)
+            }
+            FormatIcons::Map(icons) => icons
+                .get(value)
+                .or(icons.get("default"))
+                .map(String::as_str),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Truncate(usize),
+    Pad(usize),
+    PadLeft(usize),
+    Upper,
+    Lower,
+    Escape,
+    Raw,
+    Icon,
+}
+
+impl Filter {
+    fn parse(name: &str, arg: Option<&str>) -> Result<Self, String> {
+        let width = || {
+            arg.and_then(|arg| arg.trim().parse::<usize>().ok())
+                .ok_or_else(|| format!("Filter {} needs a width, e.g. {}(10)", name, name))
+        };
+        match name {
+            "truncate" => Ok(Filter::Truncate(width()?)),
+            "pad" => Ok(Filter::Pad(width()?)),
+            "pad_left" => Ok(Filter::PadLeft(width()?)),
+            "upper" => Ok(Filter::Upper),
+            "lower" => Ok(Filter::Lower),
+            "escape" => Ok(Filter::Escape),
+            "raw" => Ok(Filter::Raw),
+            "icon" => Ok(Filter::Icon),
+            _ => Err(format!("Unknown filter: {}", name)),
+        }
+    }
+
+    // `markup` tells whether the value is already markup, which `escape`,
+    // `raw` and `icon` turn on. Values still plain at the end are escaped.
+    fn apply(&self, value: String, markup: bool, icons: &FormatIcons) -> (String, bool) {
+        let value = match self {
+            Filter::Truncate(width) if value.chars().count() > *width => value
+                .chars()
+                .take(width.saturating_sub(1))
+                .chain([ELLIPSIS])
+                .collect(),
+            Filter::Truncate(_) => value,
+            Filter::Pad(width) => format!("{:<width$}", value, width = width),
+            Filter::PadLeft(width) => format!("{:>width$}", value, width = width),
+            Filter::Upper => value.to_uppercase(),
+            Filter::Lower => value.to_lowercase(),
+            Filter::Escape if !markup => escape_markup(&value),
+            Filter::Escape | Filter::Raw => value,
+            Filter::Icon => icons.get(&value).unwrap_or_default().to_string(),
+        };
+        let markup = markup || matches!(self, Filter::Escape | Filter::Raw | Filter::Icon);
+        (value, markup)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Value {
+        key: String,
+        filters: Vec<Filter>,
+    },
+    Condition {
+        key: String,
+        then: Vec<Part>,
+        otherwise: Vec<Part>,
+    },
+}
+
+// Labels and tooltips are written as templates such as
+// `{?artist|{artist} - }{title|truncate(20)}`:
+//   {key}                    the value, empty when missing
+//   {key|filter|filter(n)}   truncate(n), pad(n), pad_left(n), upper, lower,
+//                            escape, raw and icon
+//   {?key|then|else}         `then` when the value is set and not empty
+//   {{ and }}                literal braces
+// Values are escaped for Pango markup unless `raw` says they already are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let parts = parser.parts(false)?;
+        Ok(Self { parts })
+    }
+
+    pub fn render(&self, values: &Values, icons: &FormatIcons) -> String {
+        let mut out = String::new();
+        render_parts(&self.parts, values, icons, &mut out);
+        out
+    }
+}
+
+fn render_parts(parts: &[Part], values: &Values, icons: &FormatIcons, out: &mut String) {
+    for part in parts {
+        match part {
+            Part::Text(text) => out.push_str(text),
+            Part::Value { key, filters } => {
+                let value = values.get(key.as_str()).cloned().unwrap_or_default();
+                let (value, markup) = filters
+                    .iter()
+                    .fold((value, false), |(value, markup), filter| {
+                        filter.apply(value, markup, icons)
+                    });
+                match markup {
+                    true => out.push_str(&value),
+                    false => out.push_str(&escape_markup(&value)),
+                }
+            }
+            Part::Condition {
+                key,
+                then,
+                otherwise,
+            } => {
+                let is_set = values
+                    .get(key.as_str())
+                    .is_some_and(|value| !value.is_empty());
+                let branch = if is_set { then } else { otherwise };
+                render_parts(branch, values, icons, out);
+            }
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    // Inside a condition branch `|` and `}` end the branch instead of being
+    // literal text.
+    fn parts(&mut self, in_branch: bool) -> Result<Vec<Part>, String> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            let next = self.chars.get(self.pos + 1).copied();
+            match (c, next) {
+                ('{', Some('{')) | ('}', Some('}')) if !in_branch || c == '{' => {
+                    text.push(c);
+                    self.pos += 2;
+                }
+                ('{', _) => {
+                    self.pos += 1;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(self.expression()?);
+                }
+                ('}' | '|', _) if in_branch => break,
+                ('}', _) => return Err(format!("Unmatched }} at {}", self.pos)),
+                _ => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+        if in_branch && self.peek().is_none() {
+            return Err("Unclosed {".to_string());
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(parts)
+    }
+
+    fn expression(&mut self) -> Result<Part, String> {
+        if self.peek() == Some('?') {
+            self.pos += 1;
+            let key = self.key()?;
+            self.expect('|')?;
+            let then = self.parts(true)?;
+            let otherwise = match self.peek() {
+                Some('|') => {
+                    self.pos += 1;
+                    self.parts(true)?
+                }
+                _ => Vec::new(),
+            };
+            self.expect('}')?;
+            return Ok(Part::Condition {
+                key,
+                then,
+                otherwise,
+            });
+        }
+
+        let key = self.key()?;
+        let mut filters = Vec::new();
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            let name = self.key()?;
+            let arg = match self.peek() {
+                Some('(') => {
+                    self.pos += 1;
+                    let start = self.pos;
+                    while self.peek().is_some_and(|c| c != ')') {
+                        self.pos += 1;
+                    }
+                    let arg = self.chars[start..self.pos].iter().collect::<String>();
+                    self.expect(')')?;
+                    Some(arg)
+                }
+                _ => None,
+            };
+            filters.push(Filter::parse(&name, arg.as_deref())?);
+        }
+        self.expect('}')?;
+        Ok(Part::Value { key, filters })
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        {
+            self.pos += 1;
+        }
+        match self.pos > start {
+            true => Ok(self.chars[start..self.pos].iter().collect()),
+            false => Err(format!("Expected a name at {}", self.pos)),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => Err(format!(
+                "Expected {} at {}, found {}",
+                expected, self.pos, c
+            )),
+            None => Err(format!("Expected {} at the end", expected)),
+        }
+    }
+}
+
+pub fn escape_markup(text: &str) -> String {
+    glib::markup_escape_text(text).to_string()
+}
+
+// A widget's `format`, `format_alt` and `tooltip_format` options. The alt
+// format replaces the main one while toggled on.
+#[derive(Debug, Clone)]
+pub struct Format {
+    format: Template,
+    alt: Option<Template>,
+    tooltip: Option<Template>,
+    icons: Rc<FormatIcons>,
+    show_alt: Rc<Cell<bool>>,
+}
+
+impl Format {
+    pub fn new(config: Option<&FormatConfig>, default_format: &str) -> Self {
+        let template = |source: Option<&String>| {
+            source.and_then(|source| {
+                Template::parse(source)
+                    .inspect_err(|e| eprintln!("Invalid format {:?}: {}", source, e))
+                    .ok()
+            })
+        };
+
+        Self {
+            format: template(config.and_then(|c| c.format.as_ref()))
+                .or_else(|| Template::parse(default_format).ok())
+                .unwrap_or_default(),
+            alt: template(config.and_then(|c| c.format_alt.as_ref())),
+            tooltip: template(config.and_then(|c| c.tooltip_format.as_ref())),
+            icons: Rc::new(
+                config
+                    .and_then(|c| c.format_icons.clone())
+                    .unwrap_or_default(),
+            ),
+            show_alt: Rc::new(Cell::new(false)),
+        }
+    }
+
+    // Used unless the config sets its own `tooltip_format`.
+    pub fn with_default_tooltip(mut self, tooltip: &str) -> Self {
+        if self.tooltip.is_none() {
+            self.tooltip = Template::parse(tooltip).ok();
+        }
+        self
+    }
+
+    pub fn text(&self, values: &Values) -> String {
+        let template = match &self.alt {
+            Some(alt) if self.show_alt.get() => alt,
+            _ => &self.format,
+        };
+        template.render(values, &self.icons)
+    }
+
+    pub fn tooltip(&self, values: &Values) -> Option<String> {
+        self.tooltip
+            .as_ref()
+            .map(|tooltip| tooltip.render(values, &self.icons))
+    }
+
+    pub fn has_tooltip(&self) -> bool {
+        self.tooltip.is_some()
+    }
+
+    // Returns false when there is no alt format to switch to.
+    pub fn toggle_alt(&self) -> bool {
+        if self.alt.is_none() {
+            return false;
+        }
+        self.show_alt.set(!self.show_alt.get());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str, values: &[(&'static str, &str)]) -> String {
+        let values = values
+            .iter()
+            .map(|(key, value)| (*key, value.to_string()))
+            .collect::<Values>();
+        Template::parse(source)
+            .unwrap()
+            .render(&values, &FormatIcons::default())
+    }
+
+    #[test]
+    fn render_applies_filters_and_conditions() {
+        let values = [("title", "Bohemian Rhapsody"), ("artist", "Queen")];
+        assert_eq!(
+            render("{?artist|{artist|upper} - }{title|truncate(8)}", &values),
+            "QUEEN - Bohemia…"
+        );
+        assert_eq!(
+            render("{?album|{album}|no album} {{{artist|lower}}}", &values),
+            "no album {queen}"
+        );
+        assert_eq!(render("[{artist|pad(7)}]", &values), "[Queen  ]");
+        assert_eq!(render("[{artist|pad_left(7)}]", &values), "[  Queen]");
+        assert_eq!(
+            render("<b>{title}</b>", &[("title", "Tom & <Jerry>")]),
+            "<b>Tom &amp; &lt;Jerry&gt;</b>"
+        );
+        assert_eq!(
+            render("{title|escape}", &[("title", "Tom & Jerry")]),
+            "Tom &amp; Jerry"
+        );
+        assert_eq!(
+            render("{title|raw}", &[("title", "<i>Tom</i>")]),
+            "<i>Tom</i>"
+        );
+        assert_eq!(render("{missing}!", &values), "!");
+    }
+
+    #[test]
+    fn icons_are_indexed_by_value() {
+        let list = FormatIcons::List(vec!["low".into(), "mid".into(), "high".into()]);
+        assert_eq!(list.get("0"), Some("low"));
+        assert_eq!(list.get("60%"), Some("mid"));
+        assert_eq!(list.get("100"), Some("high"));
+        assert_eq!(list.get("playing"), None);
+
+        let map = FormatIcons::Map(HashMap::from([
+            ("playing".to_string(), "▶".to_string()),
+            ("default".to_string(), "■".to_string()),
+        ]));
+        assert_eq!(map.get("playing"), Some("▶"));
+        assert_eq!(map.get("stopped"), Some("■"));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_templates() {
+        assert!(Template::parse("{title").is_err());
+        assert!(Template::parse("title}").is_err());
+        assert!(Template::parse("{title|shout}").is_err());
+        assert!(Template::parse("{title|truncate}").is_err());
+        assert!(Template::parse("{?title|open").is_err());
+        assert!(Template::parse("{}").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    error::Error,
+    fs,
+    path::PathBuf,
+    rc::Rc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::utils::search::DesktopIndex;
+
+const HISTORY_FILE: &str = "history.json";
+const MAX_VISITS: usize = 10;
+const DAY_SECS: u64 = 86_400;
+// Visit weights by age in days, the same buckets Firefox uses for frecency.
+const VISIT_WEIGHTS: [(u64, f64); 4] = [(4, 100.0), (14, 70.0), (31, 50.0), (90, 30.0)];
+const OLD_VISIT_WEIGHT: f64 = 10.0;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    pub count: u32,
+    pub visits: Vec<u64>,
+}
+
+impl Record {
+    // The average weight of the sampled visits scaled by the total launch
+    // count, so both frequent and recent launches rank high.
+    pub fn frecency(&self, now: u64) -> f64 {
+        if self.visits.is_empty() {
+            return 0.0;
+        }
+
+        let total = self
+            .visits
+            .iter()
+            .map(|visit| visit_weight(now.saturating_sub(*visit) / DAY_SECS))
+            .sum::<f64>();
+        self.count as f64 * total / self.visits.len() as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LaunchHistory {
+    path: Option<PathBuf>,
+    index: DesktopIndex,
+    records: Rc<RefCell<HashMap<String, Record>>>,
+}
+
+impl LaunchHistory {
+    pub fn new(index: DesktopIndex) -> Self {
+        Self::with_path(history_path(), index)
+    }
+
+    pub fn with_path(path: Option<PathBuf>, index: DesktopIndex) -> Self {
+        let records = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default();
+
+        Self {
+            path,
+            index,
+            records: Rc::new(RefCell::new(records)),
+        }
+    }
+
+    // Launches are keyed by desktop id whenever the app resolves to one, so the
+    // dock, custom apps and the launcher all share the same record.
+    pub fn record(&self, app: &str) {
+        self.record_at(app, now());
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save launch history: {}", e);
+        }
+    }
+
+    pub fn frecency(&self, app: &str) -> f64 {
+        self.records
+            .borrow()
+            .get(&self.key(app))
+            .map_or(0.0, |record| record.frecency(now()))
+    }
+
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        let now = now();
+        let records = self.records.borrow();
+        let mut recent = records
+            .iter()
+            .map(|(app, record)| (app.clone(), record.frecency(now)))
+            .collect::<Vec<_>>();
+        recent.sort_by(|(a, score_a), (b, score_b)| score_b.total_cmp(score_a).then(a.cmp(b)));
+
+        recent.into_iter().take(limit).map(|(app, _)| app).collect()
+    }
+
+    pub fn clear(&self) {
+        self.records.borrow_mut().clear();
+        if let Some(path) = &self.path
+            && path.exists()
+            && let Err(e) = fs::remove_file(path)
+        {
+            eprintln!("Failed to clear launch history: {}", e);
+        }
+    }
+
+    fn record_at(&self, app: &str, now: u64) {
+        let key = self.key(app);
+        if key.is_empty() {
+            return;
+        }
+
+        let mut records = self.records.borrow_mut();
+        let record = records.entry(key).or_default();
+        record.count += 1;
+        record.visits.push(now);
+        if record.visits.len() > MAX_VISITS {
+            record.visits.remove(0);
+        }
+    }
+
+    fn key(&self, app: &str) -> String {
+        self.index
+            .find_id(app)
+            .unwrap_or_else(|| app.trim().to_lowercase())
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let raw = serde_json::to_string(&*self.records.borrow())?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, raw)?;
+        fs::rename(tmp_path, path)?;
+        Ok(())
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "stron", "hybar")?;
+    proj_dirs.state_dir().map(|dir| dir.join(HISTORY_FILE))
+}
+
+fn visit_weight(age_days: u64) -> f64 {
+    VISIT_WEIGHTS
+        .iter()
+        .find(|(max_age, _)| age_days <= *max_age)
+        .map_or(OLD_VISIT_WEIGHT, |(_, weight)| *weight)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn history() -> (LaunchHistory, TempDir) {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("applications");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("firefox.desktop"),
+            "[Desktop Entry]\nType=Application\nName=Firefox\nExec=firefox %u\n",
+        )
+        .unwrap();
+
+        let history = LaunchHistory::with_path(
+            Some(root.path().join("state").join(HISTORY_FILE)),
+            DesktopIndex::with_dirs(vec![dir]),
+        );
+        (history, root)
+    }
+
+    #[test]
+    fn frecency_favors_recent_visits() {
+        let now = 100 * DAY_SECS;
+        let recent = Record {
+            count: 2,
+            visits: vec![now - DAY_SECS, now],
+        };
+        let old = Record {
+            count: 2,
+            visits: vec![now - 60 * DAY_SECS, now - 95 * DAY_SECS],
+        };
+
+        assert_eq!(recent.frecency(now), 200.0);
+        assert_eq!(old.frecency(now), 40.0);
+        assert_eq!(Record::default().frecency(now), 0.0);
+    }
+
+    #[test]
+    fn records_are_shared_by_desktop_id_and_persisted() {
+        let (history, root) = history();
+        let now = now();
+        history.record_at("firefox", now);
+        history.record_at("firefox.desktop", now);
+        history.record_at("Firefox", now);
+        history.record_at("htop", now - 40 * DAY_SECS);
+        history.save().unwrap();
+
+        assert_eq!(history.recent(5), vec!["firefox.desktop", "htop"]);
+        assert_eq!(history.records.borrow()["firefox.desktop"].count, 3);
+
+        let reloaded = LaunchHistory::with_path(history.path.clone(), history.index.clone());
+        assert_eq!(reloaded.frecency("firefox"), history.frecency("firefox"));
+
+        reloaded.clear();
+        assert!(reloaded.recent(5).is_empty());
+        assert!(!root.path().join("state").join(HISTORY_FILE).exists());
+    }
+}
@@ -0,0 +1,225 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use gtk::{gdk, gio, prelude::*};
+
+use crate::utils::app_matcher::AppMatcher;
+
+const AVATAR_COLORS: u32 = 6;
+const ICON_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+#[derive(Debug, Clone)]
+pub enum Icon {
+    Paintable(gtk::IconPaintable),
+    Glyph(String),
+    Avatar(String, u32),
+}
+
+impl Icon {
+    pub fn widget(&self, size: i32) -> gtk::Widget {
+        match self {
+            Icon::Paintable(paintable) => {
+                let image = gtk::Image::from_paintable(Some(paintable));
+                image.set_pixel_size(size);
+                image.into()
+            }
+            Icon::Glyph(glyph) => label(glyph, "icon-glyph", size).into(),
+            Icon::Avatar(letter, color) => {
+                let label = label(letter, "icon-avatar", size);
+                label.add_css_class(&format!("avatar-{}", color));
+                label.into()
+            }
+        }
+    }
+}
+
+type CacheKey = (String, i32, i32);
+
+#[derive(Debug, Clone)]
+pub struct IconResolver {
+    matcher: AppMatcher,
+    overrides: Rc<RefCell<HashMap<String, String>>>,
+    cache: Rc<RefCell<HashMap<CacheKey, Icon>>>,
+}
+
+impl IconResolver {
+    pub fn new(matcher: AppMatcher, overrides: HashMap<String, String>) -> Self {
+        let resolver = Self {
+            matcher,
+            overrides: Rc::new(RefCell::new(HashMap::new())),
+            cache: Rc::new(RefCell::new(HashMap::new())),
+        };
+        resolver.set_overrides(overrides);
+
+        if let Some(display) = gdk::Display::default() {
+            let cache = Rc::clone(&resolver.cache);
+            gtk::IconTheme::for_display(&display).connect_changed(move |_| {
+                cache.borrow_mut().clear();
+            });
+        }
+
+        resolver
+    }
+
+    pub fn set_overrides(&self, overrides: HashMap<String, String>) {
+        *self.overrides.borrow_mut() = overrides
+            .into_iter()
+            .map(|(class, icon)| (class.to_lowercase(), icon))
+            .collect();
+        self.cache.borrow_mut().clear();
+    }
+
+    pub fn widget(&self, app_name: &str, size: i32, scale: i32) -> gtk::Widget {
+        self.resolve(app_name, size, scale).widget(size)
+    }
+
+    pub fn resolve(&self, app_name: &str, size: i32, scale: i32) -> Icon {
+        let key = (app_name.to_lowercase(), size, scale);
+        if let Some(icon) = self.cache.borrow().get(&key) {
+            return icon.clone();
+        }
+
+        let icon = self.lookup(app_name, size, scale);
+        self.cache.borrow_mut().insert(key, icon.clone());
+        icon
+    }
+
+    fn lookup(&self, app_name: &str, size: i32, scale: i32) -> Icon {
+        let theme = gdk::Display::default().map(|display| gtk::IconTheme::for_display(&display));
+        let find = |name: &str| find_icon(theme.as_ref(), name, size, scale);
+
+        let override_icon = self
+            .overrides
+            .borrow()
+            .get(&app_name.to_lowercase())
+            .cloned();
+        if let Some(value) = override_icon {
+            if let Some(icon) = find(&value) {
+                return icon;
+            }
+            if is_glyph(&value) {
+                return Icon::Glyph(value);
+            }
+            eprintln!("Icon override for {} not found: {}", app_name, value);
+        }
+
+        self.matcher
+            .resolve(app_name)
+            .and_then(|entry| entry.icon)
+            .and_then(|icon| find(&icon))
+            .or_else(|| find(&app_name.to_lowercase()))
+            .unwrap_or_else(|| {
+                let (letter, color) = avatar(app_name);
+                Icon::Avatar(letter, color)
+            })
+    }
+}
+
+fn find_icon(theme: Option<&gtk::IconTheme>, name: &str, size: i32, scale: i32) -> Option<Icon> {
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let path = expand_home(name);
+    if path.is_absolute() {
+        return path.is_file().then(|| {
+            Icon::Paintable(gtk::IconPaintable::for_file(
+                &gio::File::for_path(&path),
+                size,
+                scale,
+            ))
+        });
+    }
+
+    // Icon= should be a bare theme name, but some entries still carry an extension.
+    let theme = theme?;
+    [name, strip_extension(name)]
+        .into_iter()
+        .find(|name| theme.has_icon(name))
+        .map(|name| {
+            Icon::Paintable(theme.lookup_icon(
+                name,
+                &[],
+                size,
+                scale,
+                gtk::TextDirection::None,
+                gtk::IconLookupFlags::empty(),
+            ))
+        })
+}
+
+fn label(text: &str, class: &str, size: i32) -> gtk::Label {
+    let label = gtk::Label::new(Some(text));
+    label.add_css_class(class);
+    label.set_size_request(size, size);
+    label.set_halign(gtk::Align::Center);
+    label.set_valign(gtk::Align::Center);
+    label
+}
+
+fn expand_home(value: &str) -> PathBuf {
+    match value.strip_prefix("~/") {
+        Some(rest) => Path::new(&std::env::var("HOME").unwrap_or_default()).join(rest),
+        None => PathBuf::from(value),
+    }
+}
+
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if ICON_EXTENSIONS.contains(&extension) => stem,
+        _ => name,
+    }
+}
+
+// Nerd Font and emoji glyphs are one or two non-ascii characters; anything
+// longer is a theme name or path that failed to resolve.
+fn is_glyph(value: &str) -> bool {
+    let count = value.chars().count();
+    (1..=2).contains(&count) && value.chars().all(|c| !c.is_ascii())
+}
+
+fn avatar(app_name: &str) -> (String, u32) {
+    let letter = app_name
+        .chars()
+        .find(|c| c.is_alphanumeric())
+        .map(|c| c.to_uppercase().to_string())
+        .unwrap_or_else(|| "?".to_string());
+    let color = app_name.to_lowercase().bytes().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(31).wrapping_add(byte as u32)
+    }) % AVATAR_COLORS;
+    (letter, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glyph_overrides_are_short_non_ascii_strings() {
+        assert!(is_glyph("\u{f269}"));
+        assert!(is_glyph("🦊"));
+        assert!(!is_glyph("firefox"));
+        assert!(!is_glyph("ff"));
+        assert!(!is_glyph(""));
+    }
+
+    #[test]
+    fn avatar_uses_the_first_letter_and_a_stable_color() {
+        assert_eq!(avatar("steam_app_570").0, "S");
+        assert_eq!(avatar("__42").0, "4");
+        assert_eq!(avatar("--").0, "?");
+        assert_eq!(avatar("Steam").1, avatar("steam").1);
+        assert!(avatar("anything").1 < AVATAR_COLORS);
+    }
+
+    #[test]
+    fn strip_extension_only_removes_image_extensions() {
+        assert_eq!(strip_extension("firefox.png"), "firefox");
+        assert_eq!(strip_extension("org.gnome.Nautilus"), "org.gnome.Nautilus");
+    }
+}
@@ -0,0 +1,213 @@
+use gtk::{gio, glib};
+
+use crate::UiEvent;
+
+const INTERFACE: &str = "com.canonical.Unity.LauncherEntry";
+const MEMBER: &str = "Update";
+const MAX_BADGE_COUNT: i64 = 99;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LauncherEntry {
+    pub count: Option<i64>,
+    pub count_visible: Option<bool>,
+    pub progress: Option<f64>,
+    pub progress_visible: Option<bool>,
+    pub urgent: Option<bool>,
+}
+
+impl LauncherEntry {
+    pub fn merge(&mut self, update: LauncherEntry) {
+        self.count = update.count.or(self.count);
+        self.count_visible = update.count_visible.or(self.count_visible);
+        self.progress = update.progress.or(self.progress);
+        self.progress_visible = update.progress_visible.or(self.progress_visible);
+        self.urgent = update.urgent.or(self.urgent);
+    }
+
+    pub fn badge(&self) -> Option<String> {
+        let count = self.count.filter(|count| *count > 0)?;
+        if self.count_visible != Some(true) {
+            return None;
+        }
+
+        match count > MAX_BADGE_COUNT {
+            true => Some(format!("{}+", MAX_BADGE_COUNT)),
+            false => Some(count.to_string()),
+        }
+    }
+
+    pub fn progress(&self) -> Option<f64> {
+        match self.progress_visible {
+            Some(true) => self.progress.map(|progress| progress.clamp(0.0, 1.0)),
+            _ => None,
+        }
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.urgent == Some(true)
+    }
+}
+
+pub fn parse_update(parameters: &glib::Variant) -> Option<(String, LauncherEntry)> {
+    let (app_uri, properties) = parameters.get::<(String, glib::VariantDict)>()?;
+    let desktop_id = app_uri
+        .strip_prefix("application://")
+        .unwrap_or(&app_uri)
+        .to_string();
+
+    let entry = LauncherEntry {
+        count: properties.lookup::<i64>("count").ok().flatten(),
+        count_visible: properties.lookup::<bool>("count-visible").ok().flatten(),
+        progress: properties.lookup::<f64>("progress").ok().flatten(),
+        progress_visible: properties.lookup::<bool>("progress-visible").ok().flatten(),
+        urgent: properties.lookup::<bool>("urgent").ok().flatten(),
+    };
+
+    Some((desktop_id, entry))
+}
+
+pub fn listen(
+    connection: &gio::DBusConnection,
+    sender: async_channel::Sender<UiEvent>,
+) -> gio::SignalSubscriptionId {
+    connection.signal_subscribe(
+        None,
+        Some(INTERFACE),
+        Some(MEMBER),
+        None,
+        None,
+        gio::DBusSignalFlags::NONE,
+        move |_, _, _, _, _, parameters| {
+            let Some(update) = parse_update(parameters) else {
+                eprintln!("Ignoring malformed launcher entry update: {}", parameters);
+                return;
+            };
+
+            sender
+                .try_send(UiEvent::LauncherEntryUpdated(update))
+                .unwrap_or_else(|e| eprintln!("Failed to send launcher entry event: {}", e));
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gtk::prelude::*;
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+        time::{Duration, Instant},
+    };
+
+    fn update(app_uri: &str, properties: &[(&str, glib::Variant)]) -> glib::Variant {
+        let dict = glib::VariantDict::new(None);
+        for (key, value) in properties {
+            dict.insert_value(key, value);
+        }
+        (app_uri.to_string(), dict).to_variant()
+    }
+
+    #[test]
+    fn parse_update_reads_badge_and_progress() {
+        let parameters = update(
+            "application://org.telegram.desktop.desktop",
+            &[
+                ("count", 3i64.to_variant()),
+                ("count-visible", true.to_variant()),
+                ("progress", 0.5f64.to_variant()),
+            ],
+        );
+
+        let (desktop_id, entry) = parse_update(&parameters).unwrap();
+        assert_eq!(desktop_id, "org.telegram.desktop.desktop");
+        assert_eq!(entry.badge().as_deref(), Some("3"));
+        assert_eq!(entry.progress(), None);
+        assert!(!entry.is_urgent());
+    }
+
+    #[test]
+    fn merge_keeps_properties_missing_from_the_update() {
+        let mut entry = LauncherEntry {
+            count: Some(120),
+            count_visible: Some(true),
+            ..Default::default()
+        };
+        entry.merge(LauncherEntry {
+            progress: Some(1.4),
+            progress_visible: Some(true),
+            urgent: Some(true),
+            ..Default::default()
+        });
+
+        assert_eq!(entry.badge().as_deref(), Some("99+"));
+        assert_eq!(entry.progress(), Some(1.0));
+        assert!(entry.is_urgent());
+    }
+
+    #[test]
+    fn listen_receives_updates_from_a_private_bus() {
+        let Ok(mut daemon) = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let context = glib::MainContext::new();
+        let received = context.with_thread_default(|| {
+            let flags = gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+            let connect = || {
+                gio::DBusConnection::for_address_sync(
+                    address.trim(),
+                    flags,
+                    None,
+                    gio::Cancellable::NONE,
+                )
+                .unwrap()
+            };
+            let listener = connect();
+            let emitter = connect();
+
+            let (sender, receiver) = async_channel::unbounded();
+            let _subscription = listen(&listener, sender);
+            listener.flush_sync(gio::Cancellable::NONE).unwrap();
+
+            let parameters = update(
+                "application://thunderbird.desktop",
+                &[("urgent", true.to_variant())],
+            );
+            emitter
+                .emit_signal(None, "/", INTERFACE, MEMBER, Some(&parameters))
+                .unwrap();
+            emitter.flush_sync(gio::Cancellable::NONE).unwrap();
+
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                context.iteration(false);
+                if let Ok(UiEvent::LauncherEntryUpdated(update)) = receiver.try_recv() {
+                    break Some(update);
+                }
+                if Instant::now() > deadline {
+                    break None;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        });
+        daemon.kill().ok();
+
+        let (desktop_id, entry) = received
+            .unwrap()
+            .expect("no launcher entry update received");
+        assert_eq!(desktop_id, "thunderbird.desktop");
+        assert!(entry.is_urgent());
+    }
+}
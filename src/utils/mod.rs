@@ -1,20 +1,88 @@
 pub mod app_launch;
+pub mod app_matcher;
+pub mod backlight;
+pub mod battery;
 pub mod clients;
 pub mod css;
+pub mod desktop_entry;
+pub mod format;
+pub mod history;
+pub mod icons;
+pub mod launcher_entry;
+pub mod network_manager;
+pub mod procfs;
+pub mod rtnetlink;
+pub mod script;
 pub mod search;
+pub mod uevent;
 
-pub fn desktop_dirs() -> Vec<String> {
-    vec![
-        "/usr/share/applications".into(),
-        "/usr/local/share/applications".into(),
-        "/var/lib/flatpak/exports/share/applications".into(),
-        format!(
-            "{}/.local/share/flatpak/exports/share/applications",
-            std::env::var("HOME").unwrap()
-        ),
-        format!(
-            "{}/.local/share/applications",
-            std::env::var("HOME").unwrap()
-        ),
-    ]
+use std::path::PathBuf;
+
+pub fn application_dirs() -> Vec<PathBuf> {
+    let home = PathBuf::from(std::env::var("HOME").unwrap_or_default());
+    data_dirs(
+        std::env::var("XDG_DATA_HOME").ok(),
+        std::env::var("XDG_DATA_DIRS").ok(),
+        &home,
+    )
+    .into_iter()
+    .map(|dir| dir.join("applications"))
+    .collect()
+}
+
+// XDG_DATA_HOME first, then XDG_DATA_DIRS in order. Flatpak exports are
+// appended when the session did not add them itself.
+fn data_dirs(
+    data_home: Option<String>,
+    data_dirs: Option<String>,
+    home: &std::path::Path,
+) -> Vec<PathBuf> {
+    let data_home = data_home
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| home.join(".local/share"));
+    let data_dirs = data_dirs
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    let mut dirs = vec![data_home];
+    dirs.extend(
+        data_dirs
+            .split(':')
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from),
+    );
+    dirs.extend([
+        home.join(".local/share/flatpak/exports/share"),
+        PathBuf::from("/var/lib/flatpak/exports/share"),
+    ]);
+
+    let mut seen = std::collections::HashSet::new();
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn data_dirs_follow_xdg_precedence() {
+        let home = PathBuf::from("/home/user");
+        let dirs = data_dirs(
+            None,
+            Some("/var/lib/flatpak/exports/share:/usr/share".to_string()),
+            &home,
+        );
+
+        assert_eq!(
+            dirs,
+            vec![
+                PathBuf::from("/home/user/.local/share"),
+                PathBuf::from("/var/lib/flatpak/exports/share"),
+                PathBuf::from("/usr/share"),
+                PathBuf::from("/home/user/.local/share/flatpak/exports/share"),
+            ]
+        );
+    }
 }
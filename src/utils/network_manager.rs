@@ -0,0 +1,595 @@
+use gtk::{gio, glib, glib::variant::ObjectPath, prelude::*};
+use std::collections::HashMap;
+
+pub const SERVICE: &str = "org.freedesktop.NetworkManager";
+const PATH: &str = "/org/freedesktop/NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+const CONNECTION_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+const ACTIVE_INTERFACE: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const ACCESS_POINT_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+const WIRELESS_TYPE: &str = "802-11-wireless";
+// Profiles that exist for plumbing rather than to be switched by hand.
+const HIDDEN_TYPES: [&str; 3] = ["loopback", "bridge", "generic"];
+const NO_OBJECT: &str = "/";
+// The properties `state` depends on, per interface.
+const WATCHED: [(&str, &[&str]); 4] = [
+    (SERVICE, &["ActiveConnections", "PrimaryConnection"]),
+    (SETTINGS_INTERFACE, &["Connections"]),
+    (ACTIVE_INTERFACE, &["SpecificObject"]),
+    (ACCESS_POINT_INTERFACE, &["Ssid", "Strength"]),
+];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Wireless {
+    // The access point object.
+    pub path: String,
+    pub ssid: String,
+    pub strength: u8,
+}
+
+// A saved connection profile.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Connection {
+    pub path: String,
+    pub id: String,
+    pub uuid: String,
+    pub kind: String,
+    // The active connection object while connected.
+    pub active: Option<String>,
+}
+
+impl Connection {
+    pub fn is_wireless(&self) -> bool {
+        self.kind == WIRELESS_TYPE
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkState {
+    // Active profiles first.
+    pub connections: Vec<Connection>,
+    // Uuid of the connection holding the default route.
+    pub primary: Option<String>,
+    pub wireless: Option<Wireless>,
+}
+
+impl NetworkState {
+    pub fn primary(&self) -> Option<&Connection> {
+        let uuid = self.primary.as_ref()?;
+        self.connections.iter().find(|c| c.uuid == *uuid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    // The connections or the primary connection changed.
+    State,
+    // The access point at this path changed, which only matters while it is
+    // the one in use.
+    AccessPoint(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct NetworkManager {
+    connection: gio::DBusConnection,
+}
+
+impl NetworkManager {
+    pub async fn system() -> Result<Self, glib::Error> {
+        gio::bus_get_future(gio::BusType::System)
+            .await
+            .map(Self::new)
+    }
+
+    pub fn new(connection: gio::DBusConnection) -> Self {
+        Self { connection }
+    }
+
+    // Objects can disappear between listing and reading them, for instance
+    // while switching connections; those are skipped instead of failing the
+    // whole read.
+    pub async fn state(&self) -> Result<NetworkState, glib::Error> {
+        let mut active = HashMap::new();
+        let active_paths = self
+            .property::<Vec<ObjectPath>>(PATH, SERVICE, "ActiveConnections")
+            .await?;
+        for path in active_paths {
+            let uuid = self.property::<String>(path.as_str(), ACTIVE_INTERFACE, "Uuid");
+            if let Some(uuid) = skip_vanished(uuid.await)? {
+                active.insert(uuid, path.to_string());
+            }
+        }
+
+        let (paths,) = self
+            .call(SETTINGS_PATH, SETTINGS_INTERFACE, "ListConnections", None)
+            .await?
+            .get::<(Vec<ObjectPath>,)>()
+            .ok_or_else(|| invalid_data("ListConnections"))?;
+        let mut connections = Vec::new();
+        for path in paths {
+            let reply = self.call(path.as_str(), CONNECTION_INTERFACE, "GetSettings", None);
+            let Some(reply) = skip_vanished(reply.await)? else {
+                continue;
+            };
+            let Some((mut settings,)) =
+                reply.get::<(HashMap<String, HashMap<String, glib::Variant>>,)>()
+            else {
+                eprintln!(
+                    "Ignoring malformed connection settings at {}",
+                    path.as_str()
+                );
+                continue;
+            };
+            let Some(setting) = settings.remove("connection") else {
+                continue;
+            };
+            let text = |key: &str| {
+                setting
+                    .get(key)
+                    .and_then(|value| value.get::<String>())
+                    .unwrap_or_default()
+            };
+
+            let connection = Connection {
+                path: path.to_string(),
+                id: text("id"),
+                uuid: text("uuid"),
+                kind: text("type"),
+                active: None,
+            };
+            if HIDDEN_TYPES.contains(&connection.kind.as_str()) {
+                continue;
+            }
+            connections.push(Connection {
+                active: active.get(&connection.uuid).cloned(),
+                ..connection
+            });
+        }
+        connections.sort_by(|a, b| (a.active.is_none(), &a.id).cmp(&(b.active.is_none(), &b.id)));
+
+        let (primary, wireless) = self.primary().await?.unzip();
+        Ok(NetworkState {
+            connections,
+            primary,
+            wireless: wireless.flatten(),
+        })
+    }
+
+    // Tells whether NetworkManager itself went away, rather than one of its
+    // objects.
+    pub fn is_gone(&self, error: &glib::Error) -> bool {
+        self.connection.is_closed()
+            || error.matches(gio::DBusError::NameHasNoOwner)
+            || error.matches(gio::DBusError::ServiceUnknown)
+    }
+
+    // NetworkManager picks the device and access point itself when given "/".
+    pub async fn connect(&self, connection: &Connection) -> Result<(), glib::Error> {
+        let parameters = (
+            object_path(&connection.path)?,
+            object_path(NO_OBJECT)?,
+            object_path(NO_OBJECT)?,
+        )
+            .to_variant();
+        self.call(PATH, SERVICE, "ActivateConnection", Some(parameters))
+            .await
+            .map(|_| ())
+    }
+
+    pub async fn disconnect(&self, connection: &Connection) -> Result<(), glib::Error> {
+        let Some(active) = &connection.active else {
+            return Ok(());
+        };
+        let parameters = (object_path(active)?,).to_variant();
+        self.call(PATH, SERVICE, "DeactivateConnection", Some(parameters))
+            .await
+            .map(|_| ())
+    }
+
+    // Every NetworkManager object announces its changes through
+    // PropertiesChanged, so one subscription covers connections, devices and
+    // access points. Only changes to what `state` reads are passed on.
+    pub fn watch(&self, on_change: impl Fn(Change) + 'static) -> gio::SignalSubscriptionId {
+        self.connection.signal_subscribe(
+            Some(SERVICE),
+            Some(PROPERTIES_INTERFACE),
+            Some("PropertiesChanged"),
+            None,
+            None,
+            gio::DBusSignalFlags::NONE,
+            move |_, _, path, _, _, parameters| {
+                if let Some(change) = change(path, parameters) {
+                    on_change(change);
+                }
+            },
+        )
+    }
+
+    // The uuid of the connection holding the default route, along with its
+    // access point for Wi-Fi.
+    async fn primary(&self) -> Result<Option<(String, Option<Wireless>)>, glib::Error> {
+        let primary = self
+            .property::<ObjectPath>(PATH, SERVICE, "PrimaryConnection")
+            .await?;
+        if primary.as_str() == NO_OBJECT {
+            return Ok(None);
+        }
+        let uuid = self.property::<String>(primary.as_str(), ACTIVE_INTERFACE, "Uuid");
+        let Some(uuid) = skip_vanished(uuid.await)? else {
+            return Ok(None);
+        };
+        let kind = self.property::<String>(primary.as_str(), ACTIVE_INTERFACE, "Type");
+        let wireless = match skip_vanished(kind.await)?.as_deref() {
+            Some(WIRELESS_TYPE) => skip_vanished(self.wireless(primary.as_str()).await)?.flatten(),
+            _ => None,
+        };
+        Ok(Some((uuid, wireless)))
+    }
+
+    async fn wireless(&self, active: &str) -> Result<Option<Wireless>, glib::Error> {
+        let access_point = self
+            .property::<ObjectPath>(active, ACTIVE_INTERFACE, "SpecificObject")
+            .await?;
+        if access_point.as_str() == NO_OBJECT {
+            return Ok(None);
+        }
+        let ssid = self
+            .property::<Vec<u8>>(access_point.as_str(), ACCESS_POINT_INTERFACE, "Ssid")
+            .await?;
+        let strength = self
+            .property::<u8>(access_point.as_str(), ACCESS_POINT_INTERFACE, "Strength")
+            .await?;
+
+        Ok(Some(Wireless {
+            path: access_point.to_string(),
+            ssid: String::from_utf8_lossy(&ssid).into_owned(),
+            strength,
+        }))
+    }
+
+    async fn call(
+        &self,
+        path: &str,
+        interface: &str,
+        method: &str,
+        parameters: Option<glib::Variant>,
+    ) -> Result<glib::Variant, glib::Error> {
+        self.connection
+            .call_future(
+                Some(SERVICE),
+                path,
+                interface,
+                method,
+                parameters.as_ref(),
+                None,
+                gio::DBusCallFlags::NONE,
+                -1,
+            )
+            .await
+    }
+
+    async fn property<T: glib::variant::FromVariant>(
+        &self,
+        path: &str,
+        interface: &str,
+        name: &str,
+    ) -> Result<T, glib::Error> {
+        let reply = self
+            .call(
+                path,
+                PROPERTIES_INTERFACE,
+                "Get",
+                Some((interface, name).to_variant()),
+            )
+            .await?;
+        reply
+            .child_value(0)
+            .as_variant()
+            .and_then(|value| value.get::<T>())
+            .ok_or_else(|| invalid_data(name))
+    }
+}
+
+fn change(path: &str, parameters: &glib::Variant) -> Option<Change> {
+    let (interface, changed, invalidated) =
+        parameters.get::<(String, HashMap<String, glib::Variant>, Vec<String>)>()?;
+    let (_, watched) = WATCHED.iter().find(|(name, _)| *name == interface)?;
+    if !changed
+        .keys()
+        .chain(&invalidated)
+        .any(|name| watched.contains(&name.as_str()))
+    {
+        return None;
+    }
+    Some(match interface.as_str() {
+        ACCESS_POINT_INTERFACE => Change::AccessPoint(path.to_string()),
+        _ => Change::State,
+    })
+}
+
+// Reads of an object that was removed meanwhile give None.
+fn skip_vanished<T>(result: Result<T, glib::Error>) -> Result<Option<T>, glib::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e)
+            if e.matches(gio::DBusError::UnknownObject)
+                || e.matches(gio::DBusError::UnknownMethod)
+                || e.matches(gio::DBusError::UnknownInterface) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn object_path(path: &str) -> Result<ObjectPath, glib::Error> {
+    ObjectPath::try_from(path.to_string())
+        .map_err(|_| invalid_data(&format!("object path {}", path)))
+}
+
+fn invalid_data(what: &str) -> glib::Error {
+    glib::Error::new(
+        gio::IOErrorEnum::InvalidData,
+        &format!("Unexpected NetworkManager reply for {}", what),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        cell::RefCell,
+        io::{BufRead, BufReader},
+        process::{Command, Stdio},
+        rc::Rc,
+    };
+
+    const MOCK_XML: &str = r#"
+<node>
+  <interface name="org.freedesktop.NetworkManager">
+    <method name="ActivateConnection">
+      <arg type="o" direction="in"/><arg type="o" direction="in"/><arg type="o" direction="in"/>
+      <arg type="o" direction="out"/>
+    </method>
+    <method name="DeactivateConnection"><arg type="o" direction="in"/></method>
+    <property name="ActiveConnections" type="ao" access="read"/>
+    <property name="PrimaryConnection" type="o" access="read"/>
+  </interface>
+  <interface name="org.freedesktop.NetworkManager.Settings">
+    <method name="ListConnections"><arg type="ao" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.NetworkManager.Settings.Connection">
+    <method name="GetSettings"><arg type="a{sa{sv}}" direction="out"/></method>
+  </interface>
+  <interface name="org.freedesktop.NetworkManager.Connection.Active">
+    <property name="Uuid" type="s" access="read"/>
+    <property name="Type" type="s" access="read"/>
+    <property name="SpecificObject" type="o" access="read"/>
+  </interface>
+  <interface name="org.freedesktop.NetworkManager.AccessPoint">
+    <property name="Ssid" type="ay" access="read"/>
+    <property name="Strength" type="y" access="read"/>
+  </interface>
+</node>"#;
+
+    fn path(path: &str) -> ObjectPath {
+        ObjectPath::try_from(path.to_string()).unwrap()
+    }
+
+    fn settings(id: &str, uuid: &str, kind: &str) -> glib::Variant {
+        let connection = HashMap::from([
+            ("id".to_string(), id.to_variant()),
+            ("uuid".to_string(), uuid.to_variant()),
+            ("type".to_string(), kind.to_variant()),
+        ]);
+        (HashMap::from([("connection".to_string(), connection)]),).to_variant()
+    }
+
+    // A NetworkManager with a connected Wi-Fi profile, an idle wired one and
+    // the loopback profile. The second active connection and the fourth
+    // profile are listed but already gone. Method calls are recorded in
+    // `calls`.
+    fn export_mock(server: &gio::DBusConnection, calls: Rc<RefCell<Vec<String>>>) {
+        let node = gio::DBusNodeInfo::for_xml(MOCK_XML).unwrap();
+        let register = |object: &str, interface: &str| {
+            let calls = Rc::clone(&calls);
+            server
+                .register_object(object, &node.lookup_interface(interface).unwrap())
+                .method_call(move |_, _, object, _, method, parameters, invocation| {
+                    calls
+                        .borrow_mut()
+                        .push(format!("{} {}", method, parameters));
+                    let reply = match (object, method) {
+                        (_, "ActivateConnection") => Some(
+                            (path("/org/freedesktop/NetworkManager/ActiveConnection/2"),)
+                                .to_variant(),
+                        ),
+                        (_, "ListConnections") => Some(
+                            (vec![
+                                path("/org/freedesktop/NetworkManager/Settings/1"),
+                                path("/org/freedesktop/NetworkManager/Settings/2"),
+                                path("/org/freedesktop/NetworkManager/Settings/3"),
+                                path("/org/freedesktop/NetworkManager/Settings/4"),
+                            ],)
+                                .to_variant(),
+                        ),
+                        (object, "GetSettings") => Some(match object.rsplit('/').next() {
+                            Some("1") => settings("Office", "uuid-office", "802-3-ethernet"),
+                            Some("2") => settings("Home", "uuid-home", WIRELESS_TYPE),
+                            _ => settings("lo", "uuid-lo", "loopback"),
+                        }),
+                        _ => None,
+                    };
+                    invocation.return_value(reply.as_ref());
+                })
+                .property(|_, _, _, _, property| match property {
+                    "ActiveConnections" => vec![
+                        path("/org/freedesktop/NetworkManager/ActiveConnection/1"),
+                        path("/org/freedesktop/NetworkManager/ActiveConnection/2"),
+                    ]
+                    .to_variant(),
+                    "PrimaryConnection" => {
+                        path("/org/freedesktop/NetworkManager/ActiveConnection/1").to_variant()
+                    }
+                    "Uuid" => "uuid-home".to_variant(),
+                    "Type" => WIRELESS_TYPE.to_variant(),
+                    "SpecificObject" => {
+                        path("/org/freedesktop/NetworkManager/AccessPoint/7").to_variant()
+                    }
+                    "Ssid" => b"Home".to_vec().to_variant(),
+                    "Strength" => 72u8.to_variant(),
+                    _ => unreachable!(),
+                })
+                .build()
+                .unwrap();
+        };
+
+        register(PATH, SERVICE);
+        register(SETTINGS_PATH, SETTINGS_INTERFACE);
+        for object in 1..=3 {
+            register(
+                &format!("{}/{}", SETTINGS_PATH, object),
+                CONNECTION_INTERFACE,
+            );
+        }
+        register(
+            "/org/freedesktop/NetworkManager/ActiveConnection/1",
+            ACTIVE_INTERFACE,
+        );
+        register(
+            "/org/freedesktop/NetworkManager/AccessPoint/7",
+            ACCESS_POINT_INTERFACE,
+        );
+    }
+
+    #[test]
+    fn client_reads_and_switches_connections_on_a_mock_service() {
+        let Ok(mut daemon) = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("dbus-daemon not available, skipping");
+            return;
+        };
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let context = glib::MainContext::new();
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let state = context.with_thread_default(|| {
+            let flags = gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION;
+            let connect = || {
+                gio::DBusConnection::for_address_sync(
+                    address.trim(),
+                    flags,
+                    None,
+                    gio::Cancellable::NONE,
+                )
+                .unwrap()
+            };
+            let server = connect();
+            export_mock(&server, Rc::clone(&calls));
+            server
+                .call_sync(
+                    Some("org.freedesktop.DBus"),
+                    "/org/freedesktop/DBus",
+                    "org.freedesktop.DBus",
+                    "RequestName",
+                    Some(&(SERVICE, 0u32).to_variant()),
+                    None,
+                    gio::DBusCallFlags::NONE,
+                    -1,
+                    gio::Cancellable::NONE,
+                )
+                .unwrap();
+
+            let client = NetworkManager::new(connect());
+            context.block_on(async {
+                let state = client.state().await.unwrap();
+                let office = state.connections.iter().find(|c| c.id == "Office").unwrap();
+                client.connect(office).await.unwrap();
+                client.disconnect(state.primary().unwrap()).await.unwrap();
+                state
+            })
+        });
+        daemon.kill().ok();
+
+        let state = state.unwrap();
+        let ids = state
+            .connections
+            .iter()
+            .map(|c| c.id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["Home", "Office"]);
+        assert_eq!(
+            state.primary().unwrap().active.as_deref(),
+            Some("/org/freedesktop/NetworkManager/ActiveConnection/1")
+        );
+        assert_eq!(
+            state.wireless,
+            Some(Wireless {
+                path: "/org/freedesktop/NetworkManager/AccessPoint/7".to_string(),
+                ssid: "Home".to_string(),
+                strength: 72,
+            })
+        );
+
+        let calls = calls.borrow();
+        assert_eq!(
+            calls[calls.len() - 2..],
+            [
+                "ActivateConnection (objectpath '/org/freedesktop/NetworkManager/Settings/1', objectpath '/', objectpath '/')",
+                "DeactivateConnection (objectpath '/org/freedesktop/NetworkManager/ActiveConnection/1',)",
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_are_limited_to_shown_properties() {
+        let properties = |interface: &str, changed: &[&str], invalidated: &[&str]| {
+            let changed = changed
+                .iter()
+                .map(|name| (name.to_string(), 0u8.to_variant()))
+                .collect::<HashMap<_, _>>();
+            (interface, changed, invalidated.to_vec()).to_variant()
+        };
+        let access_point = "/org/freedesktop/NetworkManager/AccessPoint/7";
+
+        assert_eq!(
+            change(PATH, &properties(SERVICE, &["PrimaryConnection"], &[])),
+            Some(Change::State)
+        );
+        assert_eq!(
+            change(PATH, &properties(SERVICE, &["Connectivity"], &[])),
+            None
+        );
+        assert_eq!(
+            change(
+                access_point,
+                &properties(ACCESS_POINT_INTERFACE, &["LastSeen"], &["Strength"])
+            ),
+            Some(Change::AccessPoint(access_point.to_string()))
+        );
+        assert_eq!(
+            change(
+                access_point,
+                &properties(ACCESS_POINT_INTERFACE, &["LastSeen"], &[])
+            ),
+            None
+        );
+        assert_eq!(
+            change(
+                "/org/freedesktop/NetworkManager/Devices/2",
+                &properties("org.freedesktop.NetworkManager.Device", &["State"], &[])
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use tokio::sync::watch;
+
+pub const DEFAULT_ROOT: &str = "/proc";
+const KIB_PER_GIB: f64 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CpuTimes {
+    busy: u64,
+    idle: u64,
+}
+
+impl CpuTimes {
+    // "cpu0 user nice system idle iowait irq softirq steal guest guest_nice".
+    // Guest time is already part of user and nice.
+    fn parse(line: &str) -> Option<Self> {
+        let fields = line
+            .split_whitespace()
+            .skip(1)
+            .map(|field| field.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let field = |index: usize| fields.get(index).copied().unwrap_or(0);
+        if fields.len() < 4 {
+            return None;
+        }
+
+        Some(Self {
+            busy: field(0) + field(1) + field(2) + field(5) + field(6) + field(7),
+            idle: field(3) + field(4),
+        })
+    }
+
+    // Percentage of time spent busy since `previous`.
+    fn usage_since(&self, previous: &CpuTimes) -> f64 {
+        let busy = self.busy.saturating_sub(previous.busy) as f64;
+        let idle = self.idle.saturating_sub(previous.idle) as f64;
+        match busy + idle {
+            total if total > 0.0 => busy / total * 100.0,
+            _ => 0.0,
+        }
+    }
+}
+
+// The aggregate "cpu" line first, then one entry per online core by name.
+pub fn parse_stat(stat: &str) -> Vec<(String, CpuTimes)> {
+    stat.lines()
+        .take_while(|line| line.starts_with("cpu"))
+        .filter_map(|line| {
+            let name = line.split_whitespace().next()?;
+            Some((name.to_string(), CpuTimes::parse(line)?))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuUsage {
+    pub total: f64,
+    pub cores: Vec<f64>,
+}
+
+// Values in GiB.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MemInfo {
+    pub total: f64,
+    pub available: f64,
+    pub swap_total: f64,
+    pub swap_free: f64,
+}
+
+impl MemInfo {
+    pub fn parse(meminfo: &str) -> Option<Self> {
+        let field = |name: &str| {
+            meminfo.lines().find_map(|line| {
+                let value = line.strip_prefix(name)?.strip_prefix(':')?;
+                let kib = value.split_whitespace().next()?.parse::<f64>().ok()?;
+                Some(kib / KIB_PER_GIB)
+            })
+        };
+        let total = field("MemTotal")?;
+        // Kernels before 3.14 have no MemAvailable.
+        let available = field("MemAvailable").unwrap_or_else(|| {
+            field("MemFree").unwrap_or(0.0)
+                + field("Buffers").unwrap_or(0.0)
+                + field("Cached").unwrap_or(0.0)
+        });
+
+        Some(Self {
+            total,
+            available,
+            swap_total: field("SwapTotal").unwrap_or(0.0),
+            swap_free: field("SwapFree").unwrap_or(0.0),
+        })
+    }
+
+    pub fn used(&self) -> f64 {
+        (self.total - self.available).max(0.0)
+    }
+
+    pub fn percent(&self) -> f64 {
+        percent(self.used(), self.total)
+    }
+
+    pub fn swap_used(&self) -> f64 {
+        (self.swap_total - self.swap_free).max(0.0)
+    }
+
+    pub fn swap_percent(&self) -> f64 {
+        percent(self.swap_used(), self.swap_total)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadAvg {
+    pub one: f64,
+    pub five: f64,
+    pub fifteen: f64,
+    pub running: u32,
+    pub processes: u32,
+}
+
+impl LoadAvg {
+    // "0.52 0.58 0.59 2/1024 12345"
+    pub fn parse(loadavg: &str) -> Option<Self> {
+        let mut fields = loadavg.split_whitespace();
+        let mut load = || fields.next()?.parse::<f64>().ok();
+        let (one, five, fifteen) = (load()?, load()?, load()?);
+        let (running, processes) = fields
+            .next()
+            .and_then(|tasks| tasks.split_once('/'))
+            .and_then(|(running, total)| Some((running.parse().ok()?, total.parse().ok()?)))
+            .unwrap_or_default();
+
+        Some(Self {
+            one,
+            five,
+            fifteen,
+            running,
+            processes,
+        })
+    }
+}
+
+// Received and transmitted bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetCounters {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+// "  eth0: 1234 12 0 0 0 0 0 0 5678 34 ..." where received bytes come first
+// and transmitted bytes are the ninth field.
+pub fn parse_net_dev(net_dev: &str) -> HashMap<String, NetCounters> {
+    net_dev
+        .lines()
+        .filter_map(|line| {
+            let (name, fields) = line.split_once(':')?;
+            let fields = fields.split_whitespace().collect::<Vec<_>>();
+            Some((
+                name.trim().to_string(),
+                NetCounters {
+                    rx: fields.first()?.parse().ok()?,
+                    tx: fields.get(8)?.parse().ok()?,
+                },
+            ))
+        })
+        .collect()
+}
+
+// In bytes per second.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Throughput {
+    pub rx: f64,
+    pub tx: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sample {
+    pub cpu: CpuUsage,
+    pub memory: Option<MemInfo>,
+    pub load: Option<LoadAvg>,
+    // Per interface, empty until there are two samples to compare.
+    pub network: HashMap<String, Throughput>,
+}
+
+// Keeps the previous CPU counters so usage covers the time between samples.
+// The first sample covers the time since boot.
+#[derive(Debug, Clone)]
+pub struct ProcReader {
+    root: PathBuf,
+    // By name, cores going offline leave gaps in the numbering.
+    previous: HashMap<String, CpuTimes>,
+    previous_net: HashMap<String, NetCounters>,
+    last_read: Option<Instant>,
+}
+
+impl ProcReader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            previous: HashMap::new(),
+            previous_net: HashMap::new(),
+            last_read: None,
+        }
+    }
+
+    pub fn sample(&mut self) -> Sample {
+        let read = |file: &str| fs::read_to_string(self.root.join(file)).ok();
+
+        let times = read("stat")
+            .map(|stat| parse_stat(&stat))
+            .unwrap_or_default();
+        let mut usage = times.iter().map(|(name, times)| {
+            let previous = self.previous.get(name).copied().unwrap_or_default();
+            times.usage_since(&previous)
+        });
+        let cpu = CpuUsage {
+            total: usage.next().unwrap_or(0.0),
+            cores: usage.collect(),
+        };
+        self.previous = times.into_iter().collect();
+
+        let counters = read("net/dev")
+            .map(|net_dev| parse_net_dev(&net_dev))
+            .unwrap_or_default();
+        let now = Instant::now();
+        let elapsed = self
+            .last_read
+            .map(|last| now.duration_since(last).as_secs_f64());
+        let network = elapsed
+            .map(|elapsed| throughput(&self.previous_net, &counters, elapsed))
+            .unwrap_or_default();
+        self.previous_net = counters;
+        self.last_read = Some(now);
+
+        Sample {
+            cpu,
+            memory: read("meminfo").and_then(|meminfo| MemInfo::parse(&meminfo)),
+            load: read("loadavg").and_then(|loadavg| LoadAvg::parse(&loadavg)),
+            network,
+        }
+    }
+}
+
+// Interfaces that appeared since `previous` have no rate yet.
+fn throughput(
+    previous: &HashMap<String, NetCounters>,
+    current: &HashMap<String, NetCounters>,
+    elapsed: f64,
+) -> HashMap<String, Throughput> {
+    if elapsed <= 0.0 {
+        return HashMap::new();
+    }
+    current
+        .iter()
+        .filter_map(|(name, counters)| {
+            let previous = previous.get(name)?;
+            Some((
+                name.clone(),
+                Throughput {
+                    rx: counters.rx.saturating_sub(previous.rx) as f64 / elapsed,
+                    tx: counters.tx.saturating_sub(previous.tx) as f64 / elapsed,
+                },
+            ))
+        })
+        .collect()
+}
+
+// One task samples `root` for every widget subscribed to it. The returned
+// receiver is meant to be kept and cloned for each widget, the task stops
+// once it is the only one left.
+pub fn spawn_sampler(root: &Path, interval: Duration) -> watch::Receiver<Sample> {
+    let mut reader = ProcReader::new(root);
+    let (sender, receiver) = watch::channel(reader.sample());
+
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            if sender.receiver_count() <= 1 || sender.send(reader.sample()).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+fn percent(part: f64, total: f64) -> f64 {
+    match total {
+        total if total > 0.0 => part / total * 100.0,
+        _ => 0.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEMINFO: &str = "MemTotal:       16384000 kB\nMemFree:         1024000 kB\nMemAvailable:    4096000 kB\nSwapTotal:       2048000 kB\nSwapFree:        1536000 kB\n";
+
+    #[test]
+    fn sample_reports_usage_between_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let stat = |lines: &str| fs::write(root.join("stat"), lines).unwrap();
+        fs::write(root.join("meminfo"), MEMINFO).unwrap();
+        fs::write(root.join("loadavg"), "1.50 0.75 0.25 3/512 4242\n").unwrap();
+
+        stat(
+            "cpu  100 0 100 800 0 0 0 0 0 0\ncpu0 50 0 50 400 0 0 0 0 0 0\ncpu1 50 0 50 400 0 0 0 0 0 0\nintr 1 2 3\n",
+        );
+        let mut reader = ProcReader::new(root);
+        assert_eq!(reader.sample().cpu.total, 20.0);
+
+        // cpu0 was fully busy, cpu1 idle waiting on IO.
+        stat(
+            "cpu  200 0 200 850 50 0 0 0 0 0\ncpu0 150 0 150 400 0 0 0 0 0 0\ncpu1 50 0 50 450 50 0 0 0 0 0\n",
+        );
+        let sample = reader.sample();
+        assert_eq!(sample.cpu.total, 200.0 / 300.0 * 100.0);
+        assert_eq!(sample.cpu.cores, vec![100.0, 0.0]);
+
+        // cpu1 went offline, cpu2 keeps its own counters.
+        stat(
+            "cpu  300 0 300 900 50 0 0 0 0 0\ncpu0 200 0 200 400 0 0 0 0 0 0\ncpu2 10 0 10 80 0 0 0 0 0 0\n",
+        );
+        reader.sample();
+        stat(
+            "cpu  400 0 400 950 50 0 0 0 0 0\ncpu0 200 0 200 500 0 0 0 0 0 0\ncpu2 60 0 60 80 0 0 0 0 0 0\n",
+        );
+        assert_eq!(reader.sample().cpu.cores, vec![0.0, 100.0]);
+
+        let memory = sample.memory.unwrap();
+        assert_eq!(memory.percent(), 75.0);
+        assert_eq!(memory.swap_percent(), 25.0);
+        assert_eq!(
+            sample.load,
+            Some(LoadAvg {
+                one: 1.5,
+                five: 0.75,
+                fifteen: 0.25,
+                running: 3,
+                processes: 512,
+            })
+        );
+    }
+
+    #[test]
+    fn meminfo_falls_back_without_mem_available() {
+        let memory = MemInfo::parse(
+            "MemTotal: 1048576 kB\nMemFree: 262144 kB\nBuffers: 0 kB\nCached: 262144 kB\n",
+        )
+        .unwrap();
+        assert_eq!(memory.total, 1.0);
+        assert_eq!(memory.available, 0.5);
+        assert_eq!(memory.swap_total, 0.0);
+
+        assert_eq!(MemInfo::parse("garbage"), None);
+        assert_eq!(LoadAvg::parse(""), None);
+    }
+
+    #[test]
+    fn net_dev_rates_cover_the_elapsed_time() {
+        let header = "Inter-|   Receive                                                |  Transmit\n face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed\n";
+        let previous = parse_net_dev(&format!(
+            "{}    lo: 100 1 0 0 0 0 0 0 100 1 0 0 0 0 0 0\n  eth0: 1000 10 0 0 0 0 0 0 500 5 0 0 0 0 0 0\n",
+            header
+        ));
+        assert_eq!(previous["eth0"], NetCounters { rx: 1000, tx: 500 });
+
+        let current = parse_net_dev(
+            "  eth0: 5000 40 0 0 0 0 0 0 1500 9 0 0 0 0 0 0\n wlan0: 1 1 0 0 0 0 0 0 1 1 0 0 0 0 0 0\n",
+        );
+        let rates = throughput(&previous, &current, 2.0);
+        assert_eq!(rates.len(), 1);
+        assert_eq!(
+            rates["eth0"],
+            Throughput {
+                rx: 2000.0,
+                tx: 500.0
+            }
+        );
+    }
+}
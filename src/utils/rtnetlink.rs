@@ -0,0 +1,463 @@
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+// From linux/netlink.h, linux/if_link.h, linux/if_addr.h and linux/rtnetlink.h.
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const IFLA_IFNAME: u16 = 3;
+const IFLA_OPERSTATE: u16 = 16;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const RTA_OIF: u16 = 4;
+const RTA_GATEWAY: u16 = 5;
+const RTA_PRIORITY: u16 = 6;
+const RTA_TABLE: u16 = 15;
+const RTN_UNICAST: u8 = 1;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const IF_OPER_UNKNOWN: u8 = 0;
+const IF_OPER_UP: u8 = 6;
+
+const HEADER_LEN: usize = 16;
+const ATTRIBUTE_HEADER_LEN: usize = 4;
+// Sizes of ifinfomsg, ifaddrmsg and rtmsg.
+const LINK_LEN: usize = 16;
+const ADDRESS_LEN: usize = 8;
+const ROUTE_LEN: usize = 12;
+const BUFFER_SIZE: usize = 32768;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Link {
+    pub index: u32,
+    pub name: String,
+    pub up: bool,
+    pub loopback: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub index: u32,
+    pub address: IpAddr,
+    pub prefix: u8,
+    // Not link or host local.
+    pub global: bool,
+}
+
+// Only default routes of the main table are kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub index: u32,
+    pub gateway: Option<IpAddr>,
+    pub metric: u32,
+    pub ipv6: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub links: Vec<Link>,
+    pub addresses: Vec<Address>,
+    pub routes: Vec<Route>,
+}
+
+impl Snapshot {
+    pub fn read() -> io::Result<Self> {
+        let socket = open_socket(0)?;
+        let mut snapshot = Self::default();
+        let requests = [
+            (libc::RTM_GETLINK, LINK_LEN),
+            (libc::RTM_GETADDR, ADDRESS_LEN),
+            (libc::RTM_GETROUTE, ROUTE_LEN),
+        ];
+        for (sequence, (kind, len)) in requests.into_iter().enumerate() {
+            dump(&socket, kind, len, sequence as u32 + 1, &mut snapshot)?;
+        }
+        Ok(snapshot)
+    }
+
+    fn add(&mut self, kind: u16, payload: &[u8]) {
+        match kind {
+            libc::RTM_NEWLINK => self.links.extend(parse_link(payload)),
+            libc::RTM_NEWADDR => self.addresses.extend(parse_address(payload)),
+            libc::RTM_NEWROUTE => self.routes.extend(parse_route(payload)),
+            _ => {}
+        }
+    }
+
+    // IPv4 first since most traffic still uses it, then the lowest metric.
+    pub fn default_route(&self) -> Option<&Route> {
+        self.routes
+            .iter()
+            .filter(|route| self.link(route.index).is_some_and(|link| link.up))
+            .min_by_key(|route| (route.ipv6, route.metric))
+    }
+
+    pub fn link(&self, index: u32) -> Option<&Link> {
+        self.links.iter().find(|link| link.index == index)
+    }
+
+    // Global IPv4 addresses first.
+    pub fn address(&self, index: u32) -> Option<&Address> {
+        self.addresses
+            .iter()
+            .filter(|address| address.index == index)
+            .min_by_key(|address| (!address.global, address.address.is_ipv6()))
+    }
+}
+
+// Notifies on every link, address and route change until the receiver is
+// dropped. Like uevents, the socket is read on its own thread.
+pub fn watch() -> io::Result<async_channel::Receiver<()>> {
+    let groups = libc::RTMGRP_LINK
+        | libc::RTMGRP_IPV4_IFADDR
+        | libc::RTMGRP_IPV6_IFADDR
+        | libc::RTMGRP_IPV4_ROUTE
+        | libc::RTMGRP_IPV6_ROUTE;
+    let socket = open_socket(groups as u32)?;
+    let (sender, receiver) = async_channel::unbounded();
+
+    std::thread::spawn(move || {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        loop {
+            if let Err(e) = receive(&socket, &mut buffer) {
+                eprintln!("Failed to read network changes: {}", e);
+                return;
+            }
+            if sender.send_blocking(()).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+fn dump(
+    socket: &OwnedFd,
+    kind: u16,
+    len: usize,
+    sequence: u32,
+    snapshot: &mut Snapshot,
+) -> io::Result<()> {
+    // An all zero body asks for every address family.
+    let mut request = Vec::with_capacity(HEADER_LEN + len);
+    request.extend(((HEADER_LEN + len) as u32).to_ne_bytes());
+    request.extend(kind.to_ne_bytes());
+    request.extend(((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    request.extend(sequence.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes());
+    request.resize(HEADER_LEN + len, 0);
+
+    let sent = unsafe {
+        libc::send(
+            socket.as_raw_fd(),
+            request.as_ptr().cast(),
+            request.len(),
+            0,
+        )
+    };
+    if sent < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let read = receive(socket, &mut buffer)?;
+        for (kind, payload) in messages(&buffer[..read]) {
+            match kind {
+                NLMSG_DONE => return Ok(()),
+                NLMSG_ERROR => {
+                    let code = payload.get(..4).map_or(0, |code| {
+                        i32::from_ne_bytes(code.try_into().unwrap_or_default())
+                    });
+                    if code != 0 {
+                        return Err(io::Error::from_raw_os_error(-code));
+                    }
+                }
+                _ => snapshot.add(kind, payload),
+            }
+        }
+    }
+}
+
+fn receive(socket: &OwnedFd, buffer: &mut [u8]) -> io::Result<usize> {
+    loop {
+        let read = unsafe {
+            libc::recv(
+                socket.as_raw_fd(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                0,
+            )
+        };
+        if read >= 0 {
+            return Ok(read as usize);
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+}
+
+fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn messages(buffer: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while offset + HEADER_LEN <= buffer.len() {
+        let header = &buffer[offset..];
+        let len = u32::from_ne_bytes([header[0], header[1], header[2], header[3]]) as usize;
+        if len < HEADER_LEN || offset + len > buffer.len() {
+            break;
+        }
+        let kind = u16::from_ne_bytes([header[4], header[5]]);
+        messages.push((kind, &buffer[offset + HEADER_LEN..offset + len]));
+        offset += align(len);
+    }
+    messages
+}
+
+fn attributes(data: &[u8]) -> Vec<(u16, &[u8])> {
+    let mut attributes = Vec::new();
+    let mut offset = 0;
+    while offset + ATTRIBUTE_HEADER_LEN <= data.len() {
+        let header = &data[offset..];
+        let len = u16::from_ne_bytes([header[0], header[1]]) as usize;
+        if len < ATTRIBUTE_HEADER_LEN || offset + len > data.len() {
+            break;
+        }
+        let kind = u16::from_ne_bytes([header[2], header[3]]);
+        attributes.push((kind, &data[offset + ATTRIBUTE_HEADER_LEN..offset + len]));
+        offset += align(len);
+    }
+    attributes
+}
+
+fn read_u32(data: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+fn ip_address(data: &[u8]) -> Option<IpAddr> {
+    match data.len() {
+        4 => Some(Ipv4Addr::from(<[u8; 4]>::try_from(data).ok()?).into()),
+        16 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(data).ok()?).into()),
+        _ => None,
+    }
+}
+
+fn parse_link(payload: &[u8]) -> Option<Link> {
+    let index = read_u32(payload.get(4..)?)?;
+    let flags = read_u32(payload.get(8..)?)?;
+    let mut link = Link {
+        index,
+        loopback: flags & libc::IFF_LOOPBACK as u32 != 0,
+        ..Default::default()
+    };
+
+    let mut operstate = IF_OPER_UNKNOWN;
+    for (kind, data) in attributes(payload.get(LINK_LEN..)?) {
+        match kind {
+            IFLA_IFNAME => {
+                link.name = String::from_utf8_lossy(data)
+                    .trim_end_matches('\0')
+                    .to_string()
+            }
+            IFLA_OPERSTATE => operstate = data.first().copied().unwrap_or(IF_OPER_UNKNOWN),
+            _ => {}
+        }
+    }
+    // Tunnels and some virtual devices never report an operstate.
+    link.up = operstate == IF_OPER_UP
+        || (operstate == IF_OPER_UNKNOWN && flags & libc::IFF_RUNNING as u32 != 0);
+    Some(link)
+}
+
+fn parse_address(payload: &[u8]) -> Option<Address> {
+    let prefix = *payload.get(1)?;
+    let scope = *payload.get(3)?;
+    let index = read_u32(payload.get(4..)?)?;
+
+    // On point to point links IFA_ADDRESS is the peer and IFA_LOCAL our own.
+    let attributes = attributes(payload.get(ADDRESS_LEN..)?);
+    let address = [IFA_LOCAL, IFA_ADDRESS].iter().find_map(|wanted| {
+        attributes
+            .iter()
+            .find(|(kind, _)| kind == wanted)
+            .and_then(|(_, data)| ip_address(data))
+    })?;
+
+    Some(Address {
+        index,
+        address,
+        prefix,
+        global: scope == RT_SCOPE_UNIVERSE,
+    })
+}
+
+fn parse_route(payload: &[u8]) -> Option<Route> {
+    let family = *payload.first()?;
+    let destination_len = *payload.get(1)?;
+    let mut table = *payload.get(4)? as u32;
+    let kind = *payload.get(7)?;
+    if destination_len != 0 || kind != RTN_UNICAST {
+        return None;
+    }
+
+    let (mut index, mut gateway, mut metric) = (None, None, 0);
+    for (kind, data) in attributes(payload.get(ROUTE_LEN..)?) {
+        match kind {
+            RTA_OIF => index = read_u32(data),
+            RTA_GATEWAY => gateway = ip_address(data),
+            RTA_PRIORITY => metric = read_u32(data).unwrap_or(0),
+            RTA_TABLE => table = read_u32(data).unwrap_or(table),
+            _ => {}
+        }
+    }
+    if table != libc::RT_TABLE_MAIN as u32 {
+        return None;
+    }
+
+    Some(Route {
+        index: index?,
+        gateway,
+        metric,
+        ipv6: family == libc::AF_INET6 as u8,
+    })
+}
+
+fn open_socket(groups: u32) -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+            libc::NETLINK_ROUTE,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups = groups;
+    let bound = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&address as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if bound < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(kind: u16, body: &[u8], attributes: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut payload = body.to_vec();
+        for (kind, data) in attributes {
+            payload.extend(((ATTRIBUTE_HEADER_LEN + data.len()) as u16).to_ne_bytes());
+            payload.extend(kind.to_ne_bytes());
+            payload.extend(*data);
+            payload.resize(align(payload.len()), 0);
+        }
+
+        let mut message = ((HEADER_LEN + payload.len()) as u32).to_ne_bytes().to_vec();
+        message.extend(kind.to_ne_bytes());
+        message.resize(HEADER_LEN, 0);
+        message.extend(payload);
+        message
+    }
+
+    fn link(index: u32, name: &str, flags: u32, operstate: u8) -> Vec<u8> {
+        let mut body = vec![0u8; LINK_LEN];
+        body[4..8].copy_from_slice(&index.to_ne_bytes());
+        body[8..12].copy_from_slice(&flags.to_ne_bytes());
+        let name = format!("{}\0", name);
+        message(
+            libc::RTM_NEWLINK,
+            &body,
+            &[
+                (IFLA_IFNAME, name.as_bytes()),
+                (IFLA_OPERSTATE, &[operstate]),
+            ],
+        )
+    }
+
+    fn route(family: u8, index: u32, gateway: &[u8], metric: u32) -> Vec<u8> {
+        let body = [
+            family,
+            0,
+            0,
+            0,
+            libc::RT_TABLE_MAIN,
+            0,
+            0,
+            RTN_UNICAST,
+            0,
+            0,
+            0,
+            0,
+        ];
+        message(
+            libc::RTM_NEWROUTE,
+            &body,
+            &[
+                (RTA_OIF, &index.to_ne_bytes()),
+                (RTA_GATEWAY, gateway),
+                (RTA_PRIORITY, &metric.to_ne_bytes()),
+            ],
+        )
+    }
+
+    #[test]
+    fn snapshot_finds_the_default_interface() {
+        let mut buffer = link(1, "lo", libc::IFF_LOOPBACK as u32, IF_OPER_UNKNOWN);
+        buffer.extend(link(2, "eth0", 0, 2));
+        buffer.extend(link(3, "wlan0", 0, IF_OPER_UP));
+        buffer.extend(message(
+            libc::RTM_NEWADDR,
+            &[libc::AF_INET6 as u8, 64, 0, 253, 3, 0, 0, 0],
+            &[(
+                IFA_ADDRESS,
+                &[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            )],
+        ));
+        buffer.extend(message(
+            libc::RTM_NEWADDR,
+            &[libc::AF_INET as u8, 24, 0, 0, 3, 0, 0, 0],
+            &[(IFA_LOCAL, &[192, 168, 1, 20])],
+        ));
+        // eth0 has the better metric but its link is down.
+        buffer.extend(route(libc::AF_INET as u8, 2, &[10, 0, 0, 1], 100));
+        buffer.extend(route(libc::AF_INET as u8, 3, &[192, 168, 1, 1], 600));
+        buffer.extend(route(
+            libc::AF_INET6 as u8,
+            3,
+            &[0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            50,
+        ));
+
+        let mut snapshot = Snapshot::default();
+        for (kind, payload) in messages(&buffer) {
+            snapshot.add(kind, payload);
+        }
+
+        assert_eq!(snapshot.links.len(), 3);
+        assert!(snapshot.links[0].loopback && !snapshot.links[1].up);
+        let route = snapshot.default_route().unwrap();
+        assert_eq!(snapshot.link(route.index).unwrap().name, "wlan0");
+        assert_eq!(route.gateway, Some(IpAddr::from([192, 168, 1, 1])));
+        let address = snapshot.address(3).unwrap();
+        assert_eq!(address.address, IpAddr::from([192, 168, 1, 20]));
+        assert_eq!(address.prefix, 24);
+    }
+}
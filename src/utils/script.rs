@@ -0,0 +1,307 @@
+use serde::Deserialize;
+use std::{fmt, os::unix::process::CommandExt as _, process::Stdio, time::Duration};
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    process::Command,
+};
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+// A process that stayed up this long is considered healthy again.
+const STABLE_UPTIME: Duration = Duration::from_secs(30);
+
+pub type ScriptResult = Result<ScriptOutput, ScriptError>;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScriptOutput {
+    pub text: String,
+    pub tooltip: Option<String>,
+    pub class: Vec<String>,
+    pub percentage: Option<f64>,
+    pub alt: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Classes {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonOutput {
+    #[serde(default)]
+    text: String,
+    tooltip: Option<String>,
+    class: Option<Classes>,
+    percentage: Option<f64>,
+    alt: Option<String>,
+}
+
+impl ScriptOutput {
+    // JSON objects use Waybar's keys; anything else is plain text where the
+    // first line is the text, the second the tooltip and the third a class.
+    pub fn parse(stdout: &str) -> Self {
+        let stdout = stdout.trim_end_matches(['\n', '\r']);
+        if stdout.trim_start().starts_with('{')
+            && let Ok(json) = serde_json::from_str::<JsonOutput>(stdout)
+        {
+            return Self {
+                text: json.text,
+                tooltip: json.tooltip.filter(|tooltip| !tooltip.is_empty()),
+                class: match json.class {
+                    Some(Classes::One(class)) => {
+                        class.split_whitespace().map(String::from).collect()
+                    }
+                    Some(Classes::Many(classes)) => classes,
+                    None => Vec::new(),
+                },
+                percentage: json.percentage,
+                alt: json.alt.filter(|alt| !alt.is_empty()),
+            };
+        }
+
+        let mut lines = stdout.lines();
+        Self {
+            text: lines.next().unwrap_or_default().to_string(),
+            tooltip: lines
+                .next()
+                .filter(|tooltip| !tooltip.is_empty())
+                .map(String::from),
+            class: lines
+                .next()
+                .map(|class| class.split_whitespace().map(String::from).collect())
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ScriptError {
+    Spawn(std::io::Error),
+    Timeout(Duration),
+    Failed(Option<i32>, String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScriptError::Spawn(e) => write!(f, "Failed to start script: {}", e),
+            ScriptError::Timeout(timeout) => {
+                write!(f, "Script timed out after {}s", timeout.as_secs_f32())
+            }
+            ScriptError::Failed(Some(code), stderr) if stderr.is_empty() => {
+                write!(f, "Script exited with status {}", code)
+            }
+            ScriptError::Failed(None, stderr) if stderr.is_empty() => {
+                write!(f, "Script was killed")
+            }
+            ScriptError::Failed(_, stderr) => write!(f, "{}", stderr),
+        }
+    }
+}
+
+impl std::error::Error for ScriptError {}
+
+pub async fn run(command: &str, timeout: Duration) -> ScriptResult {
+    let child = shell(command)
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(ScriptError::Spawn)?;
+    let group = ProcessGroup(child.id());
+
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(ScriptError::Spawn)?,
+        Err(_) => return Err(ScriptError::Timeout(timeout)),
+    };
+    group.release();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(ScriptError::Failed(output.status.code(), stderr));
+    }
+    Ok(ScriptOutput::parse(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+// Sends every stdout line as an output until the process exits, returning
+// why it stopped, or None once nobody is listening anymore.
+pub async fn stream(
+    command: &str,
+    sender: &async_channel::Sender<ScriptResult>,
+) -> Option<ScriptError> {
+    let mut child = match shell(command).stderr(Stdio::inherit()).spawn() {
+        Ok(child) => child,
+        Err(e) => return Some(ScriptError::Spawn(e)),
+    };
+    let _group = ProcessGroup(child.id());
+
+    let Some(stdout) = child.stdout.take() else {
+        return Some(ScriptError::Spawn(std::io::Error::other(
+            "stdout is not piped",
+        )));
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        match lines.next_line().await {
+            Ok(Some(line)) => {
+                if sender.send(Ok(ScriptOutput::parse(&line))).await.is_err() {
+                    return None;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => return Some(ScriptError::Spawn(e)),
+        }
+    }
+
+    match child.wait().await {
+        Ok(status) => Some(ScriptError::Failed(status.code(), String::new())),
+        Err(e) => Some(ScriptError::Spawn(e)),
+    }
+}
+
+fn shell(command: &str) -> Command {
+    let mut shell = std::process::Command::new("sh");
+    shell
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .process_group(0);
+
+    let mut shell = Command::from(shell);
+    shell.kill_on_drop(true);
+    shell
+}
+
+// kill_on_drop only reaches the shell, not the pipeline it started, so the
+// whole group goes when a script is abandoned.
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    fn release(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        if let Some(pid) = self.0 {
+            unsafe { libc::kill(-(pid as i32), libc::SIGKILL) };
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    delay: Duration,
+}
+
+impl Backoff {
+    pub fn next(&mut self, uptime: Duration) -> Duration {
+        if uptime >= STABLE_UPTIME {
+            self.delay = MIN_BACKOFF;
+        }
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_BACKOFF);
+        delay
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { delay: MIN_BACKOFF }
+    }
+}
+
+// Click and scroll handlers run detached through the shell, like Waybar's.
+pub fn spawn(command: &str) {
+    let result = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .process_group(0)
+        .spawn();
+
+    match result {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => eprintln!("Failed to run {}: {}", command, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_plain_text_lines() {
+        let output = ScriptOutput::parse("VPN on\nConnected to home\nconnected warning\n");
+        assert_eq!(output.text, "VPN on");
+        assert_eq!(output.tooltip.as_deref(), Some("Connected to home"));
+        assert_eq!(output.class, vec!["connected", "warning"]);
+
+        assert_eq!(ScriptOutput::parse("").text, "");
+    }
+
+    #[test]
+    fn parse_reads_json_objects() {
+        let output = ScriptOutput::parse(
+            r#"{"text": "42%", "tooltip": "", "class": ["high", "cpu"], "percentage": 42, "alt": "busy"}"#,
+        );
+        assert_eq!(output.text, "42%");
+        assert_eq!(output.tooltip, None);
+        assert_eq!(output.class, vec!["high", "cpu"]);
+        assert_eq!(output.percentage, Some(42.0));
+        assert_eq!(output.alt.as_deref(), Some("busy"));
+
+        let output = ScriptOutput::parse(r#"{"text": "on", "class": "active"}"#);
+        assert_eq!(output.class, vec!["active"]);
+
+        let output = ScriptOutput::parse("{not json");
+        assert_eq!(output.text, "{not json");
+    }
+
+    #[tokio::test]
+    async fn run_reports_timeouts_and_failures() {
+        let output = run("echo hello", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(output.text, "hello");
+
+        let error = run("sleep 5", Duration::from_millis(100)).await;
+        assert!(matches!(error, Err(ScriptError::Timeout(_))));
+
+        let error = run("echo broken >&2; exit 3", Duration::from_secs(5)).await;
+        assert!(matches!(&error, Err(ScriptError::Failed(Some(3), stderr)) if stderr == "broken"));
+    }
+
+    #[tokio::test]
+    async fn stream_sends_every_line_until_exit() {
+        let (sender, receiver) = async_channel::unbounded();
+        let error = stream(
+            r#"echo off; echo '{"text": "on", "class": "active"}'"#,
+            &sender,
+        )
+        .await;
+        assert!(matches!(error, Some(ScriptError::Failed(Some(0), _))));
+
+        let texts = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|output| output.unwrap().text)
+            .collect::<Vec<_>>();
+        assert_eq!(texts, vec!["off", "on"]);
+    }
+
+    #[test]
+    fn backoff_doubles_until_the_process_stays_up() {
+        let mut backoff = Backoff::default();
+        let delays = (0..8)
+            .map(|_| backoff.next(Duration::ZERO).as_secs())
+            .collect::<Vec<_>>();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+        assert_eq!(backoff.next(STABLE_UPTIME), MIN_BACKOFF);
+    }
+}
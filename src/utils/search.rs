@@ -1,172 +1,516 @@
-use crate::utils::desktop_dirs;
-use std::fs;
+use crate::utils::{
+    application_dirs,
+    desktop_entry::{DesktopEntry, current_locale},
+};
+use parking_lot::RwLock;
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::CString,
+    fs,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 
-#[derive(Debug)]
-pub struct DesktopFile {
-    #[allow(dead_code)]
-    pub name: String,
+const REFRESH_DEBOUNCE_MS: u64 = 300;
 
-    pub exec: String,
-    pub icon: Option<String>,
+#[derive(Debug, Default)]
+struct Index {
+    entries: Vec<DesktopEntry>,
+    by_id: HashMap<String, usize>,
+    by_wm_class: HashMap<String, usize>,
+    by_flatpak_id: HashMap<String, usize>,
+    by_reverse_dns: HashMap<String, usize>,
+    by_executable: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
 }
 
-pub fn search_desktop_file(file_name: &str) -> Option<DesktopFile> {
-    let has_find = command_exists("find");
-    let has_grep = command_exists("grep");
+impl Index {
+    fn build(dirs: &[PathBuf], locale: Option<&str>) -> Self {
+        let mut index = Self::default();
+        let mut seen = HashSet::new();
 
-    if !has_find || !has_grep {
-        return search_file(file_name);
+        for dir in dirs {
+            for path in desktop_files(dir) {
+                let Some(id) = desktop_id(dir, &path) else {
+                    continue;
+                };
+                // The first directory providing an id wins, even when that
+                // entry is hidden or unparsable: it masks the lower ones.
+                if !seen.insert(id.clone()) {
+                    continue;
+                }
+
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                if let Some(entry) = DesktopEntry::parse(&id, &path, &content, locale)
+                    && !entry.hidden
+                {
+                    index.insert(entry);
+                }
+            }
+        }
+
+        index
     }
 
-    let mut desktop_info = None;
+    fn insert(&mut self, entry: DesktopEntry) {
+        let position = self.entries.len();
+        // Empty keys are left out, they would match any blank query.
+        let add = |map: &mut HashMap<String, usize>, value: &str| {
+            let key = value.trim().to_lowercase();
+            if !key.is_empty() {
+                map.entry(key).or_insert(position);
+            }
+        };
 
-    let path = std::process::Command::new("find")
-        .args(desktop_dirs())
-        .args([
-            "-name",
-            "*.desktop",
-            "-exec",
-            "grep",
-            "-il",
-            "--",
-            file_name,
-            "{}",
-            "+",
-        ])
-        .output()
-        .expect("Failed to execute command");
+        add(&mut self.by_id, &entry.id);
+        if let Some(class) = &entry.startup_wm_class {
+            add(&mut self.by_wm_class, class);
+        }
+        if let Some(flatpak_id) = &entry.flatpak_id {
+            add(&mut self.by_flatpak_id, flatpak_id);
+        }
+        if let Some(name) = entry.reverse_dns_name() {
+            add(&mut self.by_reverse_dns, &name);
+        }
+        if let Some(executable) = entry.executable() {
+            add(&mut self.by_executable, &executable);
+        }
+        for name in &entry.names {
+            add(&mut self.by_name, name);
+        }
+
+        self.entries.push(entry);
+    }
 
-    let output = String::from_utf8_lossy(&path.stdout);
-    for line in output.lines() {
-        let contains_filename = line.to_lowercase().contains(&file_name.to_lowercase());
+    fn position(&self, map: &HashMap<String, usize>, key: &str) -> Option<usize> {
+        map.get(&key.to_lowercase()).copied()
+    }
 
-        if !contains_filename {
-            continue;
+    fn find(&self, query: &str) -> Option<usize> {
+        let query = query.trim();
+        if query.is_empty() {
+            return None;
         }
 
-        let Ok(content) = fs::read_to_string(line) else {
-            continue;
-        };
-        desktop_info = parse_desktop_file(&content);
+        let app_id = query.strip_suffix(".desktop").unwrap_or(query);
+        [
+            (&self.by_id, query.to_string()),
+            (&self.by_id, format!("{}.desktop", query)),
+            (&self.by_wm_class, query.to_string()),
+            (&self.by_flatpak_id, app_id.to_string()),
+            (&self.by_reverse_dns, query.to_string()),
+            (&self.by_executable, query.to_string()),
+            (&self.by_name, query.to_string()),
+        ]
+        .iter()
+        .find_map(|(map, key)| self.position(map, key))
     }
-    desktop_info
 }
 
-pub fn search_file(file_name: &str) -> Option<DesktopFile> {
-    use std::fs;
+#[derive(Debug, Clone)]
+pub struct DesktopIndex {
+    dirs: Arc<Vec<PathBuf>>,
+    index: Arc<RwLock<Index>>,
+}
 
-    let mut app_found = None;
-    for dir in desktop_dirs() {
-        let entries = match fs::read_dir(dir) {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+impl DesktopIndex {
+    pub fn new() -> Self {
+        Self::with_dirs(application_dirs())
+    }
 
-        for entry in entries.flatten() {
-            let path = entry.path().to_string_lossy().to_lowercase();
-            let file_name = file_name.trim().to_lowercase().replace(' ', "-");
+    pub fn with_dirs(dirs: Vec<PathBuf>) -> Self {
+        let index = Index::build(&dirs, current_locale().as_deref());
+        Self {
+            dirs: Arc::new(dirs),
+            index: Arc::new(RwLock::new(index)),
+        }
+    }
+
+    pub fn refresh(&self) {
+        let index = Index::build(&self.dirs, current_locale().as_deref());
+        *self.index.write() = index;
+    }
+
+    pub fn find(&self, query: &str) -> Option<DesktopEntry> {
+        let index = self.index.read();
+        index
+            .find(query.trim())
+            .map(|position| index.entries[position].clone())
+    }
+
+    pub fn find_id(&self, query: &str) -> Option<String> {
+        let index = self.index.read();
+        index
+            .find(query.trim())
+            .map(|position| index.entries[position].id.clone())
+    }
 
-            if !path.contains(&file_name) {
-                println!("Skipping file: {}", entry.path().to_string_lossy());
-                continue;
+    pub fn entries(&self) -> Vec<DesktopEntry> {
+        self.index
+            .read()
+            .entries
+            .iter()
+            .filter(|entry| !entry.no_display)
+            .cloned()
+            .collect()
+    }
+
+    pub fn watch(&self) {
+        let desktop_index = self.clone();
+        std::thread::spawn(move || {
+            let Some(watcher) = Inotify::new() else {
+                eprintln!("Failed to initialize inotify, desktop entries won't refresh");
+                return;
+            };
+
+            let watch_dirs = || {
+                for dir in desktop_index.dirs.iter() {
+                    watcher.add_dir(dir);
+                }
+            };
+
+            watch_dirs();
+            loop {
+                if !watcher.wait() {
+                    eprintln!("Stopped watching desktop entries");
+                    return;
+                }
+
+                std::thread::sleep(Duration::from_millis(REFRESH_DEBOUNCE_MS));
+                watcher.drain();
+                // Directories that showed up are watched before reading them,
+                // so entries written in between are not missed.
+                watch_dirs();
+                desktop_index.refresh();
             }
+        });
+    }
+}
 
-            let content = fs::read_to_string(entry.path()).ok();
-            println!("content: {:?}", content);
-            app_found = content.and_then(|c| parse_desktop_file(&c));
-            break;
-        }
+impl Default for DesktopIndex {
+    fn default() -> Self {
+        Self::new()
     }
-    app_found
 }
 
-fn parse_desktop_file(content: &str) -> Option<DesktopFile> {
-    let mut name = None;
-    let mut exec = None;
-    let mut icon = None;
+struct Inotify {
+    fd: i32,
+}
+
+impl Inotify {
+    fn new() -> Option<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        (fd >= 0).then_some(Self { fd })
+    }
 
-    for line in content.lines() {
-        if name.is_none()
-            && let Some(stripped) = line.strip_prefix("Name=")
-        {
-            name = Some(stripped.to_string());
+    // A directory that does not exist yet, like the flatpak exports before
+    // the first install, is waited for on its nearest existing parent. The
+    // mask is added to, that parent may be watched for another tree.
+    fn add_dir(&self, dir: &Path) {
+        if dir.is_dir() {
+            self.add_tree(dir);
+        } else if let Some(parent) = dir.ancestors().skip(1).find(|parent| parent.is_dir()) {
+            self.add_watch(
+                parent,
+                libc::IN_CREATE | libc::IN_MOVED_TO | libc::IN_MASK_ADD,
+            );
         }
+    }
 
-        if exec.is_none()
-            && let Some(stripped) = line.strip_prefix("Exec=")
-        {
-            let exec_clean = stripped
-                .split_whitespace()
-                .filter(|s| !s.starts_with('%'))
-                .collect::<Vec<_>>()
-                .join(" ");
-            exec = Some(exec_clean);
+    fn add_tree(&self, dir: &Path) {
+        let mask = libc::IN_CREATE
+            | libc::IN_DELETE
+            | libc::IN_CLOSE_WRITE
+            | libc::IN_MOVED_FROM
+            | libc::IN_MOVED_TO
+            | libc::IN_DELETE_SELF;
+        if !self.add_watch(dir, mask) {
+            return;
         }
-        if icon.is_none()
-            && let Some(stripped) = line.strip_prefix("Icon=")
-        {
-            icon = Some(stripped.to_string());
+
+        let Ok(children) = fs::read_dir(dir) else {
+            return;
+        };
+        for child in children.flatten() {
+            if child.file_type().is_ok_and(|kind| kind.is_dir()) {
+                self.add_tree(&child.path());
+            }
         }
+    }
+
+    fn add_watch(&self, path: &Path, mask: u32) -> bool {
+        let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), mask) >= 0 }
+    }
 
-        if name.is_some() && exec.is_some() && icon.is_some() {
-            break;
+    fn wait(&self) -> bool {
+        let mut buffer = [0u8; 4096];
+        let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        read > 0
+    }
+
+    fn drain(&self) {
+        let mut poll = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        while unsafe { libc::poll(&mut poll, 1, 0) } > 0 {
+            if !self.wait() {
+                return;
+            }
         }
     }
+}
 
-    Some(DesktopFile {
-        name: name?,
-        exec: exec?,
-        icon,
-    })
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        unsafe { libc::close(self.fd) };
+    }
 }
 
-fn command_exists(cmd: &str) -> bool {
-    if let Some(paths) = std::env::var_os("PATH") {
-        for path in std::env::split_paths(&paths) {
-            if path.join(cmd).is_file() {
-                return true;
-            }
+fn desktop_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_desktop_files(dir, &mut HashSet::new(), &mut files);
+    files
+}
+
+// Symlinked directories are followed once each, so a link back up the tree
+// cannot recurse forever.
+fn collect_desktop_files(dir: &Path, visited: &mut HashSet<PathBuf>, files: &mut Vec<PathBuf>) {
+    let Ok(canonical) = fs::canonicalize(dir) else {
+        return;
+    };
+    if !visited.insert(canonical) {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    let mut paths = entries.flatten().map(|e| e.path()).collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        if path.is_dir() {
+            collect_desktop_files(&path, visited, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            files.push(path);
         }
     }
-    false
 }
+
+// applications/kde4/okular.desktop has the id kde4-okular.desktop
+fn desktop_id(dir: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(dir).ok()?;
+    let parts = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+
+    Some(parts.join("-"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    struct Fixture {
+        root: tempfile::TempDir,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            Self {
+                root: tempfile::tempdir().unwrap(),
+            }
+        }
+
+        fn dir(&self, name: &str) -> PathBuf {
+            self.root.path().join(name).join("applications")
+        }
+
+        fn write(&self, dir: &str, file: &str, content: &str) {
+            let path = self.dir(dir).join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        fn index(&self, dirs: &[&str]) -> DesktopIndex {
+            DesktopIndex::with_dirs(dirs.iter().map(|dir| self.dir(dir)).collect())
+        }
+    }
+
+    fn entry(name: &str, exec: &str, extra: &str) -> String {
+        format!(
+            "[Desktop Entry]\nType=Application\nName={}\nExec={}\n{}",
+            name, exec, extra
+        )
+    }
+
     #[test]
     fn get_file_from_dir_if_exists() {
-        let expected_name = "Visual Studio Code".to_string();
-        let result = search_desktop_file("code");
-
-        let app_name = if let Some(app) = &result {
-            app.name.clone()
-        } else {
-            "".to_string()
-        };
+        let fixture = Fixture::new();
+        fixture.write(
+            "system",
+            "code.desktop",
+            &entry(
+                "Visual Studio Code",
+                "/usr/share/code/code %F",
+                "Icon=vscode",
+            ),
+        );
+        fixture.write(
+            "system",
+            "vscode-url-handler.desktop",
+            &entry("Code - URL Handler", "code --open-url %U", "NoDisplay=true"),
+        );
 
-        assert_eq!(app_name, expected_name);
-        assert!(result.is_some());
+        let result = fixture.index(&["system"]).find("code");
+        assert_eq!(
+            result.map(|app| app.name).as_deref(),
+            Some("Visual Studio Code")
+        );
     }
 
     #[test]
     fn shuld_can_return_some_for_flatpak_app() {
-        let expected_name = "Discord".to_string();
-        let result = search_desktop_file("discord");
-
-        let app_name = if let Some(app) = &result {
-            app.name.clone()
-        } else {
-            "".to_string()
-        };
+        let fixture = Fixture::new();
+        fixture.write(
+            "flatpak",
+            "com.discordapp.Discord.desktop",
+            &entry(
+                "Discord",
+                "/usr/bin/flatpak run --branch=stable com.discordapp.Discord",
+                "StartupWMClass=discord",
+            ),
+        );
 
-        assert_eq!(app_name, expected_name);
-        assert!(result.is_some());
+        let result = fixture.index(&["flatpak"]).find("discord");
+        assert_eq!(result.map(|app| app.name).as_deref(), Some("Discord"));
     }
 
     #[test]
     fn should_return_none_if_app_not_exist() {
-        let result = search_desktop_file("this-app-does-not-exist");
-        assert!(result.is_none());
+        let fixture = Fixture::new();
+        fixture.write(
+            "system",
+            "firefox.desktop",
+            &entry("Firefox", "firefox", ""),
+        );
+
+        assert!(
+            fixture
+                .index(&["system"])
+                .find("this-app-does-not-exist")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn earlier_dirs_take_precedence_and_hidden_entries_mask_later_ones() {
+        let fixture = Fixture::new();
+        fixture.write(
+            "home",
+            "firefox.desktop",
+            &entry("My Firefox", "firefox", ""),
+        );
+        fixture.write("home", "htop.desktop", "[Desktop Entry]\nHidden=true\n");
+        fixture.write(
+            "system",
+            "firefox.desktop",
+            &entry("Firefox", "firefox", ""),
+        );
+        fixture.write("system", "htop.desktop", &entry("Htop", "htop", ""));
+        fixture.write(
+            "system",
+            "kde4/okular.desktop",
+            &entry("Okular", "okular %U", "NoDisplay=true"),
+        );
+
+        let index = fixture.index(&["home", "system"]);
+        assert_eq!(
+            index.find("firefox").map(|app| app.name).as_deref(),
+            Some("My Firefox")
+        );
+        assert!(index.find("htop").is_none());
+
+        let okular = index.find("kde4-okular.desktop").unwrap();
+        assert!(okular.no_display);
+        assert_eq!(index.find("okular"), Some(okular));
+    }
+
+    #[test]
+    fn find_matches_wm_class_and_localized_names() {
+        let fixture = Fixture::new();
+        fixture.write(
+            "system",
+            "org.telegram.desktop.desktop",
+            &entry(
+                "Telegram Desktop",
+                "telegram-desktop -- %u",
+                "Name[ru]=Телеграм\nStartupWMClass=TelegramDesktop",
+            ),
+        );
+
+        let index = fixture.index(&["system"]);
+        let id = |app: Option<DesktopEntry>| app.map(|app| app.id);
+        let expected = Some("org.telegram.desktop.desktop".to_string());
+        assert_eq!(id(index.find("telegramdesktop")), expected);
+        assert_eq!(id(index.find("telegram-desktop")), expected);
+        assert_eq!(id(index.find("Телеграм")), expected);
+        assert_eq!(id(index.find("org.telegram.desktop")), expected);
+    }
+
+    #[test]
+    fn blank_queries_match_nothing() {
+        let fixture = Fixture::new();
+        fixture.write("system", "nameless.desktop", &entry("", "", ""));
+
+        let index = fixture.index(&["system"]);
+        assert!(index.find("").is_none());
+        assert!(index.find("   ").is_none());
+        assert!(index.find("nameless").is_some());
+    }
+
+    #[test]
+    fn symlinked_directories_are_read_once() {
+        let fixture = Fixture::new();
+        fixture.write(
+            "system",
+            "kde/okular.desktop",
+            &entry("Okular", "okular", ""),
+        );
+        std::os::unix::fs::symlink(
+            fixture.dir("system"),
+            fixture.dir("system").join("kde/loop"),
+        )
+        .unwrap();
+
+        let index = fixture.index(&["system"]);
+        assert_eq!(index.entries().len(), 1);
+        assert!(index.find("kde-okular.desktop").is_some());
+    }
+
+    #[test]
+    fn refresh_picks_up_new_entries() {
+        let fixture = Fixture::new();
+        fixture.write(
+            "system",
+            "firefox.desktop",
+            &entry("Firefox", "firefox", ""),
+        );
+        let index = fixture.index(&["system"]);
+        assert!(index.find("kitty").is_none());
+
+        fixture.write("system", "kitty.desktop", &entry("kitty", "kitty", ""));
+        index.refresh();
+        assert!(index.find("kitty").is_some());
     }
 }
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    io,
+    os::fd::{AsRawFd, FromRawFd, OwnedFd},
+};
+
+// Kernel uevents are multicast on group 1; udev rebroadcasts its own on 2.
+const KERNEL_GROUP: u32 = 1;
+const BUFFER_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Uevent {
+    pub action: String,
+    pub devpath: String,
+    pub vars: HashMap<String, String>,
+}
+
+impl Uevent {
+    // Messages look like "change@/devices/...\0ACTION=change\0SUBSYSTEM=...\0".
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        let mut fields = message
+            .split(|byte| *byte == 0)
+            .filter(|field| !field.is_empty())
+            .map(String::from_utf8_lossy);
+        let header = fields.next()?;
+        let (action, devpath) = header.split_once('@')?;
+
+        Some(Self {
+            action: action.to_string(),
+            devpath: devpath.to_string(),
+            vars: fields
+                .filter_map(|field| {
+                    field
+                        .split_once('=')
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                })
+                .collect(),
+        })
+    }
+
+    pub fn subsystem(&self) -> Option<&str> {
+        self.vars.get("SUBSYSTEM").map(String::as_str)
+    }
+}
+
+// Sends every uevent of `subsystem` until the receiver is dropped. The socket
+// is read on its own thread since recv blocks.
+pub fn watch(subsystem: &'static str) -> io::Result<async_channel::Receiver<Uevent>> {
+    let socket = open_socket()?;
+    let (sender, receiver) = async_channel::unbounded();
+
+    std::thread::spawn(move || {
+        let mut buffer = vec![0u8; BUFFER_SIZE];
+        loop {
+            let read = unsafe {
+                libc::recv(
+                    socket.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                    0,
+                )
+            };
+            if read < 0 {
+                let error = io::Error::last_os_error();
+                if error.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                eprintln!("Failed to read uevents: {}", error);
+                return;
+            }
+
+            let Some(event) = Uevent::parse(&buffer[..read as usize]) else {
+                continue;
+            };
+            if event.subsystem() == Some(subsystem) && sender.send_blocking(event).is_err() {
+                return;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+fn open_socket() -> io::Result<OwnedFd> {
+    let fd = unsafe {
+        libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+            libc::NETLINK_KOBJECT_UEVENT,
+        )
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let socket = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+    address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+    address.nl_groups = KERNEL_GROUP;
+    let bound = unsafe {
+        libc::bind(
+            socket.as_raw_fd(),
+            (&address as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        )
+    };
+    if bound < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_the_header_and_variables() {
+        let event = Uevent::parse(
+            b"change@/devices/LNXSYSTM:00/PNP0C0A:00/power_supply/BAT0\0ACTION=change\0SUBSYSTEM=power_supply\0POWER_SUPPLY_CAPACITY=87\0",
+        )
+        .unwrap();
+
+        assert_eq!(event.action, "change");
+        assert!(event.devpath.ends_with("/BAT0"));
+        assert_eq!(event.subsystem(), Some("power_supply"));
+        assert_eq!(event.vars["POWER_SUPPLY_CAPACITY"], "87");
+
+        assert_eq!(Uevent::parse(b"libudev\0"), None);
+    }
+}